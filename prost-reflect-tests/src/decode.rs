@@ -1,18 +1,27 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
     fmt::Debug,
+    hash::Hasher,
+    io::{self, Read},
     iter::FromIterator,
+    time::{Duration, SystemTime},
 };
 
 use proptest::{prelude::*, test_runner::TestCaseError};
 use prost::{bytes::Bytes, encoding::WireType, Message};
-use prost_reflect::{DynamicMessage, MapKey, ReflectMessage, Value};
+use prost_reflect::{
+    DecodeOptions, DescriptorPool, DynamicMessage, DynamicMessageView, EncodeOptions,
+    FieldDescriptor, FieldOrExtension, MapKey, ReflectMessage, SetFieldError, Value, Visitor,
+    VisitorMut,
+};
 use prost_types::FileDescriptorSet;
 
 use crate::{
     proto::{
-        contains_group, message_with_oneof, ComplexType, ContainsGroup, MessageWithOneof,
-        ScalarArrays, Scalars, WellKnownTypes,
+        contains_group, inventory, message_with_oneof, ComplexType, ContainsGroup, Inventory,
+        MessageWithOneof, NestedScalarArrays, Point, PointWithTags, RemappedPoint, ScalarArrays,
+        Scalars, WellKnownTypes,
     },
     test_file_descriptor,
 };
@@ -85,6 +94,22 @@ fn try_set_field_validates_type() {
             .to_string(),
         "expected a value of type 'double', but found '5'"
     );
+
+    let mut dynamic = ComplexType::default().transcode_to_dynamic();
+    assert_eq!(
+        dynamic
+            .try_set_field_by_name("string_map", Value::Bool(true))
+            .unwrap_err()
+            .to_string(),
+        "expected a value of type 'map<string, test.Scalars>', but found 'true'"
+    );
+    assert_eq!(
+        dynamic
+            .try_set_field_by_name("my_enum", Value::Bool(true))
+            .unwrap_err()
+            .to_string(),
+        "expected a value of type 'repeated test.ComplexType.MyEnum', but found 'true'"
+    );
 }
 
 #[test]
@@ -509,6 +534,54 @@ fn set_oneof() {
     assert!(!dynamic.has_field_by_name("oneof_field_1"));
 }
 
+#[test]
+fn get_or_insert_message() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.TreeNode")
+        .unwrap();
+    let parent = desc.get_field_by_name("parent").unwrap();
+    let location = desc.get_field_by_name("location").unwrap();
+
+    let mut root = DynamicMessage::new(desc.clone());
+    root.set_field_by_name("name", Value::String("leaf".to_owned()));
+    let grandparent = root
+        .get_or_insert_message(&parent)
+        .unwrap()
+        .get_or_insert_message(&parent)
+        .unwrap();
+    grandparent.set_field_by_name("name", Value::String("root".to_owned()));
+    grandparent
+        .get_or_insert_message(&location)
+        .unwrap()
+        .set_field_by_name("latitude", Value::I32(5));
+
+    // Existing messages are returned rather than replaced.
+    root.get_or_insert_message(&parent)
+        .unwrap()
+        .set_field_by_name("name", Value::String("middle".to_owned()));
+
+    assert_eq!(
+        root.to_text_format(),
+        r#"name:"leaf",parent{name:"middle",parent{name:"root",location{latitude:5}}}"#
+    );
+
+    let children = desc.get_field_by_name("children").unwrap();
+    let name = desc.get_field_by_name("name").unwrap();
+    assert!(root.get_or_insert_message(&children).is_none());
+    assert!(root.get_or_insert_message(&name).is_none());
+    assert!(!root.has_field(&children));
+
+    let mut with_oneof = DynamicMessage::new(MessageWithOneof::default().descriptor());
+    with_oneof.set_field_by_name("oneof_field_1", Value::String("hello".to_owned()));
+    let oneof_value = with_oneof
+        .descriptor()
+        .get_field_by_name("oneof_value_null")
+        .unwrap();
+    assert!(with_oneof.get_or_insert_message(&oneof_value).is_some());
+    assert!(with_oneof.has_field(&oneof_value));
+    assert!(!with_oneof.has_field_by_name("oneof_field_1"));
+}
+
 #[test]
 fn set_oneof_to_default() {
     let mut dynamic = DynamicMessage::new(
@@ -780,71 +853,2168 @@ fn unknown_fields_are_roundtripped() {
 }
 
 #[test]
-fn proto3_default_fields_are_not_encoded() {
+fn wire_order_is_preserved() {
+    const BYTES: &[u8] =
+        b"\x1a\x07\x18\x05\xa0\x01\x01\x68\x01\xf0\x01\x07\x22\x02\x01\x03\xf0\x01\x08\x28\x03";
+
+    let desc = ComplexType::default().descriptor();
+    let message = DynamicMessage::decode_preserving_wire_order(desc.clone(), BYTES).unwrap();
+    assert_eq!(message.encoded_len(), BYTES.len());
+    assert_eq!(message.encode_to_vec(), BYTES);
+
+    let reordered = DynamicMessage::decode(desc, BYTES).unwrap();
+    assert_ne!(reordered.encode_to_vec(), BYTES);
+    assert_eq!(message, reordered);
+}
+
+#[test]
+fn wire_order_is_preserved_for_map_entries() {
+    // string_map entries "c", "a", "e", then optional_enum, then entries "b" and "d".
+    const BYTES: &[u8] = b"\x0a\x05\x0a\x01c\x12\x00\x0a\x05\x0a\x01a\x12\x00\x0a\x05\x0a\x01e\x12\x00\x28\x01\x0a\x05\x0a\x01b\x12\x00\x0a\x05\x0a\x01d\x12\x00";
+
+    let desc = ComplexType::default().descriptor();
+    let message = DynamicMessage::decode_preserving_wire_order(desc, BYTES).unwrap();
+    assert_eq!(message.encoded_len(), BYTES.len());
+    assert_eq!(message.encode_to_vec(), BYTES);
+}
+
+#[test]
+fn wire_order_is_preserved_for_packed_records_of_unpacked_fields() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.UnpackedScalarArray")
+        .unwrap();
+    // A packed record with 1.0 and 2.0, an unpacked record with 3.0, then a packed record with 4.0.
+    const BYTES: &[u8] = b"\xd2\x02\x10\x00\x00\x00\x00\x00\x00\xf0\x3f\x00\x00\x00\x00\x00\x00\x00\x40\xd1\x02\x00\x00\x00\x00\x00\x00\x08\x40\xd2\x02\x08\x00\x00\x00\x00\x00\x00\x10\x40";
+
+    let mut message = DynamicMessage::decode_preserving_wire_order(desc, BYTES).unwrap();
+    assert_eq!(message.encoded_len(), BYTES.len());
+    assert_eq!(message.encode_to_vec(), BYTES);
+
+    // Elements added after decoding are written after the decoded records, using the field's own encoding.
+    message
+        .get_field_by_name_mut("unpacked_double")
+        .unwrap()
+        .as_list_mut()
+        .unwrap()
+        .push(Value::F64(5.0));
+    let mut expected = BYTES.to_vec();
+    expected.extend_from_slice(b"\xd1\x02\x00\x00\x00\x00\x00\x00\x14\x40");
+    assert_eq!(message.encoded_len(), expected.len());
+    assert_eq!(message.encode_to_vec(), expected);
+}
+
+#[test]
+fn wire_order_is_preserved_after_modification() {
+    const BYTES: &[u8] = b"\xf0\x01\x07\x28\x03\xf0\x01\x08";
+
+    let desc = ComplexType::default().descriptor();
+    let mut message = DynamicMessage::decode_preserving_wire_order(desc, BYTES).unwrap();
+    message.set_field_by_name("my_enum", Value::List(vec![Value::EnumNumber(1)]));
+    message.clear_field_by_name("optional_enum");
+    assert_eq!(
+        message.encode_to_vec(),
+        b"\xf0\x01\x07\xf0\x01\x08\x22\x01\x01"
+    );
+}
+
+#[test]
+fn find_missing_required_fields() {
+    let mut message = DynamicMessage::new(ContainsGroup::default().descriptor());
+    assert!(message.is_initialized());
+
+    message.get_field_by_name_mut("requiredgroup").unwrap();
+    message
+        .get_field_by_name_mut("optionalgroup")
+        .unwrap()
+        .as_message_mut()
+        .unwrap()
+        .set_field_by_name("c", Value::String("c".to_owned()));
+    let repeated_group_desc = message
+        .descriptor()
+        .get_field_by_name("repeatedgroup")
+        .unwrap()
+        .kind()
+        .as_message()
+        .unwrap()
+        .clone();
+    let mut complete = DynamicMessage::new(repeated_group_desc.clone());
+    complete.set_field_by_name("e", Value::String("e".to_owned()));
+    message.set_field_by_name(
+        "repeatedgroup",
+        Value::List(vec![
+            Value::Message(complete),
+            Value::Message(DynamicMessage::new(repeated_group_desc)),
+        ]),
+    );
+
+    assert_eq!(
+        message.find_missing_required_fields(),
+        vec![
+            "requiredgroup.a".to_owned(),
+            "repeatedgroup[1].e".to_owned()
+        ]
+    );
+    assert!(!message.is_initialized());
+}
+
+#[test]
+fn find_in_repeated() {
+    let message = Inventory {
+        items: vec![
+            inventory::Item {
+                id: 1,
+                name: "foo".to_owned(),
+            },
+            inventory::Item {
+                id: 2,
+                name: "bar".to_owned(),
+            },
+        ],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let items = message.descriptor().get_field_by_name("items").unwrap();
+    let item_desc = items.kind().as_message().unwrap().clone();
+    let id = item_desc.get_field_by_name("id").unwrap();
+
+    let item = message
+        .find_in_repeated(&items, &id, &Value::I32(2))
+        .unwrap();
+    assert_eq!(
+        item.get_field_by_name("name").unwrap().as_str(),
+        Some("bar")
+    );
+    assert!(message
+        .find_in_repeated(&items, &id, &Value::I32(3))
+        .is_none());
+
+    let name = item_desc.get_field_by_name("name").unwrap();
+    assert!(message
+        .find_in_repeated(&name, &id, &Value::I32(1))
+        .is_none());
+    assert!(message
+        .find_in_repeated(&items, &items, &Value::I32(1))
+        .is_none());
+}
+
+#[test]
+fn mutate_nested_values_in_place() {
+    let mut message = DynamicMessage::new(ComplexType::default().descriptor());
+    let nested = message.descriptor().get_field_by_name("nested").unwrap();
+    let int_map = message.descriptor().get_field_by_name("int_map").unwrap();
+    let my_enum = message.descriptor().get_field_by_name("my_enum").unwrap();
+    assert!(!message.has_field(&nested));
+
+    message
+        .get_field_mut(&nested)
+        .as_message_mut()
+        .unwrap()
+        .set_field_by_name("int32", Value::I32(5));
+    message
+        .get_field_mut(&my_enum)
+        .as_list_mut()
+        .unwrap()
+        .push(Value::EnumNumber(1));
+    message
+        .get_field_mut(&int_map)
+        .as_map_mut()
+        .unwrap()
+        .insert(
+            MapKey::I32(1),
+            Value::Message(Scalars::default().transcode_to_dynamic()),
+        );
+
+    assert!(message.has_field(&nested));
+    assert!(message.has_field(&my_enum));
+    assert!(message.has_field(&int_map));
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 5,
+                ..Default::default()
+            }),
+            my_enum: vec![1],
+            int_map: HashMap::from_iter([(1, Scalars::default())]),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn enum_values_used() {
     let message = ComplexType {
-        optional_enum: 0,
+        my_enum: vec![1, 3, 1],
+        optional_enum: -4,
         ..Default::default()
     }
     .transcode_to_dynamic();
+    let enum_desc = message
+        .descriptor()
+        .get_field_by_name("optional_enum")
+        .unwrap()
+        .kind()
+        .as_enum()
+        .unwrap()
+        .clone();
 
-    assert!(message.encode_to_vec().is_empty());
+    assert_eq!(
+        message.enum_values_used(),
+        HashMap::from_iter([(enum_desc, HashSet::from_iter([1, 3, -4]))])
+    );
 }
 
 #[test]
-fn oneof_set_multiple_values() {
-    let mut value = Vec::new();
-    MessageWithOneof {
-        test_oneof: Some(message_with_oneof::TestOneof::OneofField1(
-            "hello".to_owned(),
-        )),
+fn decode_base64_and_hex() {
+    let message = Scalars {
+        int32: 150,
+        string: "hi?>".to_owned(),
+        ..Default::default()
+    };
+    let desc = message.descriptor();
+    let bytes = message.encode_to_vec();
+    assert_eq!(bytes, b"\x18\x96\x01\x72\x04hi?>");
+
+    for encoded in ["GJYBcgRoaT8+", "GJYBcgRoaT8-", "GJYBcgRoaT8+\n"] {
+        let decoded = DynamicMessage::decode_base64(desc.clone(), encoded).unwrap();
+        assert_eq!(decoded.transcode_to::<Scalars>().unwrap(), message);
     }
-    .encode(&mut value)
-    .unwrap();
-    MessageWithOneof {
-        test_oneof: Some(message_with_oneof::TestOneof::OneofField2(5)),
+
+    // The padding of a partial final group is optional.
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    };
+    assert_eq!(point.encode_to_vec(), b"\x08\x01\x10\x02");
+    for encoded in ["CAEQAg==", "CAEQAg"] {
+        let decoded = DynamicMessage::decode_base64(point.descriptor(), encoded).unwrap();
+        assert_eq!(decoded.transcode_to::<Point>().unwrap(), point);
     }
-    .encode(&mut value)
-    .unwrap();
 
-    let dynamic_message = DynamicMessage::decode(
-        test_file_descriptor()
-            .get_message_by_name("test.MessageWithOneof")
+    let err = DynamicMessage::decode_base64(desc.clone(), "GJY*").unwrap_err();
+    assert!(err.to_string().contains("invalid base64"));
+
+    for encoded in ["189601", " 189601\n"] {
+        let decoded = DynamicMessage::decode_hex(desc.clone(), encoded).unwrap();
+        assert_eq!(
+            decoded.get_field_by_name("int32").unwrap().as_ref(),
+            &Value::I32(150)
+        );
+    }
+    assert!(DynamicMessage::decode_hex(desc.clone(), "18 96 01").is_err());
+    assert_eq!(
+        DynamicMessage::decode_hex(point.descriptor(), "0801100A")
+            .unwrap()
+            .transcode_to::<Point>()
             .unwrap(),
-        value.as_ref(),
+        Point {
+            latitude: 1,
+            longitude: 10,
+        }
+    );
+
+    let err = DynamicMessage::decode_hex(desc.clone(), "18960").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("invalid hex: odd number of digits"));
+    let err = DynamicMessage::decode_hex(desc.clone(), "18+601").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("invalid hex: unexpected byte 0x2b at offset 2"));
+    assert!(DynamicMessage::decode_hex(desc, "1896").is_err());
+}
+
+#[test]
+fn decode_max_field_size() {
+    let bytes = Scalars {
+        string: "hello".to_owned(),
+        bytes: vec![0; 100],
+        ..Default::default()
+    }
+    .encode_to_vec();
+
+    let options = DecodeOptions::new().max_field_size(10);
+    let err = DynamicMessage::decode_with_options(
+        Scalars::default().descriptor(),
+        bytes.as_slice(),
+        &options,
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("field 15 exceeds the maximum field size of 10 bytes"));
+
+    let options = DecodeOptions::new().max_field_size(100);
+    let message = DynamicMessage::decode_with_options(
+        Scalars::default().descriptor(),
+        bytes.as_slice(),
+        &options,
     )
     .unwrap();
+    assert_eq!(message.encode_to_vec(), bytes);
+}
 
-    assert!(!dynamic_message.has_field_by_name("oneof_field_1"));
-    assert!(dynamic_message.has_field_by_name("oneof_field_2"));
+#[test]
+fn decode_max_recursion_depth() {
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut bytes = b"\x0a\x04leaf".to_vec();
+        for _ in 0..depth {
+            let mut parent = vec![0x1a];
+            prost::encoding::encode_varint(bytes.len() as u64, &mut parent);
+            parent.extend(bytes);
+            bytes = parent;
+        }
+        bytes
+    }
 
-    assert_eq!(dynamic_message.encode_to_vec().as_slice(), b"\x10\x05");
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.TreeNode")
+        .unwrap();
+
+    let options = DecodeOptions::new().max_recursion_depth(5);
+    let message =
+        DynamicMessage::decode_with_options(desc.clone(), nested(5).as_slice(), &options).unwrap();
+    assert_eq!(message.encode_to_vec(), nested(5));
+    let err = DynamicMessage::decode_with_options(desc.clone(), nested(6).as_slice(), &options)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("message exceeds the maximum recursion depth of 5"));
+
+    // Map entries and nested messages inside them are counted.
+    let mut bytes = b"\x2a".to_vec();
+    let mut wrapper = b"\x12".to_vec();
+    let complex = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                int32: 1,
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    prost::encoding::encode_varint(complex.len() as u64, &mut wrapper);
+    wrapper.extend(complex);
+    prost::encoding::encode_varint(wrapper.len() as u64, &mut bytes);
+    bytes.extend(wrapper);
+    let options = DecodeOptions::new().max_recursion_depth(4);
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).is_ok());
+    let options = DecodeOptions::new().max_recursion_depth(3);
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).is_err());
+
+    // By default, only prost's recursion limit applies.
+    let options = DecodeOptions::new();
+    assert!(
+        DynamicMessage::decode_with_options(desc.clone(), nested(100).as_slice(), &options).is_ok()
+    );
+    let err = DynamicMessage::decode_with_options(desc.clone(), nested(1000).as_slice(), &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("recursion limit reached"));
+    assert!(DynamicMessage::decode(desc, nested(1000).as_slice()).is_err());
 }
 
 #[test]
-fn roundtrip_extension() {
-    let message_desc = test_file_descriptor()
-        .get_message_by_name("my.package2.MyMessage")
-        .unwrap();
+fn decode_max_message_size() {
+    let bytes = Scalars {
+        string: "hello".to_owned(),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    assert_eq!(bytes.len(), 7);
 
-    let extension_desc = message_desc.get_extension(113).unwrap();
+    let options = DecodeOptions::new().max_message_size(7);
+    assert!(DynamicMessage::decode_with_options(
+        Scalars::default().descriptor(),
+        bytes.as_slice(),
+        &options,
+    )
+    .is_ok());
+
+    let options = DecodeOptions::new().max_message_size(6);
+    let err = DynamicMessage::decode_with_options(
+        Scalars::default().descriptor(),
+        bytes.as_slice(),
+        &options,
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("message of 7 bytes exceeds the maximum message size of 6 bytes"));
+
+    // The size is checked before the message is copied out of the buffer.
+    use prost::bytes::Buf;
+
+    struct NoCopyBuf<'a>(&'a [u8]);
+
+    impl Buf for NoCopyBuf<'_> {
+        fn remaining(&self) -> usize {
+            self.0.remaining()
+        }
+
+        fn chunk(&self) -> &[u8] {
+            self.0.chunk()
+        }
+
+        fn advance(&mut self, cnt: usize) {
+            self.0.advance(cnt)
+        }
+
+        fn copy_to_bytes(&mut self, _: usize) -> Bytes {
+            panic!("message should not be copied")
+        }
+    }
+
+    assert!(DynamicMessage::decode_with_options(
+        Scalars::default().descriptor(),
+        NoCopyBuf(&bytes),
+        &options,
+    )
+    .is_err());
+}
+
+#[test]
+fn decode_deny_unknown_fields() {
+    let desc = ComplexType::default().descriptor();
+    let options = DecodeOptions::new().deny_unknown_fields(true);
+
+    let bytes = ComplexType {
+        string_map: HashMap::from_iter([("key".to_owned(), Scalars::default())]),
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .encode_to_vec();
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).is_ok());
+
+    // Field 100 of the nested 'Scalars' message is not defined.
+    let bytes = b"\x1a\x03\xa0\x06\x01".as_ref();
+    let message = DynamicMessage::decode(desc.clone(), bytes).unwrap();
     assert_eq!(
-        message_desc.get_extension_by_json_name(extension_desc.json_name()),
-        Some(extension_desc.clone())
+        message
+            .get_field_by_name("nested")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .unknown_fields()
+            .count(),
+        1
     );
 
-    let mut dynamic_message = DynamicMessage::new(message_desc.clone());
-    dynamic_message.set_extension(&extension_desc, Value::F64(42.0));
-    let bytes = dynamic_message.encode_to_vec();
+    let err = DynamicMessage::decode_with_options(desc.clone(), bytes, &options).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unknown field 100 with wire type Varint in message 'test.Scalars'"));
 
-    let roundtripped_dynamic_message =
-        DynamicMessage::decode(message_desc, bytes.as_ref()).unwrap();
-    assert!(roundtripped_dynamic_message.has_extension(&extension_desc));
+    let bytes = b"\x1a\x00\xa1\x06\x00\x00\x00\x00\x00\x00\x00\x00".as_ref();
+    let err = DynamicMessage::decode_with_options(desc, bytes, &options).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unknown field 100 with wire type SixtyFourBit in message 'test.ComplexType'"));
+}
+
+#[test]
+fn rebind_to_pool() {
+    let message = ComplexType {
+        int_map: HashMap::from_iter([(
+            1,
+            Scalars {
+                string: "hello".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let new_pool = DescriptorPool::decode(crate::DESCRIPTOR_POOL_BYTES).unwrap();
+    let rebound = message.rebind_to_pool(&new_pool).unwrap();
+    assert_eq!(rebound.descriptor().parent_pool(), &new_pool);
+    assert_eq!(rebound.encode_to_vec(), message.encode_to_vec());
+    let nested = rebound.get_field_by_name("nested").unwrap();
     assert_eq!(
-        roundtripped_dynamic_message
-            .get_extension(&extension_desc)
+        nested.as_message().unwrap().descriptor().parent_pool(),
+        &new_pool
+    );
+    assert_eq!(
+        nested
+            .as_message()
+            .unwrap()
+            .get_field_by_name("int32")
+            .unwrap()
             .as_ref(),
-        &Value::F64(42.0)
+        &Value::I32(5)
+    );
+
+    let incompatible_pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![prost_types::FileDescriptorProto {
+            name: Some("point.proto".to_owned()),
+            package: Some("test".to_owned()),
+            message_type: vec![prost_types::DescriptorProto {
+                name: Some("Point".to_owned()),
+                field: vec![prost_types::FieldDescriptorProto {
+                    name: Some("latitude".to_owned()),
+                    number: Some(1),
+                    label: Some(prost_types::field_descriptor_proto::Label::Optional as i32),
+                    r#type: Some(prost_types::field_descriptor_proto::Type::String as i32),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    })
+    .unwrap();
+
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic();
+    let err = point.rebind_to_pool(&incompatible_pool).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("message is not compatible with the definition of 'test.Point' in the new pool"));
+
+    // Field 2 is not defined in the incompatible pool, but is known in the new pool.
+    let unknown_point = DynamicMessage::decode(
+        incompatible_pool.get_message_by_name("test.Point").unwrap(),
+        b"\x10\x02".as_ref(),
+    )
+    .unwrap();
+    assert_eq!(unknown_point.unknown_fields().count(), 1);
+    let rebound = unknown_point.rebind_to_pool(&new_pool).unwrap();
+    assert_eq!(rebound.unknown_fields().count(), 0);
+    assert_eq!(
+        rebound.get_field_by_name("longitude").unwrap().as_ref(),
+        &Value::I32(2)
+    );
+
+    let err = ContainsGroup::default()
+        .transcode_to_dynamic()
+        .rebind_to_pool(&incompatible_pool)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("message 'test2.ContainsGroup' not found"));
+}
+
+#[test]
+fn pack_and_unpack_any() {
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    };
+    let message = point.transcode_to_dynamic();
+
+    let any = message.pack_into_any(Some("example.com"));
+    assert_eq!(
+        any.transcode_to::<prost_types::Any>().unwrap(),
+        prost_types::Any {
+            type_url: "example.com/test.Point".to_owned(),
+            value: point.encode_to_vec(),
+        }
+    );
+    assert_eq!(any.unpack_any(&test_file_descriptor()).unwrap(), message);
+
+    let err = message.unpack_any(&test_file_descriptor()).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("expected a message of type 'google.protobuf.Any'"));
+
+    let mut any = message.pack_into_any(None);
+    any.set_field_by_name(
+        "type_url",
+        Value::String("type.googleapis.com/test.Missing".to_owned()),
+    );
+    let err = any.unpack_any(&test_file_descriptor()).unwrap_err();
+    assert!(err.to_string().contains("message 'test.Missing' not found"));
+}
+
+#[test]
+fn remap_to() {
+    let message = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic();
+
+    let remapped = message.remap_to(RemappedPoint::default().descriptor());
+    assert_eq!(
+        remapped.transcode_to::<RemappedPoint>().unwrap(),
+        RemappedPoint {
+            latitude: 1,
+            longitude: String::new(),
+        }
+    );
+}
+
+#[test]
+fn decode_stream() {
+    let points = [
+        Point {
+            latitude: 1,
+            longitude: 2,
+        },
+        Point::default(),
+        Point {
+            latitude: -3,
+            longitude: 4,
+        },
+    ];
+
+    let path = std::env::temp_dir().join(format!("decode_stream_{}.bin", std::process::id()));
+    let mut bytes = Vec::new();
+    for point in &points {
+        point.encode_length_delimited(&mut bytes).unwrap();
+    }
+    std::fs::write(&path, &bytes).unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let messages = DynamicMessage::decode_stream(Point::default().descriptor(), file)
+        .map(|message| message.unwrap().transcode_to::<Point>().unwrap())
+        .collect::<Vec<_>>();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(messages, points);
+
+    let mut results =
+        DynamicMessage::decode_stream(Point::default().descriptor(), &bytes[..bytes.len() - 1]);
+    assert!(results.next().unwrap().is_ok());
+    assert!(results.next().unwrap().is_ok());
+    assert_eq!(
+        results.next().unwrap().unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(results.next().is_none());
+
+    let mut results =
+        DynamicMessage::decode_stream(Point::default().descriptor(), b"\x02\x0a\x01".as_ref());
+    assert_eq!(
+        results.next().unwrap().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    // The length prefix overflows a u64.
+    let mut results = DynamicMessage::decode_stream(
+        Point::default().descriptor(),
+        b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02".as_ref(),
+    );
+    assert_eq!(
+        results.next().unwrap().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    // Interrupted reads are retried, and other errors are returned unchanged.
+    struct FlakyReader<'a> {
+        bytes: &'a [u8],
+        calls: usize,
+    }
+
+    impl Read for FlakyReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.calls += 1;
+            match self.calls % 3 {
+                1 => Err(io::ErrorKind::Interrupted.into()),
+                _ if self.bytes.is_empty() => Err(io::ErrorKind::PermissionDenied.into()),
+                _ => self.bytes.read(&mut buf[..1]),
+            }
+        }
+    }
+
+    let mut results = DynamicMessage::decode_stream(
+        Point::default().descriptor(),
+        FlakyReader {
+            bytes: &bytes,
+            calls: 0,
+        },
+    );
+    for point in &points {
+        assert_eq!(
+            &results
+                .next()
+                .unwrap()
+                .unwrap()
+                .transcode_to::<Point>()
+                .unwrap(),
+            point
+        );
+    }
+    assert_eq!(
+        results.next().unwrap().unwrap_err().kind(),
+        io::ErrorKind::PermissionDenied
+    );
+    assert!(results.next().is_none());
+}
+
+#[test]
+fn decode_from_reader() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([("hello".to_owned(), Scalars::default())]),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+    let bytes = message.encode_to_vec();
+
+    let path = std::env::temp_dir().join(format!("decode_from_reader_{}.bin", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    let dynamic_message =
+        DynamicMessage::decode_from_reader(ComplexType::default().descriptor(), file).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        dynamic_message.transcode_to::<ComplexType>().unwrap(),
+        message
+    );
+
+    let err = DynamicMessage::decode_from_reader(
+        ComplexType::default().descriptor(),
+        &bytes[..bytes.len() - 1],
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::PermissionDenied.into())
+        }
+    }
+
+    let err =
+        DynamicMessage::decode_from_reader(ComplexType::default().descriptor(), FailingReader)
+            .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+}
+
+#[test]
+fn message_to_flat_map() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                int32: 1,
+                string: "x".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        int_map: HashMap::from_iter([(5, Scalars::default())]),
+        nested: Some(Scalars {
+            double: 1.5,
+            r#bool: true,
+            bytes: b"\x01".to_vec(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, 7],
+        optional_enum: 3,
+    }
+    .transcode_to_dynamic();
+
+    let map = message.to_flat_map("/");
+    assert_eq!(
+        map.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("my_enum[0]".to_owned(), "FOO".to_owned()),
+            ("my_enum[1]".to_owned(), "BAR".to_owned()),
+            ("my_enum[2]".to_owned(), "7".to_owned()),
+            ("nested/bool".to_owned(), "true".to_owned()),
+            ("nested/bytes".to_owned(), "\"\\001\"".to_owned()),
+            ("nested/double".to_owned(), "1.5".to_owned()),
+            ("optional_enum".to_owned(), "BAR".to_owned()),
+            ("string_map[\"a\"]/int32".to_owned(), "1".to_owned()),
+            ("string_map[\"a\"]/string".to_owned(), "\"x\"".to_owned()),
+        ]
+    );
+
+    assert!(DynamicMessage::new(message.descriptor())
+        .to_flat_map(".")
+        .is_empty());
+}
+
+#[test]
+fn message_summary() {
+    let message = Scalars {
+        double: 1.5,
+        int32: 3,
+        string: "hello".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        message.summary(100),
+        "Scalars{double=1.5, int32=3, string=\"hello\"}"
+    );
+    for max_len in [0, 2, 10, 20] {
+        let summary = message.summary(max_len);
+        assert_eq!(summary.chars().count(), max_len);
+        assert!(summary.ends_with(&"..."[..max_len.min(3)]));
+    }
+    assert_eq!(message.summary(20), "Scalars{double=1....");
+    assert_eq!(message.summary(10), "Scalars...");
+
+    let empty = Scalars::default().transcode_to_dynamic();
+    assert_eq!(empty.summary(100), "Scalars{}");
+}
+
+#[test]
+fn decode_map_entry_missing_key_or_value() {
+    let desc = ComplexType::default().descriptor();
+
+    // A string_map entry with only the key, and an int_map entry with only the value.
+    let bytes = b"\x0a\x03\x0a\x01a\x12\x04\x12\x02\x18\x05";
+    let message = DynamicMessage::decode(desc, bytes.as_ref()).unwrap();
+
+    assert_eq!(
+        message.get_field_by_name("string_map").unwrap().as_ref(),
+        &Value::Map(HashMap::from_iter([(
+            MapKey::String("a".to_owned()),
+            Value::Message(Scalars::default().transcode_to_dynamic()),
+        )]))
+    );
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap().int_map,
+        HashMap::from_iter([(
+            0,
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            }
+        )])
+    );
+}
+
+#[test]
+fn encoded_len_includes_unknown_fields() {
+    let mut message = ComplexType {
+        string_map: HashMap::from_iter([("a".to_owned(), Scalars::default())]),
+        nested: Some(Scalars {
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert_eq!(message.encoded_len(), message.encode_to_vec().len());
+
+    message
+        .merge(b"\xa0\x06\x01\xaa\x06\x02hi".as_ref())
+        .unwrap();
+    assert_eq!(message.unknown_fields().count(), 2);
+    assert_eq!(message.encoded_len(), message.encode_to_vec().len());
+}
+
+#[test]
+fn map_for_field() {
+    let desc = Inventory::default().descriptor();
+    let counts = desc.get_field_by_name("counts").unwrap();
+
+    let value = Value::map_for_field(
+        &counts,
+        [
+            (MapKey::String("apples".to_owned()), Value::I32(3)),
+            (MapKey::String("pears".to_owned()), Value::I32(5)),
+        ],
+    )
+    .unwrap();
+    let mut message = DynamicMessage::new(desc.clone());
+    message.set_field(&counts, value);
+    assert_eq!(
+        message.transcode_to::<Inventory>().unwrap().counts,
+        HashMap::from_iter([("apples".to_owned(), 3), ("pears".to_owned(), 5)])
+    );
+
+    assert_eq!(
+        Value::map_for_field(
+            &counts,
+            [(MapKey::String("apples".to_owned()), Value::U32(3))]
+        ),
+        Err(SetFieldError::InvalidType {
+            field: counts.clone(),
+            value: Value::U32(3),
+        })
+    );
+    assert_eq!(
+        Value::map_for_field(&counts, [(MapKey::I32(1), Value::I32(3))]),
+        Err(SetFieldError::InvalidType {
+            field: counts,
+            value: Value::I32(1),
+        })
+    );
+
+    let items = desc.get_field_by_name("items").unwrap();
+    assert!(Value::map_for_field(&items, []).is_err());
+}
+
+#[test]
+fn visit_message_tree() {
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn visit_scalar(&mut self, field: &FieldDescriptor, value: &Value) {
+            self.0.push(format!("{}={}", field.name(), value));
+        }
+
+        fn visit_message(&mut self, field: &FieldDescriptor, _: &DynamicMessage) {
+            self.0.push(format!("{}{{}}", field.name()));
+        }
+
+        fn visit_list_element(&mut self, field: &FieldDescriptor, index: usize, value: &Value) {
+            self.0
+                .push(format!("{}[{}]={}", field.name(), index, value));
+        }
+
+        fn visit_map_entry(&mut self, field: &FieldDescriptor, key: &MapKey, _: &Value) {
+            self.0
+                .push(format!("{}[{}]", field.name(), Value::from(key.clone())));
+        }
+    }
+
+    let message = ComplexType {
+        int_map: HashMap::from_iter([(
+            1,
+            Scalars {
+                int32: 2,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut recorder = Recorder::default();
+    message.accept(&mut recorder);
+    assert_eq!(
+        recorder.0,
+        [
+            "int_map[1]",
+            "int32=2",
+            "nested{}",
+            "string=\"hello\"",
+            "my_enum[0]=1",
+            "my_enum[1]=3",
+        ]
+    );
+}
+
+#[test]
+fn visit_message_tree_mut() {
+    struct Redact;
+
+    impl VisitorMut for Redact {
+        fn visit_scalar(&mut self, field: &FieldDescriptor, value: &mut Value) {
+            if field.name() == "string" {
+                *value = Value::String("<redacted>".to_owned());
+            }
+        }
+
+        fn visit_map_entry(&mut self, _: &FieldDescriptor, _: &MapKey, value: &mut Value) {
+            value.as_message_mut().unwrap().clear_field_by_name("int32");
+        }
+    }
+
+    let mut message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                int32: 2,
+                string: "secret".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    message.accept_mut(&mut Redact);
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            string_map: HashMap::from_iter([(
+                "a".to_owned(),
+                Scalars {
+                    string: "<redacted>".to_owned(),
+                    ..Default::default()
+                },
+            )]),
+            nested: Some(Scalars {
+                string: "<redacted>".to_owned(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn redact_sensitive_fields() {
+    let pool = test_file_descriptor();
+    let desc = pool.get_message_by_name("custom.options.Account").unwrap();
+    let sensitive = pool
+        .get_extension_by_name("custom.options.sensitive")
+        .unwrap();
+
+    let mut message = DynamicMessage::parse_text_format(
+        desc.clone(),
+        r#"
+            name: "root"
+            password: "hunter2"
+            recovery_codes: ["a", "b"]
+            parent { name: "parent" password: "secret" }
+            linked { name: "linked" password: "secret" }
+            by_id { key: "x" value { name: "mapped" password: "secret" } }
+        "#,
+    )
+    .unwrap();
+    message
+        .redact_fields(|field| field.options().get_extension(&sensitive).as_bool() == Some(true));
+
+    let expected = DynamicMessage::parse_text_format(
+        desc,
+        r#"
+            name: "root"
+            parent { name: "parent" }
+            linked { name: "linked" }
+            by_id { key: "x" value { name: "mapped" } }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(message, expected);
+}
+
+#[test]
+fn decode_merges_duplicate_message_fields() {
+    // Sets `nested` twice: first with `int32: 5, string: "a"`, then with `int64: 6, string: "b"`.
+    let bytes = b"\x1a\x05\x18\x05\x72\x01a\x1a\x05\x20\x06\x72\x01b";
+    let message =
+        DynamicMessage::decode(ComplexType::default().descriptor(), bytes.as_ref()).unwrap();
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap().nested,
+        Some(Scalars {
+            int32: 5,
+            int64: 6,
+            string: "b".to_owned(),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        message.encode_to_vec(),
+        ComplexType::decode(bytes.as_ref()).unwrap().encode_to_vec()
+    );
+}
+
+#[test]
+fn encode_without_unknown_fields() {
+    let known = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        my_enum: vec![1],
+        ..Default::default()
+    };
+    // Unknown field 100 in the top-level message, and 101 in `nested`.
+    let mut bytes = known.encode_to_vec();
+    bytes.extend_from_slice(b"\xa0\x06\x01\x1a\x03\xa8\x06\x02");
+    let message =
+        DynamicMessage::decode(ComplexType::default().descriptor(), bytes.as_slice()).unwrap();
+
+    let mut buf = Vec::new();
+    message
+        .encode_with_options(
+            &mut buf,
+            &EncodeOptions::new().include_unknown_fields(false),
+        )
+        .unwrap();
+    assert_eq!(buf, known.encode_to_vec());
+    assert_eq!(message.unknown_fields().count(), 1);
+
+    let mut buf = Vec::new();
+    message
+        .encode_with_options(&mut buf, &EncodeOptions::default())
+        .unwrap();
+    assert_eq!(buf, message.encode_to_vec());
+}
+
+#[test]
+fn encode_without_unknown_fields_preserving_wire_order() {
+    // optional_enum, unknown field 30, my_enum, then nested containing only unknown field 30.
+    const BYTES: &[u8] = b"\x28\x01\xf0\x01\x07\x22\x01\x03\x1a\x03\xf0\x01\x07";
+
+    let message =
+        DynamicMessage::decode_preserving_wire_order(ComplexType::default().descriptor(), BYTES)
+            .unwrap();
+    let mut buf = Vec::new();
+    message
+        .encode_with_options(
+            &mut buf,
+            &EncodeOptions::new().include_unknown_fields(false),
+        )
+        .unwrap();
+    assert_eq!(buf, b"\x28\x01\x22\x01\x03\x1a\x00");
+}
+
+#[test]
+fn message_is_compatible_with() {
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic();
+    let with_tags = PointWithTags::default().descriptor();
+    let remapped = RemappedPoint::default().descriptor();
+
+    assert!(point.is_compatible_with(&point.descriptor()));
+    assert!(point.is_compatible_with(&with_tags));
+    // Field 1 changed from int32 to string.
+    assert!(!point.is_compatible_with(&remapped));
+
+    let tagged = PointWithTags {
+        latitude: 1,
+        longitude: 2,
+        tags: vec![3],
+    }
+    .transcode_to_dynamic();
+    assert!(!tagged.is_compatible_with(&point.descriptor()));
+
+    // Unknown fields are only checked if the target defines them.
+    let unknown_varint = DynamicMessage::decode(point.descriptor(), b"\x18\x05".as_ref()).unwrap();
+    assert!(unknown_varint.is_compatible_with(&point.descriptor()));
+    assert!(unknown_varint.is_compatible_with(&with_tags));
+    assert!(unknown_varint.is_compatible_with(&remapped));
+    let unknown_packed =
+        DynamicMessage::decode(point.descriptor(), b"\x1a\x01\x05".as_ref()).unwrap();
+    assert!(unknown_packed.is_compatible_with(&with_tags));
+    assert!(!unknown_packed.is_compatible_with(&remapped));
+
+    let complex = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert!(complex.is_compatible_with(&complex.descriptor()));
+    assert!(!complex.is_compatible_with(&Scalars::default().descriptor()));
+}
+
+#[test]
+fn timestamp_and_duration_conversions() {
+    let time = SystemTime::UNIX_EPOCH - Duration::new(5, 250);
+    let timestamp = DynamicMessage::from_system_time(time).unwrap();
+    assert_eq!(
+        timestamp.transcode_to::<prost_types::Timestamp>().unwrap(),
+        prost_types::Timestamp {
+            seconds: -6,
+            nanos: 999_999_750,
+        }
+    );
+    assert_eq!(timestamp.to_system_time(), Some(time));
+    assert_eq!(timestamp.to_duration(), None);
+
+    let out_of_range = prost_types::Timestamp {
+        seconds: 253402300800,
+        nanos: 0,
+    }
+    .transcode_to_dynamic();
+    assert_eq!(out_of_range.to_system_time(), None);
+    assert_eq!(
+        DynamicMessage::from_system_time(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(253402300800)
+        ),
+        None
+    );
+
+    let duration = DynamicMessage::from_duration(Duration::new(3, 5)).unwrap();
+    assert_eq!(
+        duration.transcode_to::<prost_types::Duration>().unwrap(),
+        prost_types::Duration {
+            seconds: 3,
+            nanos: 5,
+        }
+    );
+    assert_eq!(duration.to_duration(), Some(Duration::new(3, 5)));
+    assert_eq!(duration.to_system_time(), None);
+
+    let negative = prost_types::Duration {
+        seconds: -1,
+        nanos: 0,
+    }
+    .transcode_to_dynamic();
+    assert_eq!(negative.to_duration(), None);
+    assert_eq!(
+        DynamicMessage::from_duration(Duration::from_secs(315_576_000_001)),
+        None
+    );
+
+    assert_eq!(Point::default().transcode_to_dynamic().to_duration(), None);
+}
+
+#[test]
+fn message_size_delta_by_field() {
+    let old = Scalars {
+        int32: 1,
+        string: "hello".to_owned(),
+        bytes: b"abc".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let mut new = Scalars {
+        int32: 300,
+        string: "hi".to_owned(),
+        bytes: b"abc".to_vec(),
+        bool: true,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    new.merge(b"\xa0\x06\x01".as_ref()).unwrap();
+
+    assert_eq!(new.size_delta(&old), 3);
+    assert_eq!(old.size_delta(&new), -3);
+
+    let int32 = new
+        .descriptor()
+        .get_field_by_name("int32")
+        .unwrap()
+        .number();
+    let string = new
+        .descriptor()
+        .get_field_by_name("string")
+        .unwrap()
+        .number();
+    let bool = new.descriptor().get_field_by_name("bool").unwrap().number();
+    assert_eq!(
+        new.size_delta_by_field(&old),
+        HashMap::from_iter([(int32, 1), (string, -3), (bool, 2), (100, 3)])
+    );
+    assert_eq!(
+        new.size_delta_by_field(&old).values().sum::<i64>(),
+        new.size_delta(&old)
+    );
+    assert!(new.size_delta_by_field(&new).is_empty());
+}
+
+#[test]
+fn decode_lenient_changed_wire_type() {
+    let bytes = RemappedPoint {
+        longitude: "abc".to_owned(),
+        latitude: 5,
+    }
+    .encode_to_vec();
+    let desc = Point::default().descriptor();
+
+    assert!(DynamicMessage::decode(desc.clone(), bytes.as_slice()).is_err());
+
+    let message = DynamicMessage::decode_lenient(desc, bytes.as_slice()).unwrap();
+    assert!(!message.has_field_by_name("latitude"));
+    assert!(!message.has_field_by_name("longitude"));
+    assert_eq!(
+        message
+            .unknown_fields()
+            .map(|field| (field.number(), field.wire_type()))
+            .collect::<Vec<_>>(),
+        vec![(1, WireType::LengthDelimited), (3, WireType::Varint)]
+    );
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn decode_lenient_nested_changed_wire_type() {
+    // int_map { key: 1, value { double (varint): 7 } }, nested { int32: 5, string (varint): 1 }
+    let bytes = b"\x12\x06\x08\x01\x12\x02\x08\x07\x1a\x04\x18\x05\x70\x01";
+    let desc = ComplexType::default().descriptor();
+
+    assert!(DynamicMessage::decode(desc.clone(), bytes.as_ref()).is_err());
+
+    let message = DynamicMessage::decode_lenient(desc, bytes.as_ref()).unwrap();
+    let nested = message.get_field_by_name("nested").unwrap();
+    let nested = nested.as_message().unwrap();
+    assert_eq!(
+        nested.get_field_by_name("int32").unwrap().as_ref(),
+        &Value::I32(5)
+    );
+    assert_eq!(nested.unknown_fields().next().unwrap().number(), 14);
+
+    let int_map = message.get_field_by_name("int_map").unwrap();
+    let entry = int_map.as_map().unwrap()[&MapKey::I32(1)]
+        .as_message()
+        .unwrap()
+        .clone();
+    assert!(!entry.has_field_by_name("double"));
+    assert_eq!(entry.unknown_fields().next().unwrap().number(), 1);
+
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn decode_lenient_group_changed_wire_type() {
+    // OptionalGroup { c (varint): 1, d: 5 }, RepeatedGroup { e: "x", f (length-delimited): "ab" }
+    let bytes = b"\x13\x08\x01\x10\x05\x14\x1b\x0a\x01x\x12\x02ab\x1c";
+    let desc = ContainsGroup::default().descriptor();
+
+    assert!(DynamicMessage::decode(desc.clone(), bytes.as_ref()).is_err());
+
+    let message = DynamicMessage::decode_lenient(desc, bytes.as_ref()).unwrap();
+    let optional_group = message.get_field_by_name("optionalgroup").unwrap();
+    let optional_group = optional_group.as_message().unwrap();
+    assert!(!optional_group.has_field_by_name("c"));
+    assert_eq!(
+        optional_group.get_field_by_name("d").unwrap().as_ref(),
+        &Value::I32(5)
+    );
+    assert_eq!(optional_group.unknown_fields().next().unwrap().number(), 1);
+
+    let repeated_group = message.get_field_by_name("repeatedgroup").unwrap();
+    let repeated_group = repeated_group.as_list().unwrap()[0].as_message().unwrap();
+    assert_eq!(
+        repeated_group.get_field_by_name("e").unwrap().as_ref(),
+        &Value::String("x".to_owned())
+    );
+    assert!(!repeated_group.has_field_by_name("f"));
+    assert_eq!(repeated_group.unknown_fields().next().unwrap().number(), 2);
+
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn decode_lenient_group_missing_end_tag() {
+    let bytes = b"\x13\x10\x05";
+    let desc = ContainsGroup::default().descriptor();
+
+    assert!(DynamicMessage::decode_lenient(desc, bytes.as_ref()).is_err());
+}
+
+#[test]
+fn iter_all_fields_includes_defaults() {
+    let message = MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField2(5)),
+    }
+    .transcode_to_dynamic();
+    assert_eq!(
+        message
+            .iter_all_fields()
+            .map(|(field, value)| (field.name().to_owned(), value.into_owned()))
+            .collect::<Vec<_>>(),
+        vec![("oneof_field_2".to_owned(), Value::I32(5))]
+    );
+
+    let message = Point {
+        latitude: 0,
+        longitude: 3,
+    }
+    .transcode_to_dynamic();
+    assert_eq!(
+        message
+            .iter_all_fields()
+            .map(|(field, value)| (field.name().to_owned(), value.into_owned()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("latitude".to_owned(), Value::I32(0)),
+            ("longitude".to_owned(), Value::I32(3)),
+        ]
+    );
+
+    let message = ComplexType::default().transcode_to_dynamic();
+    assert_eq!(
+        message.iter_all_fields().count(),
+        message.descriptor().fields().count()
+    );
+    assert_eq!(
+        MessageWithOneof::default()
+            .transcode_to_dynamic()
+            .iter_all_fields()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn message_view_decodes_fields_lazily() {
+    let message = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+    let bytes = Bytes::from(message.encode_to_vec());
+    let desc = message.descriptor();
+    let nested = desc.get_field_by_name("nested").unwrap();
+    let int_map = desc.get_field_by_name("int_map").unwrap();
+    let my_enum = desc.get_field_by_name("my_enum").unwrap();
+    let optional_enum = desc.get_field_by_name("optional_enum").unwrap();
+
+    let view = DynamicMessageView::new(desc, bytes).unwrap();
+    assert!(view.has_field(&nested));
+    assert!(!view.has_field(&optional_enum));
+    assert!(!view.is_field_decoded(&nested));
+
+    assert_eq!(
+        view.get_field(&my_enum).unwrap().as_ref(),
+        &Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(3)])
+    );
+    assert!(view.is_field_decoded(&my_enum));
+    assert!(!view.is_field_decoded(&nested));
+    assert!(!view.is_field_decoded(&int_map));
+
+    assert_eq!(
+        view.get_field(&optional_enum).unwrap().as_ref(),
+        &Value::EnumNumber(0)
+    );
+    assert!(!view.is_field_decoded(&optional_enum));
+
+    assert_eq!(view.to_message().unwrap(), message.transcode_to_dynamic());
+    assert!(!view.is_field_decoded(&nested));
+}
+
+#[test]
+fn message_view_to_message_wire_order() {
+    let desc = MessageWithOneof::default().descriptor();
+    let bytes = Bytes::from_static(b"\x10\x05\x0a\x01a\x48\x01");
+    let view = DynamicMessageView::new(desc.clone(), bytes.clone()).unwrap();
+
+    let message = view.to_message().unwrap();
+    assert_eq!(message, DynamicMessage::decode(desc, bytes).unwrap());
+    assert!(message.has_field_by_name("oneof_field_1"));
+    assert!(!message.has_field_by_name("oneof_field_2"));
+    assert_eq!(message.unknown_fields().count(), 1);
+    assert_eq!(message.encode_to_vec(), b"\x0a\x01a\x48\x01");
+}
+
+#[test]
+fn message_view_invalid_field() {
+    let desc = Point::default().descriptor();
+    let view =
+        DynamicMessageView::new(desc.clone(), Bytes::from_static(b"\x0a\x01a\x10\x01")).unwrap();
+    assert!(view.get_field_by_name("latitude").unwrap().is_err());
+    assert_eq!(
+        view.get_field_by_name("longitude")
+            .unwrap()
+            .unwrap()
+            .as_ref(),
+        &Value::I32(1)
+    );
+
+    assert!(DynamicMessageView::new(desc, Bytes::from_static(b"\x0a\x05a")).is_err());
+}
+
+#[test]
+fn set_field_checked_enum_numbers() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let enum_field = desc.get_field_by_name("enum").unwrap();
+    let mut message = DynamicMessage::new(desc.clone());
+
+    assert_eq!(
+        message.set_field_checked(&enum_field, Value::EnumNumber(3)),
+        Ok(())
+    );
+    assert_eq!(
+        message.set_field_checked(&enum_field, Value::EnumNumber(5)),
+        Err(SetFieldError::InvalidEnumNumber {
+            field: enum_field.clone(),
+            number: 5,
+        })
+    );
+    assert_eq!(
+        message.get_field(&enum_field).as_ref(),
+        &Value::EnumNumber(3)
+    );
+    assert_eq!(
+        message
+            .set_field_checked(&enum_field, Value::EnumNumber(5))
+            .unwrap_err()
+            .to_string(),
+        "enum number 5 is not defined by the closed enum type 'test2.Proto2Enum' of field 'test2.DefaultValues.enum'"
+    );
+    assert!(matches!(
+        message.set_field_checked(&enum_field, Value::I32(3)),
+        Err(SetFieldError::InvalidType { .. })
+    ));
+
+    // Open enums accept unknown numbers.
+    let desc = ComplexType::default().descriptor();
+    let mut message = DynamicMessage::new(desc.clone());
+    assert_eq!(
+        message.set_field_checked(
+            &desc.get_field_by_name("optional_enum").unwrap(),
+            Value::EnumNumber(42)
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        message.set_field_checked(
+            &desc.get_field_by_name("my_enum").unwrap(),
+            Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(42)])
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn encode_force_unpacked() {
+    let arrays = ScalarArrays {
+        int32: vec![1, 2],
+        double: vec![1.0],
+        ..Default::default()
+    };
+    let message = NestedScalarArrays {
+        nested: Some(arrays.clone()),
+        list: vec![arrays.clone()],
+        map: HashMap::from_iter([(5, arrays.clone())]),
+    };
+    let dynamic = message.transcode_to_dynamic();
+    let int32 = arrays.descriptor().get_field_by_name("int32").unwrap();
+
+    let mut buf = Vec::new();
+    dynamic
+        .encode_with_options(&mut buf, &EncodeOptions::new())
+        .unwrap();
+    assert_eq!(buf, message.encode_to_vec());
+
+    let options = EncodeOptions::new().force_unpacked(&int32);
+    let mut buf = Vec::new();
+    dynamic.encode_with_options(&mut buf, &options).unwrap();
+    assert_ne!(buf, message.encode_to_vec());
+    assert_eq!(NestedScalarArrays::decode(buf.as_slice()).unwrap(), message);
+    assert_eq!(
+        &buf[..16],
+        b"\x0a\x0e\x0a\x08\x00\x00\x00\x00\x00\x00\xf0\x3f\x18\x01\x18\x02"
+    );
+
+    let mut buf = Vec::new();
+    arrays
+        .transcode_to_dynamic()
+        .encode_with_options(&mut buf, &options)
+        .unwrap();
+    assert_eq!(
+        buf,
+        b"\x0a\x08\x00\x00\x00\x00\x00\x00\xf0\x3f\x18\x01\x18\x02"
+    );
+
+    let mut unpacked = Vec::new();
+    dynamic
+        .encode_with_options(&mut unpacked, &options)
+        .unwrap();
+    let mut buf = [0; 4];
+    let err = dynamic
+        .encode_with_options(&mut buf.as_mut_slice(), &options)
+        .unwrap_err();
+    assert_eq!(err.required_capacity(), unpacked.len());
+    assert_eq!(err.remaining(), 4);
+    assert_eq!(buf, [0; 4]);
+}
+
+#[test]
+fn encode_force_unpacked_extension() {
+    let pool = test_file_descriptor();
+    let extension = pool
+        .get_extension_by_name("test2.packed_extension")
+        .unwrap();
+    assert!(extension.is_packed());
+
+    let mut message =
+        DynamicMessage::new(pool.get_message_by_name("test2.PackedExtensions").unwrap());
+    message.set_extension(&extension, Value::List(vec![Value::I32(1), Value::I32(2)]));
+    assert_eq!(message.encode_to_vec(), b"\xa2\x06\x02\x01\x02");
+
+    let options = EncodeOptions::new().force_unpacked_extension(&extension);
+    let mut buf = Vec::new();
+    message.encode_with_options(&mut buf, &options).unwrap();
+    assert_eq!(buf, b"\xa0\x06\x01\xa0\x06\x02");
+}
+
+#[test]
+fn message_oneof_case() {
+    let mut message = MessageWithOneof::default().transcode_to_dynamic();
+    let oneof = message.descriptor().oneofs().next().unwrap();
+    assert_eq!(oneof.name(), "test_oneof");
+    assert_eq!(message.oneof_case(&oneof), None);
+
+    message.set_field_by_name("oneof_field_1", Value::String("hello".to_owned()));
+    assert_eq!(message.oneof_case(&oneof).unwrap().name(), "oneof_field_1");
+
+    message.set_field_by_name("oneof_field_2", Value::I32(5));
+    assert_eq!(message.oneof_case(&oneof).unwrap().name(), "oneof_field_2");
+
+    message.clear_field_by_name("oneof_field_2");
+    assert_eq!(message.oneof_case(&oneof), None);
+
+    let message = MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField2(0)),
+    }
+    .transcode_to_dynamic();
+    assert_eq!(message.oneof_case(&oneof).unwrap().name(), "oneof_field_2");
+}
+
+#[test]
+fn message_stable_hash() {
+    fn hash(message: &DynamicMessage) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        message.stable_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let message = ComplexType {
+        string_map: HashMap::from_iter((0..20).map(|i| {
+            (
+                i.to_string(),
+                Scalars {
+                    int32: i,
+                    double: i as f64 / 3.0,
+                    ..Default::default()
+                },
+            )
+        })),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+    let a = message.transcode_to_dynamic();
+
+    // Rebuild the map in a different insertion order.
+    let mut b = DynamicMessage::new(a.descriptor());
+    let mut entries: Vec<_> = a
+        .get_field_by_name("string_map")
+        .unwrap()
+        .as_map()
+        .unwrap()
+        .clone()
+        .into_iter()
+        .collect();
+    entries.sort_by(|(l, _), (r, _)| r.cmp(l));
+    b.set_field_by_name(
+        "my_enum",
+        a.get_field_by_name("my_enum").unwrap().into_owned(),
+    );
+    b.set_field_by_name("string_map", Value::Map(entries.into_iter().collect()));
+    assert_eq!(a, b);
+    assert_eq!(hash(&a), hash(&b));
+
+    let mut c = a.clone();
+    c.set_field_by_name(
+        "my_enum",
+        Value::List(vec![Value::EnumNumber(3), Value::EnumNumber(1)]),
+    );
+    assert_ne!(hash(&a), hash(&c));
+
+    let mut d = a.clone();
+    d.merge(b"\xa0\x06\x01".as_ref()).unwrap();
+    assert_ne!(hash(&a), hash(&d));
+
+    let mut e = DynamicMessage::new(ScalarArrays::default().descriptor());
+    e.set_field_by_name("double", Value::List(vec![Value::F64(0.0)]));
+    let mut f = e.clone();
+    f.set_field_by_name("double", Value::List(vec![Value::F64(-0.0)]));
+    assert_eq!(e, f);
+    assert_ne!(hash(&e), hash(&f));
+}
+
+#[test]
+fn get_field_borrows_set_values() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([("hello".to_owned(), Scalars::default())]),
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert!(matches!(
+        message.get_field_by_name("string_map").unwrap(),
+        Cow::Borrowed(Value::Map(_))
+    ));
+    assert!(matches!(
+        message.get_field_by_name("nested").unwrap(),
+        Cow::Borrowed(Value::Message(_))
+    ));
+    assert!(matches!(
+        message.get_field_by_number(2).unwrap(),
+        Cow::Owned(Value::Map(_))
+    ));
+    assert!(matches!(
+        message.get_field_by_name("my_enum").unwrap(),
+        Cow::Owned(Value::List(_))
+    ));
+}
+
+#[test]
+fn set_fields_from() {
+    let mut message = DynamicMessage::new(Scalars::default().descriptor());
+    message
+        .set_fields_from(vec![
+            ("int32".to_owned(), Value::I32(3)),
+            ("string".to_owned(), Value::String("hello".to_owned())),
+        ])
+        .unwrap();
+    assert_eq!(
+        message.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 3,
+            string: "hello".to_owned(),
+            ..Default::default()
+        }
+    );
+
+    let err = message
+        .set_fields_from(vec![
+            ("bool".to_owned(), Value::Bool(true)),
+            ("notfound".to_owned(), Value::I32(1)),
+            ("int64".to_owned(), Value::I64(2)),
+        ])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SetFieldError::FieldNameNotFound {
+            name: "notfound".to_owned()
+        }
+    );
+    assert_eq!(err.to_string(), "field 'notfound' not found");
+    assert!(message.has_field_by_name("bool"));
+    assert!(!message.has_field_by_name("int64"));
+
+    assert!(matches!(
+        message.set_fields_from(vec![("int64".to_owned(), Value::I32(2))]),
+        Err(SetFieldError::InvalidType { .. })
+    ));
+    assert!(!message.has_field_by_name("int64"));
+}
+
+#[test]
+fn from_message() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "hello".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    };
+
+    let dynamic_message = DynamicMessage::from_message(&message);
+    assert_eq!(dynamic_message.descriptor(), message.descriptor());
+    assert_eq!(dynamic_message, message.transcode_to_dynamic());
+    assert_eq!(
+        dynamic_message.transcode_to::<ComplexType>().unwrap(),
+        message
+    );
+}
+
+#[test]
+fn message_approx_eq() {
+    let message = |double: f64, float: f32, int32: i32| {
+        ComplexType {
+            string_map: HashMap::from_iter([(
+                "a".to_owned(),
+                Scalars {
+                    double,
+                    ..Default::default()
+                },
+            )]),
+            nested: Some(Scalars {
+                float,
+                int32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .transcode_to_dynamic()
+    };
+
+    let a = message(1.0, 100.0, 1);
+    assert!(a.approx_eq(&a, 0.0));
+    assert!(a.approx_eq(&message(1.0 + 1e-10, 100.0, 1), 1e-9));
+    assert!(!a.approx_eq(&message(1.0 + 1e-8, 100.0, 1), 1e-9));
+    assert!(a.approx_eq(&message(1.0, 100.001, 1), 1e-4));
+    assert!(!a.approx_eq(&message(1.0, 100.1, 1), 1e-4));
+    assert!(!a.approx_eq(&message(1.0, 100.0, 2), 1.0));
+
+    let nan = message(f64::NAN, f32::NAN, 1);
+    assert_ne!(nan, nan);
+    assert!(nan.approx_eq(&nan, 0.0));
+    assert!(!nan.approx_eq_with(&nan, 0.0, false));
+    assert!(!nan.approx_eq(&a, 1.0));
+
+    let list = |values: Vec<f64>| {
+        ScalarArrays {
+            double: values,
+            ..Default::default()
+        }
+        .transcode_to_dynamic()
+    };
+    assert!(list(vec![1.0, 2.0]).approx_eq(&list(vec![1.0, 2.0 + 1e-12]), 1e-9));
+    assert!(!list(vec![1.0, 2.0]).approx_eq(&list(vec![1.0]), 1e-9));
+
+    let mut unknown = a.clone();
+    unknown.merge(b"\xa0\x06\x01".as_ref()).unwrap();
+    assert!(!a.approx_eq(&unknown, 1.0));
+    assert!(!a.approx_eq(&Scalars::default().transcode_to_dynamic(), 1.0));
+}
+
+#[test]
+fn map_key_value_conversions() {
+    let keys = [
+        MapKey::Bool(true),
+        MapKey::I32(-1),
+        MapKey::I64(2),
+        MapKey::U32(3),
+        MapKey::U64(4),
+        MapKey::String("five".to_owned()),
+    ];
+    for key in keys {
+        let value = key.to_value();
+        assert_eq!(value.clone().try_into_map_key(), Ok(key.clone()));
+        assert_eq!(MapKey::try_from(value), Ok(key));
+    }
+
+    for value in [
+        Value::F32(1.0),
+        Value::F64(2.0),
+        Value::Bytes(Bytes::from_static(b"bytes")),
+        Value::EnumNumber(3),
+        Value::List(vec![]),
+        Value::Map(HashMap::new()),
+        Value::Message(Scalars::default().transcode_to_dynamic()),
+    ] {
+        let err = value.clone().try_into_map_key().unwrap_err();
+        assert_eq!(err.expected(), "MapKey");
+        assert_eq!(err.into_value(), value);
+    }
+}
+
+#[test]
+fn reparse_unknown_fields_incompatible() {
+    // Field 1 is an int32 encoded as a string, and field 10 is not defined.
+    let bytes = b"\x0a\x03abc\x50\x01\x10\x05".as_ref();
+    let mut message = DynamicMessage::decode_lenient(Point::default().descriptor(), bytes).unwrap();
+    assert_eq!(message.unknown_fields().count(), 2);
+
+    message.reparse_unknown_fields();
+    let unknowns: Vec<_> = message.unknown_fields().map(|f| f.number()).collect();
+    assert_eq!(unknowns, [1, 10]);
+    assert_eq!(message.encode_to_vec(), b"\x0a\x03abc\x10\x05\x50\x01");
+}
+
+#[test]
+fn encode_into_shared_buffer() {
+    let point = Point {
+        latitude: 1,
+        longitude: 2,
+    };
+    let message =
+        DynamicMessage::decode(point.descriptor(), b"\x08\x01\x10\x02\x50\x03".as_ref()).unwrap();
+
+    let mut buf = prost::bytes::BytesMut::with_capacity(12);
+    message.encode(&mut buf).unwrap();
+    message.encode(&mut buf).unwrap();
+    assert_eq!(
+        buf.as_ref(),
+        b"\x08\x01\x10\x02\x50\x03\x08\x01\x10\x02\x50\x03"
+    );
+
+    let mut buf = [0u8; 5];
+    assert!(message.encode(&mut buf.as_mut()).is_err());
+}
+
+#[test]
+fn clear_message_with_unknown_fields() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.TreeNode")
+        .unwrap();
+    let mut message = DynamicMessage::decode(
+        desc.clone(),
+        b"\x0a\x04root\x12\x02\x08\x01\x22\x06\x0a\x04leaf\x50\x03".as_ref(),
+    )
+    .unwrap();
+    assert_eq!(message.unknown_fields().count(), 1);
+
+    message.clear();
+    assert_eq!(message.descriptor(), desc);
+    assert_eq!(message, DynamicMessage::new(desc));
+    assert!(message.encode_to_vec().is_empty());
+
+    message.set_field_by_name("name", Value::String("reused".to_owned()));
+    assert_eq!(message.encode_to_vec(), b"\x0a\x06reused");
+}
+
+#[test]
+fn decode_lazy() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.TreeNode")
+        .unwrap();
+    // name: "root", location: { latitude: 1 }, children: [{ name: "a" }, { name: "b" }], then location: { longitude: 2 }
+    let bytes =
+        b"\x0a\x04root\x12\x02\x08\x01\x22\x03\x0a\x01a\x22\x03\x0a\x01b\x12\x02\x10\x02".as_ref();
+
+    let options = DecodeOptions::new().lazy(true);
+    let lazy = DynamicMessage::decode_with_options(desc.clone(), bytes, &options).unwrap();
+    let eager = DynamicMessage::decode(desc.clone(), bytes).unwrap();
+    assert_eq!(lazy.encoded_len(), eager.encoded_len());
+    assert_eq!(lazy.encode_to_vec(), eager.encode_to_vec());
+    assert_eq!(lazy, eager);
+    assert_eq!(
+        lazy.get_field_by_name("location")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .transcode_to::<Point>()
+            .unwrap(),
+        Point {
+            latitude: 1,
+            longitude: 2
+        }
+    );
+    assert_eq!(lazy.encode_to_vec(), eager.encode_to_vec());
+
+    // The nested message has an invalid UTF-8 string, which is not detected until it is accessed.
+    let bytes = b"\x0a\x04leaf\x1a\x03\x0a\x01\xff".as_ref();
+    assert!(DynamicMessage::decode(desc.clone(), bytes).is_err());
+    let mut lazy = DynamicMessage::decode_with_options(desc, bytes, &options).unwrap();
+    assert_eq!(lazy.encode_to_vec(), bytes);
+    assert!(lazy.has_field_by_name("parent"));
+    let parent = lazy.get_field_by_name_mut("parent").unwrap();
+    assert_eq!(parent.as_message().unwrap().fields().count(), 0);
+    assert_eq!(parent.as_message().unwrap().unknown_fields().count(), 1);
+    assert_eq!(lazy.encode_to_vec(), bytes);
+
+    // Once modified, the nested message is encoded from its fields, which include the invalid field as an unknown
+    // field.
+    lazy.get_field_by_name_mut("parent")
+        .unwrap()
+        .as_message_mut()
+        .unwrap()
+        .clear_field_by_name("children");
+    assert_eq!(lazy.encode_to_vec(), bytes);
+}
+
+#[test]
+fn decode_bytes_zero_copy() {
+    let bytes = Bytes::from(
+        Scalars {
+            bytes: b"a large blob".to_vec(),
+            ..Default::default()
+        }
+        .encode_to_vec(),
+    );
+    let is_shared = |message: &DynamicMessage| {
+        let value = message.get_field_by_name("bytes").unwrap();
+        let value = value.as_bytes().unwrap();
+        assert_eq!(value.as_ref(), b"a large blob");
+        bytes.as_ptr_range().contains(&value.as_ptr())
+    };
+
+    let desc = Scalars::default().descriptor();
+    assert!(is_shared(
+        &DynamicMessage::decode(desc.clone(), bytes.clone()).unwrap()
+    ));
+    assert!(is_shared(
+        &DynamicMessage::decode_with_options(desc.clone(), bytes.clone(), &DecodeOptions::new())
+            .unwrap()
+    ));
+    assert!(is_shared(
+        &DynamicMessage::decode_lenient(desc.clone(), bytes.clone()).unwrap()
+    ));
+    assert!(!is_shared(
+        &DynamicMessage::decode(desc, bytes.as_ref()).unwrap()
+    ));
+}
+
+#[test]
+fn proto3_default_fields_are_not_encoded() {
+    let message = ComplexType {
+        optional_enum: 0,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert!(message.encode_to_vec().is_empty());
+}
+
+#[test]
+fn oneof_set_multiple_values() {
+    let mut value = Vec::new();
+    MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField1(
+            "hello".to_owned(),
+        )),
+    }
+    .encode(&mut value)
+    .unwrap();
+    MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField2(5)),
+    }
+    .encode(&mut value)
+    .unwrap();
+
+    let dynamic_message = DynamicMessage::decode(
+        test_file_descriptor()
+            .get_message_by_name("test.MessageWithOneof")
+            .unwrap(),
+        value.as_ref(),
+    )
+    .unwrap();
+
+    assert!(!dynamic_message.has_field_by_name("oneof_field_1"));
+    assert!(dynamic_message.has_field_by_name("oneof_field_2"));
+
+    assert_eq!(dynamic_message.encode_to_vec().as_slice(), b"\x10\x05");
+}
+
+#[test]
+fn roundtrip_extension() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+
+    let extension_desc = message_desc.get_extension(113).unwrap();
+    assert_eq!(
+        message_desc.get_extension_by_json_name(extension_desc.json_name()),
+        Some(extension_desc.clone())
+    );
+
+    let mut dynamic_message = DynamicMessage::new(message_desc.clone());
+    dynamic_message.set_extension(&extension_desc, Value::F64(42.0));
+    let bytes = dynamic_message.encode_to_vec();
+
+    let roundtripped_dynamic_message =
+        DynamicMessage::decode(message_desc, bytes.as_ref()).unwrap();
+    assert!(roundtripped_dynamic_message.has_extension(&extension_desc));
+    assert_eq!(
+        roundtripped_dynamic_message
+            .get_extension(&extension_desc)
+            .as_ref(),
+        &Value::F64(42.0)
+    );
+}
+
+#[test]
+fn set_and_clear_extension() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    let in_extendee = message_desc.get_extension(111).unwrap();
+    let in_file = message_desc.get_extension(112).unwrap();
+
+    let mut dynamic_message = DynamicMessage::new(message_desc.clone());
+    assert!(!dynamic_message.has_extension(&in_extendee));
+    assert_eq!(
+        dynamic_message.get_extension(&in_extendee).as_ref(),
+        &Value::I32(0)
+    );
+
+    dynamic_message.set_extension(&in_extendee, Value::I32(3));
+    dynamic_message.set_extension(&in_file, Value::F32(1.5));
+    let bytes = dynamic_message.encode_to_vec();
+
+    let mut roundtripped = DynamicMessage::decode(message_desc, bytes.as_ref()).unwrap();
+    assert_eq!(roundtripped, dynamic_message);
+    assert_eq!(
+        roundtripped.get_extension(&in_extendee).as_ref(),
+        &Value::I32(3)
+    );
+
+    roundtripped.clear_extension(&in_extendee);
+    assert!(!roundtripped.has_extension(&in_extendee));
+    assert!(roundtripped.has_extension(&in_file));
+    assert_eq!(roundtripped.encode_to_vec(), b"\x85\x07\x00\x00\xc0\x3f");
+
+    assert_eq!(roundtripped.take_extension(&in_file), Some(Value::F32(1.5)));
+    assert!(roundtripped.encode_to_vec().is_empty());
+}
+
+#[test]
+fn get_repeated_scalars_as_list() {
+    let message = ScalarArrays {
+        double: vec![1.1, 2.2],
+        int32: vec![3, -4],
+        uint64: vec![5],
+        bool: vec![true, false],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let field = |name: &str| message.get_field_by_name(name).unwrap();
+    assert_eq!(field("double").as_f64_list(), Some(vec![1.1, 2.2]));
+    assert_eq!(field("int32").as_i32_list(), Some(vec![3, -4]));
+    assert_eq!(field("uint64").as_u64_list(), Some(vec![5]));
+    assert_eq!(field("bool").as_bool_list(), Some(vec![true, false]));
+    assert_eq!(field("float").as_f32_list(), Some(vec![]));
+    assert_eq!(field("int32").as_i64_list(), None);
+    assert_eq!(field("uint64").as_u32_list(), None);
+
+    let enums = ComplexType {
+        my_enum: vec![0, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    assert_eq!(
+        enums
+            .get_field_by_name("my_enum")
+            .unwrap()
+            .as_enum_number_list(),
+        Some(vec![0, 3])
+    );
+    assert_eq!(Value::I32(1).as_i32_list(), None);
+}
+
+#[test]
+fn iter_fields_sorted() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+
+    let mut dynamic_message = DynamicMessage::new(message_desc.clone());
+    dynamic_message.set_extension(&message_desc.get_extension(113).unwrap(), Value::F64(1.0));
+    dynamic_message.set_field_by_name("int", Value::I32(2));
+    dynamic_message.set_extension(&message_desc.get_extension(111).unwrap(), Value::I32(3));
+
+    let fields: Vec<_> = dynamic_message
+        .iter_fields_sorted()
+        .map(|(field, value)| (field.number(), value.clone()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            (1, Value::I32(2)),
+            (111, Value::I32(3)),
+            (113, Value::F64(1.0))
+        ]
     );
+    assert!(matches!(
+        dynamic_message.iter_fields_sorted().nth(1),
+        Some((FieldOrExtension::Extension(_), _))
+    ));
 }
 
 #[test]
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
-use prost::{bytes::Bytes, Message};
-use prost_reflect::{DescriptorPool, DynamicMessage, MapKey, ReflectMessage, Syntax, Value};
+use prost::{bytes::Bytes, encoding::WireType, Message};
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, MapKey, MessageDescriptor, ReflectMessage, Syntax, Value,
+};
+use prost_types::field_options::CType;
 
 use crate::{
     proto::{self, ComplexType, Scalars},
@@ -100,6 +103,311 @@ fn test_descriptor_methods() {
     assert_eq!(method_desc.full_name(), "my.package.MyService.MyMethod");
 }
 
+#[test]
+fn test_all_field_paths() {
+    let complex = ComplexType::default().descriptor();
+    assert_eq!(
+        complex.all_field_paths(2),
+        [
+            "string_map",
+            "int_map",
+            "nested",
+            "nested.double",
+            "nested.float",
+            "nested.int32",
+            "nested.int64",
+            "nested.uint32",
+            "nested.uint64",
+            "nested.sint32",
+            "nested.sint64",
+            "nested.fixed32",
+            "nested.fixed64",
+            "nested.sfixed32",
+            "nested.sfixed64",
+            "nested.bool",
+            "nested.string",
+            "nested.bytes",
+            "my_enum",
+            "optional_enum",
+        ]
+    );
+    assert_eq!(
+        complex.all_field_paths(1),
+        [
+            "string_map",
+            "int_map",
+            "nested",
+            "my_enum",
+            "optional_enum"
+        ]
+    );
+    assert!(complex.all_field_paths(0).is_empty());
+
+    let account = test_file_descriptor()
+        .get_message_by_name("custom.options.Account")
+        .unwrap();
+    assert_eq!(
+        account.all_field_paths(3),
+        [
+            "name",
+            "password",
+            "recovery_codes",
+            "parent",
+            "parent.name",
+            "parent.password",
+            "parent.recovery_codes",
+            "parent.parent",
+            "parent.parent.name",
+            "parent.parent.password",
+            "parent.parent.recovery_codes",
+            "parent.parent.parent",
+            "parent.parent.linked",
+            "parent.parent.by_id",
+            "parent.linked",
+            "parent.by_id",
+            "linked",
+            "by_id",
+        ]
+    );
+}
+
+#[test]
+fn test_pool_iteration_order() {
+    fn collect_messages(message: MessageDescriptor, result: &mut Vec<String>) {
+        result.push(message.full_name().to_owned());
+        for child in message.child_messages() {
+            collect_messages(child, result);
+        }
+    }
+
+    let pool = test_file_descriptor();
+
+    let mut expected = Vec::new();
+    for file in pool.files() {
+        for message in file.messages() {
+            collect_messages(message, &mut expected);
+        }
+    }
+    let actual: Vec<_> = pool
+        .all_messages()
+        .map(|message| message.full_name().to_owned())
+        .collect();
+    assert_eq!(actual, expected);
+
+    let services: Vec<_> = pool
+        .all_services()
+        .map(|service| service.full_name().to_owned())
+        .collect();
+    let expected_services: Vec<_> = pool
+        .files()
+        .flat_map(|file| file.services().collect::<Vec<_>>())
+        .map(|service| service.full_name().to_owned())
+        .collect();
+    assert_eq!(services, expected_services);
+    assert!(services.contains(&"custom.options.Service".to_owned()));
+
+    assert!(pool
+        .all_enums()
+        .any(|enum_| enum_.full_name() == "test.ComplexType.MyEnum"));
+    assert!(pool
+        .all_extensions()
+        .any(|extension| extension.full_name() == "custom.options.sensitive"));
+}
+
+#[test]
+fn test_get_field_by_json_name() {
+    let message = test_file_descriptor()
+        .get_message_by_name("test2.JsonNames")
+        .unwrap();
+
+    let snake_case = message.get_field_by_json_name("snakeCaseField").unwrap();
+    assert_eq!(snake_case.name(), "snake_case_field");
+    assert!(message.get_field_by_json_name("snake_case_field").is_none());
+
+    let custom = message.get_field_by_json_name("renamed").unwrap();
+    assert_eq!(custom.name(), "custom_field");
+    assert_eq!(custom.json_name(), "renamed");
+    assert!(message.get_field_by_json_name("customField").is_none());
+}
+
+#[test]
+fn test_field_default_value() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test2.DefaultValues")
+        .unwrap();
+    let default_value = |name: &str| desc.get_field_by_name(name).unwrap().default_value();
+    assert_eq!(default_value("double"), Value::F64(1.1));
+    assert_eq!(default_value("int32"), Value::I32(-3));
+    assert_eq!(default_value("bool"), Value::Bool(true));
+    assert_eq!(default_value("string"), Value::String("hello".to_owned()));
+    assert_eq!(
+        default_value("bytes"),
+        Value::Bytes(Bytes::from_static(
+            b"\0\x01\x07\x08\x0C\n\r\t\x0B\\\'\"\xFE"
+        ))
+    );
+    assert_eq!(default_value("defaulted_enum"), Value::EnumNumber(3));
+    assert_eq!(default_value("enum"), Value::EnumNumber(2));
+
+    let desc = ComplexType::default().descriptor();
+    let default_value = |name: &str| desc.get_field_by_name(name).unwrap().default_value();
+    assert_eq!(default_value("optional_enum"), Value::EnumNumber(0));
+    assert_eq!(default_value("my_enum"), Value::List(vec![]));
+    assert_eq!(default_value("int_map"), Value::Map(HashMap::new()));
+    assert_eq!(
+        default_value("nested"),
+        Value::Message(DynamicMessage::new(Scalars::default().descriptor()))
+    );
+
+    let message = DynamicMessage::new(desc.clone());
+    for field in desc.fields() {
+        assert_eq!(
+            message.get_field(&field).into_owned(),
+            field.default_value()
+        );
+    }
+}
+
+#[test]
+fn test_enumerate_field_paths() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("test.TreeNode")
+        .unwrap();
+
+    assert!(message_desc.enumerate_field_paths(0).is_empty());
+    assert_eq!(
+        message_desc.enumerate_field_paths(1),
+        ["name", "location", "parent", "children", "wrapper"]
+    );
+    assert_eq!(
+        message_desc.enumerate_field_paths(2),
+        [
+            "name",
+            "location",
+            "location.latitude",
+            "location.longitude",
+            "parent",
+            "children",
+            "wrapper",
+            "wrapper.node",
+            "wrapper.complex",
+        ]
+    );
+    assert_eq!(
+        message_desc.enumerate_field_paths(usize::MAX),
+        [
+            "name",
+            "location",
+            "location.latitude",
+            "location.longitude",
+            "parent",
+            "children",
+            "wrapper",
+            "wrapper.node",
+            "wrapper.complex",
+            "wrapper.complex.string_map",
+            "wrapper.complex.int_map",
+            "wrapper.complex.nested",
+            "wrapper.complex.nested.double",
+            "wrapper.complex.nested.float",
+            "wrapper.complex.nested.int32",
+            "wrapper.complex.nested.int64",
+            "wrapper.complex.nested.uint32",
+            "wrapper.complex.nested.uint64",
+            "wrapper.complex.nested.sint32",
+            "wrapper.complex.nested.sint64",
+            "wrapper.complex.nested.fixed32",
+            "wrapper.complex.nested.fixed64",
+            "wrapper.complex.nested.sfixed32",
+            "wrapper.complex.nested.sfixed64",
+            "wrapper.complex.nested.bool",
+            "wrapper.complex.nested.string",
+            "wrapper.complex.nested.bytes",
+            "wrapper.complex.my_enum",
+            "wrapper.complex.optional_enum",
+        ]
+    );
+
+    let complex = ComplexType::default().descriptor();
+    assert_eq!(
+        complex.enumerate_field_paths(usize::MAX),
+        complex.all_field_paths(usize::MAX)
+    );
+    assert_ne!(
+        message_desc.enumerate_field_paths(3),
+        message_desc.all_field_paths(3)
+    );
+}
+
+#[test]
+fn test_deprecated_options() {
+    let pool = test_file_descriptor();
+
+    let enum_desc = pool.get_enum_by_name("test2.DeprecatedEnum").unwrap();
+    assert!(enum_desc.is_deprecated());
+    assert!(!enum_desc.get_value(0).unwrap().is_deprecated());
+    assert!(enum_desc.get_value(1).unwrap().is_deprecated());
+
+    let service_desc = pool
+        .services()
+        .find(|service| service.full_name() == "test2.DeprecatedService")
+        .unwrap();
+    assert!(service_desc.is_deprecated());
+    let methods: Vec<_> = service_desc
+        .methods()
+        .map(|method| (method.name().to_owned(), method.is_deprecated()))
+        .collect();
+    assert_eq!(
+        methods,
+        [
+            ("OldMethod".to_owned(), true),
+            ("NewMethod".to_owned(), false)
+        ]
+    );
+
+    assert!(!pool
+        .get_file_by_name("test2.proto")
+        .unwrap()
+        .is_deprecated());
+    assert!(!pool
+        .get_enum_by_name("test.EnumWithAlias")
+        .unwrap()
+        .is_deprecated());
+    assert!(!pool
+        .services()
+        .find(|service| service.full_name() == "my.package.MyService")
+        .unwrap()
+        .is_deprecated());
+}
+
+#[test]
+fn test_typed_options() {
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("test2.DeprecatedMessage")
+        .unwrap();
+    assert!(message_desc.is_deprecated());
+    assert!(!message_desc.is_map_entry());
+
+    let deprecated_field = message_desc.get_field_by_name("deprecated_field").unwrap();
+    assert!(deprecated_field.is_deprecated());
+    assert!(!deprecated_field.is_packed());
+
+    let packed_field = message_desc.get_field_by_name("packed_field").unwrap();
+    assert!(!packed_field.is_deprecated());
+    assert!(packed_field.is_packed());
+    assert_eq!(packed_field.ctype(), CType::String);
+
+    let cord_field = message_desc.get_field_by_name("cord_field").unwrap();
+    assert_eq!(cord_field.ctype(), CType::Cord);
+
+    let message_desc = Scalars::default().descriptor();
+    assert!(!message_desc.is_deprecated());
+    assert!(!message_desc
+        .get_field_by_name("int32")
+        .unwrap()
+        .is_deprecated());
+}
+
 #[test]
 fn test_descriptor_methods_proto2() {
     let file_desc = test_file_descriptor()
@@ -124,6 +432,14 @@ fn test_descriptor_methods_proto2() {
             .collect::<Vec<_>>(),
         vec![100, 110, 111, 112, 113, 114, 115],
     );
+    assert_eq!(
+        message_desc.extension_ranges().collect::<Vec<_>>(),
+        vec![100..101, 110..116],
+    );
+    assert!(message_desc.is_extension_number(100));
+    assert!(message_desc.is_extension_number(115));
+    assert!(!message_desc.is_extension_number(101));
+    assert!(!message_desc.is_extension_number(116));
 
     let mut extensions: Vec<_> = test_file_descriptor()
         .all_extensions()
@@ -399,6 +715,218 @@ fn test_enum_alias() {
     assert_eq!(enum_desc.get_value(3), None);
 }
 
+#[test]
+fn test_enum_values_declaration_order() {
+    let enum_desc = test_file_descriptor()
+        .get_enum_by_name("test.ComplexType.MyEnum")
+        .unwrap();
+    let values: Vec<_> = enum_desc
+        .values()
+        .map(|value| (value.name().to_owned(), value.number()))
+        .collect();
+    assert_eq!(
+        values,
+        [
+            ("DEFAULT".to_owned(), 0),
+            ("FOO".to_owned(), 1),
+            ("BAR".to_owned(), 3),
+            ("NEG".to_owned(), -4),
+        ]
+    );
+
+    let enum_desc = test_file_descriptor()
+        .get_enum_by_name("test.EnumWithAlias")
+        .unwrap();
+    let names: Vec<_> = enum_desc
+        .values()
+        .map(|value| value.name().to_owned())
+        .collect();
+    assert_eq!(names, ["FOO", "BAR", "A", "B", "C", "TWO"]);
+    assert_eq!(enum_desc.values().len(), 6);
+    assert_eq!(
+        enum_desc.values().next().unwrap(),
+        enum_desc.default_value()
+    );
+}
+
+#[test]
+fn test_get_extension_by_number() {
+    let pool = test_file_descriptor();
+
+    let names: Vec<_> = [111, 112, 113]
+        .into_iter()
+        .map(|number| {
+            pool.get_extension_by_number("my.package2.MyMessage", number)
+                .unwrap()
+                .full_name()
+                .to_owned()
+        })
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "my.package2.MyMessage.in_extendee",
+            "my.package2.in_file",
+            "my.package2.OtherMessage.in_other"
+        ]
+    );
+
+    assert_eq!(
+        pool.get_extension_by_number("google.protobuf.EnumValueOptions", 50000)
+            .unwrap()
+            .full_name(),
+        "demo.len"
+    );
+    assert!(pool
+        .get_extension_by_number("my.package2.MyMessage", 1)
+        .is_none());
+    assert!(pool
+        .get_extension_by_number("my.package2.MyMessage", 114)
+        .is_none());
+    assert!(pool
+        .get_extension_by_number("my.package2.NotAMessage", 111)
+        .is_none());
+}
+
+#[test]
+fn test_is_recursive() {
+    let pool = test_file_descriptor();
+    let is_recursive = |name: &str| pool.get_message_by_name(name).unwrap().is_recursive();
+
+    assert!(is_recursive("test.TreeNode"));
+    assert!(!is_recursive("test.Point"));
+    assert!(!is_recursive("test.Scalars"));
+    assert!(!is_recursive("test.ComplexType"));
+    assert!(is_recursive("google.protobuf.Value"));
+    assert!(is_recursive("google.protobuf.ListValue"));
+    // Contains a recursive type, without being part of the cycle itself.
+    assert!(!is_recursive("test.WellKnownTypes"));
+}
+
+#[test]
+fn test_max_nesting_depth() {
+    let pool = test_file_descriptor();
+    let max_nesting_depth =
+        |name: &str| pool.get_message_by_name(name).unwrap().max_nesting_depth();
+
+    assert_eq!(max_nesting_depth("test.Point"), Some(0));
+    assert_eq!(max_nesting_depth("test.Scalars"), Some(0));
+    // The map entry of `string_map` and its `Scalars` value are each a level of nesting.
+    assert_eq!(max_nesting_depth("test.ComplexType"), Some(2));
+    assert_eq!(max_nesting_depth("test.NestedScalarArrays"), Some(2));
+    assert_eq!(max_nesting_depth("test.TreeNode"), None);
+    assert_eq!(max_nesting_depth("google.protobuf.Value"), None);
+    // Contains a recursive type, without being part of the cycle itself.
+    assert_eq!(max_nesting_depth("test.WellKnownTypes"), None);
+}
+
+#[test]
+fn test_field_wire_type() {
+    let pool = test_file_descriptor();
+    let field = |message: &str, field: &str| {
+        pool.get_message_by_name(message)
+            .unwrap()
+            .get_field_by_name(field)
+            .unwrap()
+            .wire_type()
+    };
+
+    assert_eq!(field("test.Scalars", "int32"), WireType::Varint);
+    assert_eq!(field("test.Scalars", "double"), WireType::SixtyFourBit);
+    assert_eq!(field("test.Scalars", "float"), WireType::ThirtyTwoBit);
+    assert_eq!(field("test.Scalars", "string"), WireType::LengthDelimited);
+    assert_eq!(
+        field("test.ScalarArrays", "int32"),
+        WireType::LengthDelimited
+    );
+    assert_eq!(
+        field("test.ScalarArrays", "string"),
+        WireType::LengthDelimited
+    );
+    assert_eq!(
+        field("test2.UnpackedScalarArray", "unpacked_double"),
+        WireType::SixtyFourBit
+    );
+    assert_eq!(
+        field("test.ComplexType", "int_map"),
+        WireType::LengthDelimited
+    );
+    assert_eq!(
+        field("test2.ContainsGroup", "requiredgroup"),
+        WireType::StartGroup
+    );
+
+    let extension = pool
+        .get_extension_by_name("my.package2.MyMessage.in_extendee")
+        .unwrap();
+    assert_eq!(extension.wire_type(), WireType::Varint);
+}
+
+#[test]
+fn test_reserved_ranges() {
+    let pool = test_file_descriptor();
+
+    let message_desc = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert_eq!(
+        message_desc.reserved_ranges().collect::<Vec<_>>(),
+        vec![2..3, 15..16, 9..12]
+    );
+
+    let enum_desc = pool.get_enum_by_name("my.package.MyEnum").unwrap();
+    assert_eq!(
+        enum_desc.reserved_ranges().collect::<Vec<_>>(),
+        vec![-2..=-2, 15..=15, 9..=11]
+    );
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+fn test_descriptor_hash_identity() {
+    let pool = test_file_descriptor();
+    let other_pool = DescriptorPool::decode(DESCRIPTOR_POOL_BYTES).unwrap();
+
+    let scalars = pool.get_message_by_name("test.Scalars").unwrap();
+    let complex = pool.get_message_by_name("test.ComplexType").unwrap();
+    let other_scalars = other_pool.get_message_by_name("test.Scalars").unwrap();
+
+    let mut messages = HashMap::new();
+    messages.insert(scalars.clone(), 1);
+    messages.insert(complex.clone(), 2);
+    messages.insert(other_scalars.clone(), 3);
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[&Scalars::default().descriptor()], 1);
+    assert_eq!(messages[&complex], 2);
+    assert_eq!(messages[&other_scalars], 3);
+
+    let mut fields = HashMap::new();
+    for field in scalars.fields() {
+        fields.insert(field.clone(), field.name().to_owned());
+    }
+    assert_eq!(fields.len(), scalars.fields().len());
+    assert_eq!(
+        fields[&scalars.get_field_by_name("int32").unwrap()],
+        "int32"
+    );
+    assert!(!fields.contains_key(&other_scalars.get_field_by_name("int32").unwrap()));
+
+    let my_enum = pool.get_enum_by_name("test.ComplexType.MyEnum").unwrap();
+    let mut enums = HashMap::new();
+    enums.insert(my_enum.clone(), ());
+    assert!(enums.contains_key(
+        complex
+            .get_field_by_name("my_enum")
+            .unwrap()
+            .kind()
+            .as_enum()
+            .unwrap()
+    ));
+    assert!(!enums.contains_key(
+        &other_pool
+            .get_enum_by_name("test.ComplexType.MyEnum")
+            .unwrap()
+    ));
+}
+
 #[test]
 fn test_get_extension() {
     let file_descriptor_set = test_file_descriptor()
@@ -5,8 +5,11 @@ use std::{
 };
 
 use proptest::{prelude::*, test_runner::TestCaseError};
-use prost::Message;
-use prost_reflect::{DeserializeOptions, DynamicMessage, ReflectMessage, SerializeOptions};
+use prost::{bytes::Bytes, Message};
+use prost_reflect::{
+    BytesEncoding, DescriptorPool, DeserializeOptions, DynamicMessage, Kind, ReflectMessage,
+    SerializeOptions, Value,
+};
 use prost_types::FileDescriptorSet;
 use serde_json::json;
 
@@ -14,7 +17,7 @@ use crate::{
     arbitrary,
     proto::{
         contains_group, message_with_oneof, ComplexType, ContainsGroup, MessageWithAliasedEnum,
-        MessageWithOneof, Point, ScalarArrays, Scalars, WellKnownTypes,
+        MessageWithOneof, OutOfOrderFields, Point, ScalarArrays, Scalars, WellKnownTypes,
     },
     test_file_descriptor,
 };
@@ -333,6 +336,65 @@ fn serialize_no_stringify_64_bit_integers() {
     );
 }
 
+#[test]
+fn serialize_no_stringify_64_bit_integers_large_values() {
+    let value = to_json_with_options(
+        &Scalars {
+            int64: -(1 << 53),
+            uint64: 1 << 53,
+            sint64: (1 << 53) + 1,
+            fixed64: u64::MAX,
+            sfixed64: i64::MIN,
+            ..Default::default()
+        },
+        &SerializeOptions::new().stringify_64_bit_integers(false),
+    );
+
+    assert_eq!(
+        value,
+        json!({
+            "int64": -9007199254740992i64,
+            "uint64": 9007199254740992u64,
+            "sint64": "9007199254740993",
+            "fixed64": "18446744073709551615",
+            "sfixed64": "-9223372036854775808",
+        })
+    );
+}
+
+#[test]
+fn serialize_bytes_encoding() {
+    let message = Scalars {
+        bytes: b"i\xa6\xbem\xb6\xffX".to_vec(),
+        ..Default::default()
+    };
+    let wkt = WellKnownTypes {
+        bytes: Some(vec![0xfb, 0xff]),
+        ..Default::default()
+    };
+
+    let cases = [
+        (BytesEncoding::StandardBase64, "aaa+bbb/WA==", "+/8="),
+        (BytesEncoding::UrlSafeBase64, "aaa-bbb_WA", "-_8"),
+        (BytesEncoding::Hex, "69a6be6db6ff58", "fbff"),
+    ];
+    for (encoding, expected, expected_wkt) in cases {
+        let options = SerializeOptions::new().bytes_encoding(encoding);
+        assert_eq!(
+            to_json_with_options(&message, &options),
+            json!({ "bytes": expected })
+        );
+        assert_eq!(
+            to_json_with_options(&wkt, &options),
+            json!({ "bytes": expected_wkt })
+        );
+        if encoding != BytesEncoding::Hex {
+            let roundtrip: Scalars = from_json(json!({ "bytes": expected }), "test.Scalars");
+            assert_eq!(roundtrip, message);
+        }
+    }
+}
+
 #[test]
 fn serialize_use_proto_field_name() {
     let value = to_json_with_options(
@@ -522,6 +584,92 @@ fn deserialize_scalars_float_extrema() {
     assert!(nan.double.is_nan());
 }
 
+#[test]
+fn serialize_non_finite_float_wrappers() {
+    let pool = DescriptorPool::global();
+    let serialize = |name: &str, value: Value, reject: bool| {
+        let desc = pool
+            .get_message_by_name(&format!("google.protobuf.{}", name))
+            .unwrap();
+        let mut message = DynamicMessage::new(desc);
+        message.set_field_by_name("value", value);
+        let options = SerializeOptions::new().reject_non_finite_floats(reject);
+        message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .map_err(|err| err.to_string())
+    };
+
+    assert_eq!(
+        serialize("DoubleValue", Value::F64(f64::NAN), false),
+        Ok(json!("NaN"))
+    );
+    assert_eq!(
+        serialize("FloatValue", Value::F32(f32::NEG_INFINITY), false),
+        Ok(json!("-Infinity"))
+    );
+    assert_eq!(
+        serialize("DoubleValue", Value::F64(f64::INFINITY), true),
+        Err("cannot serialize non-finite floating-point value 'Infinity'".to_owned())
+    );
+    assert_eq!(
+        serialize("FloatValue", Value::F32(f32::NAN), true),
+        Err("cannot serialize non-finite floating-point value 'NaN'".to_owned())
+    );
+}
+
+#[test]
+fn serialize_reject_non_finite_floats() {
+    let options = SerializeOptions::new().reject_non_finite_floats(true);
+    let serialize = |message: DynamicMessage| {
+        message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .map_err(|err| err.to_string())
+    };
+
+    assert_eq!(
+        serialize(
+            Scalars {
+                float: 1.5,
+                double: -2.5,
+                ..Default::default()
+            }
+            .transcode_to_dynamic()
+        ),
+        Ok(json!({ "float": 1.5, "double": -2.5 }))
+    );
+    assert_eq!(
+        serialize(
+            Scalars {
+                float: f32::INFINITY,
+                ..Default::default()
+            }
+            .transcode_to_dynamic()
+        ),
+        Err("cannot serialize non-finite floating-point value 'Infinity'".to_owned())
+    );
+    assert_eq!(
+        serialize(
+            ScalarArrays {
+                double: vec![0.0, f64::NEG_INFINITY],
+                ..Default::default()
+            }
+            .transcode_to_dynamic()
+        ),
+        Err("cannot serialize non-finite floating-point value '-Infinity'".to_owned())
+    );
+
+    let wkt = WellKnownTypes {
+        float: Some(f32::NAN),
+        double: Some(f64::NAN),
+        ..Default::default()
+    };
+    assert_eq!(to_json(&wkt), json!({ "float": "NaN", "double": "NaN" }));
+    assert_eq!(
+        serialize(wkt.transcode_to_dynamic()),
+        Err("cannot serialize non-finite floating-point value 'NaN'".to_owned())
+    );
+}
+
 #[test]
 fn deserialize_scalars_empty() {
     let value: Scalars = from_json(json!({}), "test.Scalars");
@@ -644,6 +792,185 @@ fn deserialize_aliased_enum() {
     assert_eq!(value2, MessageWithAliasedEnum { aliased: 1 },);
 }
 
+#[test]
+fn deserialize_closed_enum_number() {
+    let pool = test_file_descriptor();
+    let proto2_desc = pool.get_message_by_name("test2.DefaultValues").unwrap();
+    assert!(proto2_desc
+        .get_field_by_name("enum")
+        .unwrap()
+        .kind()
+        .as_enum()
+        .unwrap()
+        .is_closed());
+
+    let message = DynamicMessage::deserialize(proto2_desc.clone(), json!({ "enum": 3 })).unwrap();
+    assert_eq!(
+        message.get_field_by_name("enum").unwrap().as_enum_number(),
+        Some(3)
+    );
+    let err = DynamicMessage::deserialize(proto2_desc, json!({ "enum": 4 })).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unrecognized number 4 for closed enum 'test2.Proto2Enum'"
+    );
+
+    let proto3_desc = pool.get_message_by_name("test.ComplexType").unwrap();
+    assert!(!proto3_desc
+        .get_field_by_name("optional_enum")
+        .unwrap()
+        .kind()
+        .as_enum()
+        .unwrap()
+        .is_closed());
+    let message = DynamicMessage::deserialize(proto3_desc, json!({ "optionalEnum": 42 })).unwrap();
+    assert_eq!(
+        message
+            .get_field_by_name("optional_enum")
+            .unwrap()
+            .as_enum_number(),
+        Some(42)
+    );
+}
+
+#[test]
+fn deserialize_from_reader() {
+    let json = br#"{
+        "nested": { "int32": 3, "string": "hello" },
+        "intMap": { "1": { "bool": true } },
+        "myEnum": ["FOO", "BAR"]
+    }"#;
+
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap();
+    let mut deserializer = serde_json::Deserializer::from_reader(json.as_ref());
+    let message = DynamicMessage::deserialize(desc, &mut deserializer).unwrap();
+    deserializer.end().unwrap();
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 3,
+                string: "hello".to_owned(),
+                ..Default::default()
+            }),
+            int_map: HashMap::from_iter([(
+                1,
+                Scalars {
+                    bool: true,
+                    ..Default::default()
+                }
+            )]),
+            my_enum: vec![1, 3],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn value_to_json_wrappers() {
+    let pool = DescriptorPool::global();
+    let wrapper = |name: &str, value: Value| {
+        let desc = pool
+            .get_message_by_name(&format!("google.protobuf.{}", name))
+            .unwrap();
+        let mut message = DynamicMessage::new(desc.clone());
+        message.set_field_by_name("value", value);
+        Value::Message(message)
+            .to_json(&Kind::Message(desc))
+            .unwrap()
+    };
+
+    assert_eq!(wrapper("DoubleValue", Value::F64(1.5)), json!(1.5));
+    assert_eq!(wrapper("FloatValue", Value::F32(-2.5)), json!(-2.5));
+    assert_eq!(wrapper("Int64Value", Value::I64(-3)), json!("-3"));
+    assert_eq!(wrapper("UInt64Value", Value::U64(4)), json!("4"));
+    assert_eq!(wrapper("Int32Value", Value::I32(-5)), json!(-5));
+    assert_eq!(wrapper("UInt32Value", Value::U32(6)), json!(6));
+    assert_eq!(wrapper("BoolValue", Value::Bool(true)), json!(true));
+    assert_eq!(
+        wrapper("StringValue", Value::String("hello".to_owned())),
+        json!("hello")
+    );
+    assert_eq!(
+        wrapper("BytesValue", Value::Bytes(Bytes::from_static(b"hi"))),
+        json!("aGk=")
+    );
+
+    let empty_desc = pool.get_message_by_name("google.protobuf.Empty").unwrap();
+    assert_eq!(
+        Value::Message(DynamicMessage::new(empty_desc.clone()))
+            .to_json(&Kind::Message(empty_desc))
+            .unwrap(),
+        json!({})
+    );
+
+    assert_eq!(
+        Value::List(vec![Value::U64(1), Value::U64(2)])
+            .to_json(&Kind::Uint64)
+            .unwrap(),
+        json!(["1", "2"])
+    );
+}
+
+#[test]
+fn serialize_to_json_string() {
+    let message = Point {
+        latitude: 1,
+        longitude: -2,
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        message.to_json_string().unwrap(),
+        r#"{"latitude":1,"longitude":-2}"#
+    );
+    assert_eq!(
+        message.to_json_string_pretty().unwrap(),
+        "{\n  \"latitude\": 1,\n  \"longitude\": -2\n}"
+    );
+    assert_eq!(
+        Point::default()
+            .transcode_to_dynamic()
+            .to_json_string()
+            .unwrap(),
+        "{}"
+    );
+}
+
+#[test]
+fn field_mask_paths() {
+    let mask = DynamicMessage::from_field_mask_paths(["foo_bar", "baz.qux_quux"]);
+    assert_eq!(mask.descriptor().full_name(), "google.protobuf.FieldMask");
+    assert_eq!(
+        mask.field_mask_paths(),
+        Some(vec!["foo_bar".to_owned(), "baz.qux_quux".to_owned()])
+    );
+    assert_eq!(
+        serde_json::to_value(&mask).unwrap(),
+        json!("fooBar,baz.quxQuux")
+    );
+
+    let deserialized =
+        DynamicMessage::deserialize(mask.descriptor(), json!("fooBar,baz.quxQuux")).unwrap();
+    assert_eq!(deserialized, mask);
+    assert_eq!(
+        deserialized.field_mask_paths(),
+        Some(vec!["foo_bar".to_owned(), "baz.qux_quux".to_owned()])
+    );
+
+    let empty = DynamicMessage::from_field_mask_paths(Vec::<String>::new());
+    assert_eq!(empty.field_mask_paths(), Some(vec![]));
+    assert_eq!(serde_json::to_value(&empty).unwrap(), json!(""));
+
+    assert_eq!(
+        Point::default().transcode_to_dynamic().field_mask_paths(),
+        None
+    );
+}
+
 #[test]
 fn deserialize_array() {
     let value: ScalarArrays = from_json(
@@ -908,6 +1235,174 @@ fn deserialize_any() {
     );
 }
 
+#[test]
+fn serialize_any_with_type_url_prefix() {
+    let pool = test_file_descriptor();
+    let any = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic()
+    .pack_into_any(Some("example.com"));
+
+    let err = serde_json::to_value(&any).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unsupported type url 'example.com/test.Point'"
+    );
+
+    let options = SerializeOptions::new().type_url_prefix("example.com/");
+    let json = any
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "@type": "example.com/test.Point",
+            "latitude": 1,
+            "longitude": 2,
+        })
+    );
+
+    // A prefix which only matches part of the domain is not recognized.
+    let options = SerializeOptions::new().type_url_prefix("example");
+    assert!(any
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .is_err());
+
+    let options = DeserializeOptions::new().any_resolver(move |type_url| {
+        pool.get_message_by_name(type_url.strip_prefix("example.com/")?)
+    });
+    let roundtripped =
+        DynamicMessage::deserialize_with_options(any.descriptor(), json, &options).unwrap();
+    assert_eq!(roundtripped, any);
+}
+
+#[test]
+fn deserialize_field_aliases() {
+    let scalars = Scalars::default().descriptor();
+    let complex = ComplexType::default().descriptor();
+    let field = |message: &prost_reflect::MessageDescriptor, name: &str| {
+        message.get_field_by_name(name).unwrap()
+    };
+
+    let options = DeserializeOptions::new()
+        .field_aliases([
+            ("oldInt32", field(&scalars, "int32")),
+            ("legacy_double", field(&scalars, "double")),
+            ("oldInt32", field(&scalars, "int32")),
+        ])
+        .unwrap()
+        .field_aliases([("inner", field(&complex, "nested"))])
+        .unwrap();
+
+    let value: ComplexType = from_json_with_options(
+        json!({
+            "inner": {
+                "oldInt32": 5,
+                "legacy_double": 1.5,
+            },
+        }),
+        "test.ComplexType",
+        &options,
+    );
+    assert_eq!(
+        value,
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 5,
+                double: 1.5,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    );
+
+    // Aliases only apply to the message they were defined for.
+    let err = DynamicMessage::deserialize_with_options(
+        complex.clone(),
+        json!({ "oldInt32": 5 }),
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "unrecognized field name 'oldInt32'");
+
+    let err = DeserializeOptions::new()
+        .field_aliases([("stringMap", field(&complex, "nested"))])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "alias 'stringMap' conflicts with an existing field name of message 'test.ComplexType'"
+    );
+    assert_eq!(err.alias(), "stringMap");
+    assert_eq!(err.message_name(), "test.ComplexType");
+    assert!(DeserializeOptions::new()
+        .field_aliases([("int64", field(&scalars, "int32"))])
+        .is_err());
+    assert!(options
+        .field_aliases([("oldInt32", field(&scalars, "int64"))])
+        .is_err());
+}
+
+#[test]
+fn deserialize_any_with_resolver() {
+    let test_pool = test_file_descriptor();
+    let mut any_pool = DescriptorPool::new();
+    any_pool
+        .add_file_descriptor_proto(
+            test_pool
+                .get_file_by_name("google/protobuf/any.proto")
+                .unwrap()
+                .file_descriptor_proto()
+                .clone(),
+        )
+        .unwrap();
+    let any_desc = any_pool.get_message_by_name("google.protobuf.Any").unwrap();
+
+    let json = json!({
+        "@type": "example.com/test.Point",
+        "longitude": 1,
+        "latitude": 2,
+    });
+
+    let err = DynamicMessage::deserialize(any_desc.clone(), json.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unsupported type url 'example.com/test.Point'"
+    );
+
+    let options = DeserializeOptions::new().any_resolver(move |type_url| {
+        test_pool.get_message_by_name(type_url.strip_prefix("example.com/")?)
+    });
+    let value: prost_types::Any =
+        DynamicMessage::deserialize_with_options(any_desc, json, &options)
+            .unwrap()
+            .transcode_to()
+            .unwrap();
+    assert_eq!(
+        value,
+        prost_types::Any {
+            type_url: "example.com/test.Point".to_owned(),
+            value: Point {
+                longitude: 1,
+                latitude: 2,
+            }
+            .encode_to_vec(),
+        }
+    );
+
+    let err = DynamicMessage::deserialize_with_options(
+        prost_types::Any::default().descriptor(),
+        json!({ "@type": "type.googleapis.com/test.Point" }),
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "message 'type.googleapis.com/test.Point' not found"
+    );
+}
+
 #[test]
 fn deserialize_any_buffer_fields() {
     let value: prost_types::Any = from_json(
@@ -1217,6 +1712,67 @@ fn deserialize_timestamp_seconds_out_of_range() {
         from_json(json!("0000-01-01T00:00:00Z"), "google.protobuf.Timestamp");
 }
 
+#[test]
+fn struct_json_conversions() {
+    let json = json!({
+        "name": "foo",
+        "nested": { "list": [1.5, true, null, "bar"] },
+    });
+
+    let message = DynamicMessage::struct_from_json(json.clone()).unwrap();
+    assert_eq!(message.descriptor().full_name(), "google.protobuf.Struct");
+    assert_eq!(message.to_json_for_struct(), Some(json));
+
+    let value = DynamicMessage::value_from_json(json!("hello")).unwrap();
+    assert_eq!(
+        value.transcode_to::<prost_types::Value>().unwrap(),
+        prost_types::Value {
+            kind: Some(prost_types::value::Kind::StringValue("hello".to_owned())),
+        }
+    );
+    assert_eq!(value.to_json_for_struct(), Some(json!("hello")));
+
+    let list = DynamicMessage::list_value_from_json(json!([1.0, {}])).unwrap();
+    assert_eq!(list.to_json_for_struct(), Some(json!([1.0, {}])));
+
+    assert!(DynamicMessage::struct_from_json(json!([])).is_err());
+    assert!(DynamicMessage::list_value_from_json(json!({})).is_err());
+    assert_eq!(
+        Point::default().transcode_to_dynamic().to_json_for_struct(),
+        None
+    );
+}
+
+#[test]
+fn serialize_preserve_proto_order() {
+    let message = OutOfOrderFields {
+        third: "3".to_owned(),
+        first: 1,
+        second: true,
+    };
+
+    assert_eq!(
+        to_json_string_with_options(&message, &Default::default()),
+        r#"{"first":1,"second":true,"third":"3"}"#
+    );
+    assert_eq!(
+        to_json_string_with_options(
+            &message,
+            &SerializeOptions::new().preserve_proto_order(true)
+        ),
+        r#"{"third":"3","first":1,"second":true}"#
+    );
+    assert_eq!(
+        to_json_string_with_options(
+            &OutOfOrderFields::default(),
+            &SerializeOptions::new()
+                .preserve_proto_order(true)
+                .skip_default_fields(false)
+        ),
+        r#"{"third":"","first":0,"second":false}"#
+    );
+}
+
 #[test]
 fn roundtrip_group() {
     roundtrip_json(&ContainsGroup {
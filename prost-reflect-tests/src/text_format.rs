@@ -6,7 +6,10 @@ use std::{
 
 use proptest::prelude::*;
 use prost::Message;
-use prost_reflect::{text_format::FormatOptions, DynamicMessage, ReflectMessage, Value};
+use prost_reflect::{
+    text_format::{EscapeStyle, FormatOptions},
+    DynamicMessage, ReflectMessage, Value,
+};
 
 use crate::{
     proto::{
@@ -75,13 +78,63 @@ fn fmt_scalars_float_extrema() {
         neg_inf.to_text_format_with_options(&FormatOptions::new().pretty(true)),
         "double: -inf\nfloat: -inf"
     );
-    assert_eq!(nan.to_text_format(), "double:NaN,float:NaN");
+    assert_eq!(nan.to_text_format(), "double:nan,float:nan");
     assert_eq!(
         nan.to_text_format_with_options(&FormatOptions::new().pretty(true)),
-        "double: NaN\nfloat: NaN"
+        "double: nan\nfloat: nan"
     );
 }
 
+#[test]
+fn fmt_scalars_float_shortest_roundtrip() {
+    let cases: &[(f64, f32, &str)] = &[
+        (0.1, 0.1, "double:0.1,float:0.1"),
+        (1.0, 1.0, "double:1.0,float:1.0"),
+        (-2.5, -2.5, "double:-2.5,float:-2.5"),
+        (
+            1e15,
+            1e15,
+            "double:1000000000000000.0,float:1000000000000000.0",
+        ),
+        (1e16, 1e16, "double:1e16,float:1e16"),
+        (0.0001, 0.0001, "double:0.0001,float:0.0001"),
+        (0.00001, 0.00001, "double:1e-5,float:1e-5"),
+        (
+            f64::MAX,
+            f32::MAX,
+            "double:1.7976931348623157e308,float:3.4028235e38",
+        ),
+        (
+            f64::MIN_POSITIVE,
+            f32::MIN_POSITIVE,
+            "double:2.2250738585072014e-308,float:1.1754944e-38",
+        ),
+        (5e-324, 1e-45, "double:5e-324,float:1e-45"),
+        (-5e-324, -1e-45, "double:-5e-324,float:-1e-45"),
+        (
+            f64::from(0.1f32),
+            0.1,
+            "double:0.10000000149011612,float:0.1",
+        ),
+    ];
+
+    for &(double, float, expected) in cases {
+        let value = Scalars {
+            double,
+            float,
+            ..Default::default()
+        }
+        .transcode_to_dynamic();
+
+        let text = value.to_text_format();
+        assert_eq!(text, expected);
+
+        let parsed: Scalars = from_text(&text);
+        assert_eq!(parsed.double.to_bits(), double.to_bits());
+        assert_eq!(parsed.float.to_bits(), float.to_bits());
+    }
+}
+
 #[test]
 fn fmt_scalars_default() {
     let value = Scalars::default().transcode_to_dynamic();
@@ -156,6 +209,21 @@ fn fmt_complex_type() {
     assert_eq!(value.to_text_format_with_options(&FormatOptions::new().pretty(true)), "string_map: [{\n  key: \"1\"\n  value {\n    double: 1.1\n    float: 2.2\n    int32: 3\n  }\n}]\nint_map: [{\n  key: 3\n  value {\n    sint32: 7\n    sint64: 8\n    fixed32: 9\n  }\n}]\nnested {\n  sfixed32: 11\n  sfixed64: 12\n  bool: true\n  string: \"5\"\n  bytes: \"6\"\n}\nmy_enum: [DEFAULT, FOO, 2, BAR, NEG]\noptional_enum: FOO");
 }
 
+#[test]
+fn fmt_sorted_map_keys() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter(
+            ["b", "a", "B", "10", "9"].map(|key| (key.to_owned(), Scalars::default())),
+        ),
+        int_map: HashMap::from_iter([10, -1, 2].map(|key| (key, Scalars::default()))),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let options = FormatOptions::new().sort_map_keys(true);
+    assert_eq!(value.to_text_format_with_options(&options), "string_map:[{key:\"10\",value{}},{key:\"9\",value{}},{key:\"B\",value{}},{key:\"a\",value{}},{key:\"b\",value{}}],int_map:[{key:-1,value{}},{key:2,value{}},{key:10,value{}}]");
+}
+
 #[test]
 fn fmt_well_known_types() {
     let value = WellKnownTypes {
@@ -206,6 +274,27 @@ fn fmt_well_known_types() {
     assert_eq!(value.to_text_format_with_options(&FormatOptions::new().pretty(true)), "timestamp {\n  seconds: 63108020\n  nanos: 21000000\n}\nduration {\n  seconds: 1\n  nanos: 340012\n}\nstruct {\n  fields: [{\n    key: \"number\"\n    value {\n      number_value: 42.0\n    }\n  }]\n}\nfloat {\n  value: 42.1\n}\ndouble {\n  value: 12.4\n}\nint32 {\n  value: 1\n}\nint64 {\n  value: -2\n}\nuint32 {\n  value: 3\n}\nuint64 {\n  value: 4\n}\nbool {}\nstring {\n  value: \"hello\"\n}\nbytes {\n  value: \"hello\"\n}\nmask {\n  paths: [\"field_one\", \"field_two.b.d\"]\n}\nlist {\n  values: [{\n    string_value: \"foo\"\n  }, {\n    bool_value: false\n  }]\n}\nempty {}");
 }
 
+#[test]
+fn fmt_header_directives() {
+    let value = Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic();
+
+    let options = FormatOptions::new().header_directives(true);
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        "# proto-file: test.proto\n# proto-message: test.Point\nlatitude:1,longitude:2"
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&options.pretty(true)),
+        "# proto-file: test.proto\n# proto-message: test.Point\nlatitude: 1\nlongitude: 2"
+    );
+    assert_eq!(value.to_text_format(), "latitude:1,longitude:2");
+    assert_eq!(value.to_string(), "latitude:1,longitude:2");
+}
+
 #[test]
 fn fmt_empty() {
     let value = ().transcode_to_dynamic();
@@ -238,6 +327,41 @@ fn fmt_any() {
     );
 }
 
+#[test]
+fn fmt_any_type_url_prefix() {
+    let value = transcode_any(&prost_types::Any {
+        type_url: "example.com/test.Point".to_owned(),
+        value: Point {
+            longitude: 1,
+            latitude: 2,
+        }
+        .encode_to_vec(),
+    });
+
+    assert_eq!(
+        value.to_text_format(),
+        r#"type_url:"example.com/test.Point",value:"\010\002\020\001""#
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().type_url_prefix("example.com")),
+        "[example.com/test.Point]{latitude:2,longitude:1}"
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().type_url_prefix("example.com/")),
+        "[example.com/test.Point]{latitude:2,longitude:1}"
+    );
+
+    // The standard prefixes are still recognized.
+    let value = transcode_any(&prost_types::Any {
+        type_url: "type.googleapis.com/test.Point".to_owned(),
+        value: vec![],
+    });
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().type_url_prefix("example.com")),
+        "[type.googleapis.com/test.Point]{}"
+    );
+}
+
 #[test]
 fn fmt_any_wkt() {
     let value = transcode_any(&prost_types::Any {
@@ -320,6 +444,318 @@ fn fmt_any_invalid_bytes() {
     );
 }
 
+#[test]
+fn fmt_any_mark_unresolved() {
+    let options = FormatOptions::new().mark_unresolved_any(true);
+
+    let value = transcode_any(&prost_types::Any {
+        type_url: "type.googleapis.com/NotFound".to_owned(),
+        value: b"\x08\x01".to_vec(),
+    });
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        "[UNRESOLVED type.googleapis.com/NotFound]{}",
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&options.clone().pretty(true)),
+        "[UNRESOLVED type.googleapis.com/NotFound] {}",
+    );
+
+    let value = transcode_any(&prost_types::Any {
+        type_url: "type.googleapis.com/google.protobuf.Empty".to_owned(),
+        value: b"hello".to_vec(),
+    });
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        "[UNRESOLVED type.googleapis.com/google.protobuf.Empty]{}",
+    );
+
+    let value = transcode_any(&prost_types::Any {
+        type_url: "type.googleapis.com/google.protobuf.Empty".to_owned(),
+        value: vec![],
+    });
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        "[type.googleapis.com/google.protobuf.Empty]{}",
+    );
+
+    let value = transcode_any(&prost_types::Any {
+        type_url: "type.googleapis.com/NotFound".to_owned(),
+        value: vec![],
+    });
+    assert_eq!(
+        value.to_text_format_with_options(&options.clone().expand_any(false)),
+        "type_url:\"type.googleapis.com/NotFound\"",
+    );
+
+    let value = transcode_any(&prost_types::Any::default());
+    assert_eq!(value.to_text_format_with_options(&options), "");
+}
+
+#[test]
+fn fmt_any_no_expand() {
+    let value = transcode_any(&prost_types::Any {
+        type_url: "type.googleapis.com/test.Point".to_owned(),
+        value: Point {
+            latitude: 1,
+            longitude: -1,
+        }
+        .encode_to_vec(),
+    });
+
+    let options = FormatOptions::new().expand_any(false);
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        r#"type_url:"type.googleapis.com/test.Point",value:"\010\001\020\377\377\377\377\377\377\377\377\377\001""#,
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&options.clone().pretty(true)),
+        "type_url: \"type.googleapis.com/test.Point\"\nvalue: \"\\010\\001\\020\\377\\377\\377\\377\\377\\377\\377\\377\\377\\001\"",
+    );
+
+    let parsed = DynamicMessage::parse_text_format(
+        value.descriptor(),
+        &value.to_text_format_with_options(&options),
+    )
+    .unwrap();
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn fmt_max_string_length() {
+    let value = Scalars {
+        string: "aaaé".to_owned(),
+        bytes: b"\x00\x01\x02".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().max_string_length(4)),
+        r#"string:"aaa\303"...(5 bytes),bytes:"\000\001\002""#
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::buf_compatible().max_string_length(4)),
+        "string: \"aaa\"...(5 bytes)\nbytes: \"\\x00\\x01\\x02\"\n"
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().max_string_length(2)),
+        r#"string:"aa"...(5 bytes),bytes:"\000\001"...(3 bytes)"#
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().max_string_length(5)),
+        value.to_text_format()
+    );
+}
+
+#[test]
+fn fmt_space_separated_fields() {
+    let value = ComplexType {
+        nested: Some(Scalars {
+            int32: 1,
+            bool: true,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let text =
+        value.to_text_format_with_options(&FormatOptions::new().space_separated_fields(true));
+    assert_eq!(
+        text,
+        "nested{int32:1 bool:true} my_enum:[FOO,BAR] optional_enum:FOO"
+    );
+    assert_eq!(
+        DynamicMessage::parse_text_format(value.descriptor(), &text).unwrap(),
+        value
+    );
+
+    assert_eq!(
+        value.to_text_format_with_options(
+            &FormatOptions::new()
+                .pretty(true)
+                .space_separated_fields(true)
+        ),
+        value.to_text_format_with_options(&FormatOptions::new().pretty(true))
+    );
+}
+
+#[test]
+fn fmt_field_number_comments() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                int32: 1,
+                ..Default::default()
+            },
+        )]),
+        my_enum: vec![1, 3],
+        optional_enum: 3,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let options = FormatOptions::new()
+        .pretty(true)
+        .field_number_comments(true);
+    let text = value.to_text_format_with_options(&options);
+    assert_eq!(
+        text,
+        "string_map: [{\n  key: \"a\"\n  value {\n    int32: 1  # 3\n  }\n}]  # 1\nmy_enum: [FOO, BAR]  # 4\noptional_enum: BAR  # 5"
+    );
+    assert_eq!(
+        DynamicMessage::parse_text_format(value.descriptor(), &text).unwrap(),
+        value
+    );
+
+    // No effect in compact mode.
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().field_number_comments(true)),
+        value.to_text_format()
+    );
+
+    let buf_options = FormatOptions::buf_compatible().field_number_comments(true);
+    assert_eq!(
+        value.to_text_format_with_options(&buf_options),
+        "string_map: {\n  key: \"a\"\n  value: {\n    int32: 1  # 3\n  }\n}  # 1\nmy_enum: FOO  # 4\nmy_enum: BAR  # 4\noptional_enum: BAR  # 5\n"
+    );
+}
+
+#[test]
+fn fmt_expand_repeated_fields() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter([(
+            "1".to_owned(),
+            Scalars {
+                int32: 3,
+                ..Default::default()
+            },
+        )]),
+        int_map: HashMap::from_iter([10, -1, 2].map(|key| (key, Scalars::default()))),
+        my_enum: vec![0, 1, -4],
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let options = FormatOptions::new()
+        .expand_repeated_fields(true)
+        .sort_map_keys(true);
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        "string_map:{key:\"1\",value{int32:3}},int_map:{key:-1,value{}},int_map:{key:2,value{}},int_map:{key:10,value{}},my_enum:DEFAULT,my_enum:FOO,my_enum:NEG,optional_enum:FOO"
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&options.clone().space_separated_fields(true)),
+        "string_map:{key:\"1\",value{int32:3}} int_map:{key:-1,value{}} int_map:{key:2,value{}} int_map:{key:10,value{}} my_enum:DEFAULT my_enum:FOO my_enum:NEG optional_enum:FOO"
+    );
+
+    let value = ScalarArrays {
+        int32: vec![5, -6],
+        string: vec!["a".to_owned(), "b".to_owned()],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let options = FormatOptions::new()
+        .pretty(true)
+        .expand_repeated_fields(true);
+    let text = value.to_text_format_with_options(&options);
+    assert_eq!(text, "int32: 5\nint32: -6\nstring: \"a\"\nstring: \"b\"");
+    assert_eq!(
+        DynamicMessage::parse_text_format(value.descriptor(), &text).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn fmt_string_escape_style() {
+    let value = Scalars {
+        string: "tab\t\"quote\" é".to_owned(),
+        bytes: b"\x00\x7f\x80\xff'".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        value.to_text_format(),
+        r#"string:"tab\t\"quote\" \303\251",bytes:"\000\177\200\377\'""#
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().escape_style(EscapeStyle::Hex)),
+        r#"string:"tab\t\"quote\" \xc3\xa9",bytes:"\x00\x7f\x80\xff\'""#
+    );
+    assert_eq!(
+        value.to_text_format_with_options(&FormatOptions::new().escape_style(EscapeStyle::Octal)),
+        value.to_text_format()
+    );
+
+    let parsed = DynamicMessage::parse_text_format(
+        value.descriptor(),
+        &value.to_text_format_with_options(&FormatOptions::new().escape_style(EscapeStyle::Hex)),
+    )
+    .unwrap();
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn fmt_write_text_format() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter([(
+            "hello".to_owned(),
+            Scalars {
+                string: "world".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    for options in [
+        FormatOptions::new(),
+        FormatOptions::new().pretty(true),
+        FormatOptions::buf_compatible(),
+    ] {
+        let mut buf = Vec::new();
+        value
+            .write_text_format_with_options(&mut buf, &options)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            value.to_text_format_with_options(&options)
+        );
+    }
+
+    let mut buf = Vec::new();
+    DynamicMessage::new(value.descriptor())
+        .write_text_format_with_options(&mut buf, &FormatOptions::buf_compatible())
+        .unwrap();
+    assert!(buf.is_empty());
+
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "broken",
+            ))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = value.write_text_format(FailingWriter).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+}
+
 #[test]
 fn fmt_group() {
     let value = ContainsGroup {
@@ -353,6 +789,39 @@ fn fmt_group() {
     );
 }
 
+#[test]
+fn fmt_use_field_numbers() {
+    let value = ContainsGroup {
+        requiredgroup: Some(contains_group::RequiredGroup {
+            a: "bar".to_owned(),
+            b: None,
+        }),
+        repeatedgroup: vec![contains_group::RepeatedGroup {
+            e: "hello".to_owned(),
+            f: Some(10),
+        }],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let options = FormatOptions::new().use_field_numbers(true);
+    assert_eq!(
+        value.to_text_format_with_options(&options),
+        "1{1:\"bar\"},3:[{1:\"hello\",2:10}]",
+    );
+
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    let mut value = DynamicMessage::new(message_desc.clone());
+    value.set_field_by_name("int", Value::I32(1));
+    value.set_extension(&message_desc.get_extension(113).unwrap(), Value::F64(2.5));
+    assert_eq!(
+        value.to_text_format_with_options(&options.pretty(true)),
+        "1: 1\n113: 2.5",
+    );
+}
+
 #[test]
 fn parse_group() {
     let value = ContainsGroup {
@@ -462,6 +931,22 @@ fn parse_aliased_enum() {
     assert_eq!(value2, MessageWithAliasedEnum { aliased: 1 },);
 }
 
+#[test]
+fn roundtrip_aliased_enum() {
+    let enum_desc = test_file_descriptor()
+        .get_enum_by_name("test.EnumWithAlias")
+        .unwrap();
+    for name in ["FOO", "BAR", "A", "B", "C", "TWO"] {
+        let number = enum_desc.get_value_by_name(name).unwrap().number();
+        let value: MessageWithAliasedEnum = from_text(&format!("aliased: {}", name));
+        assert_eq!(value, MessageWithAliasedEnum { aliased: number });
+
+        let text = value.transcode_to_dynamic().to_text_format();
+        let roundtripped: MessageWithAliasedEnum = from_text(&text);
+        assert_eq!(roundtripped, value);
+    }
+}
+
 #[test]
 fn parse_array() {
     let value: ScalarArrays = from_text("double: [1.1, 2f] , float: 3 ; float: inf");
@@ -737,6 +1222,103 @@ fn parse_error() {
     );
 }
 
+#[test]
+fn set_field_from_str() {
+    let mut message = DynamicMessage::new(Scalars::default().descriptor());
+    message.set_field_from_str("int32", "-5").unwrap();
+    message.set_field_from_str("uint64", "0x10").unwrap();
+    message.set_field_from_str("double", "1.5").unwrap();
+    message.set_field_from_str("float", "inf").unwrap();
+    message.set_field_from_str("bool", "true").unwrap();
+    message
+        .set_field_from_str("string", "'hello' \"world\"")
+        .unwrap();
+    message
+        .set_field_from_str("bytes", r#""\001\x02""#)
+        .unwrap();
+    assert_eq!(
+        message.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: -5,
+            uint64: 16,
+            double: 1.5,
+            float: f32::INFINITY,
+            bool: true,
+            string: "helloworld".to_owned(),
+            bytes: b"\x01\x02".to_vec(),
+            ..Default::default()
+        }
+    );
+
+    let mut message = DynamicMessage::new(ComplexType::default().descriptor());
+    message.set_field_from_str("optional_enum", "BAR").unwrap();
+    message.set_field_from_str("my_enum", "[FOO, -4]").unwrap();
+    message.set_field_from_str("my_enum", "3").unwrap();
+    message
+        .set_field_from_str("nested", "{ int32: 1 }")
+        .unwrap();
+    assert_eq!(
+        message.get_field_by_name("optional_enum").unwrap().as_ref(),
+        &Value::EnumNumber(3)
+    );
+    assert_eq!(
+        message.get_field_by_name("my_enum").unwrap().as_ref(),
+        &Value::List(vec![Value::EnumNumber(3)])
+    );
+    assert_eq!(
+        message
+            .get_field_by_name("nested")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .get_field_by_name("int32")
+            .unwrap()
+            .as_ref(),
+        &Value::I32(1)
+    );
+
+    let mut message = DynamicMessage::new(Scalars::default().descriptor());
+    message.set_field_from_str("int32", "1").unwrap();
+    let errors = [
+        ("int32", "1.5"),
+        ("int32", "\"1\""),
+        ("int32", "1 2"),
+        ("int32", ""),
+        ("uint32", "-1"),
+        ("bool", "yes"),
+        ("nonexistent", "1"),
+    ];
+    for (name, literal) in errors {
+        assert!(
+            message.set_field_from_str(name, literal).is_err(),
+            "{}: {}",
+            name,
+            literal
+        );
+    }
+    assert_eq!(
+        message
+            .set_field_from_str("int32", "1 2")
+            .unwrap_err()
+            .to_string(),
+        "expected end of input, but found '2'"
+    );
+    assert_eq!(
+        message
+            .set_field_from_str("nonexistent", "1")
+            .unwrap_err()
+            .to_string(),
+        "field 'nonexistent' not found for message 'test.Scalars'"
+    );
+    assert_eq!(
+        message.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 1,
+            ..Default::default()
+        }
+    );
+}
+
 #[test]
 fn duplicate_oneof_field() {
     let desc = test_file_descriptor()
@@ -757,6 +1339,129 @@ fn duplicate_oneof_field() {
     );
 }
 
+#[test]
+fn fmt_buf_compatible() {
+    let value = ComplexType {
+        string_map: HashMap::from_iter([
+            (
+                "b".to_owned(),
+                Scalars {
+                    int32: 2,
+                    ..Default::default()
+                },
+            ),
+            (
+                "a".to_owned(),
+                Scalars {
+                    double: 1e6,
+                    float: 0.5,
+                    string: "héllo\n\"".to_owned(),
+                    bytes: b"\x00\xff".to_vec(),
+                    ..Default::default()
+                },
+            ),
+        ]),
+        nested: Some(Scalars {
+            int64: -3,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, 5],
+        optional_enum: -4,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let text = value.to_text_format_with_options(&FormatOptions::buf_compatible());
+    assert_eq!(
+        text,
+        r#"string_map: {
+  key: "a"
+  value: {
+    double: 1e+06
+    float: 0.5
+    string: "héllo\n\""
+    bytes: "\x00\xff"
+  }
+}
+string_map: {
+  key: "b"
+  value: {
+    int32: 2
+  }
+}
+nested: {
+  int64: -3
+}
+my_enum: FOO
+my_enum: BAR
+my_enum: 5
+optional_enum: NEG
+"#
+    );
+    assert_eq!(
+        DynamicMessage::parse_text_format(value.descriptor(), &text).unwrap(),
+        value
+    );
+
+    let fmt_double = |double: f64| {
+        Scalars {
+            double,
+            ..Default::default()
+        }
+        .transcode_to_dynamic()
+        .to_text_format_with_options(&FormatOptions::buf_compatible())
+    };
+    assert_eq!(fmt_double(0.0), "");
+    assert_eq!(fmt_double(150.0), "double: 150\n");
+    assert_eq!(fmt_double(123456.0), "double: 123456\n");
+    assert_eq!(fmt_double(1234567.0), "double: 1.234567e+06\n");
+    assert_eq!(fmt_double(0.0001), "double: 0.0001\n");
+    assert_eq!(fmt_double(1.5e-7), "double: 1.5e-07\n");
+    assert_eq!(fmt_double(1e100), "double: 1e+100\n");
+    assert_eq!(fmt_double(f64::NEG_INFINITY), "double: -inf\n");
+}
+
+#[test]
+fn merge_text_format() {
+    let mut scalars = Scalars::default().transcode_to_dynamic();
+    scalars
+        .merge_text_format("int32: 1, string: 'first'")
+        .unwrap();
+    scalars
+        .merge_text_format("int32: 2, bool: true, int32: 3")
+        .unwrap();
+    assert_eq!(
+        scalars.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 3,
+            string: "first".to_owned(),
+            bool: true,
+            ..Default::default()
+        }
+    );
+
+    let mut arrays = ScalarArrays::default().transcode_to_dynamic();
+    arrays.merge_text_format("int32: [1, 2]").unwrap();
+    arrays.merge_text_format("int32: 3, int32: [4]").unwrap();
+    assert_eq!(
+        arrays.transcode_to::<ScalarArrays>().unwrap().int32,
+        vec![1, 2, 3, 4]
+    );
+
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.MessageWithOneof")
+        .unwrap();
+    let mut oneof = DynamicMessage::new(desc);
+    oneof
+        .merge_text_format("oneof_field_1: 'hello', oneof_field_2: 5")
+        .unwrap();
+    assert!(!oneof.has_field_by_name("oneof_field_1"));
+    assert_eq!(
+        oneof.get_field_by_name("oneof_field_2").unwrap().as_ref(),
+        &Value::I32(5)
+    );
+}
+
 proptest! {
     #![proptest_config(ProptestConfig {
         cases: 32,
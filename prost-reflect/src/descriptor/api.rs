@@ -1,6 +1,9 @@
 use std::{
     borrow::Cow,
-    fmt, iter,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
     ops::{Range, RangeInclusive},
     sync::Arc,
 };
@@ -11,16 +14,16 @@ use prost::{
     DecodeError, EncodeError, Message,
 };
 use prost_types::{
-    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, OneofDescriptorProto,
-    ServiceDescriptorProto,
+    field_options::CType, DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto,
+    FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto,
+    OneofDescriptorProto, ServiceDescriptorProto,
 };
 
 use crate::{
     descriptor::{
         error::DescriptorErrorKind,
         find_enum_proto, find_message_proto, tag, to_index,
-        types::{self, Options},
+        types::{self, DeprecatedOption, Options},
         Definition, DefinitionKind, DescriptorIndex, EnumDescriptorInner, EnumValueDescriptorInner,
         ExtensionDescriptorInner, FieldDescriptorInner, FileDescriptorInner, KindIndex,
         MessageDescriptorInner, MethodDescriptorInner, OneofDescriptorInner,
@@ -36,6 +39,7 @@ impl fmt::Debug for Syntax {
         match *self {
             Syntax::Proto2 => write!(f, "proto2"),
             Syntax::Proto3 => write!(f, "proto3"),
+            Syntax::Editions => write!(f, "editions"),
         }
     }
 }
@@ -108,29 +112,46 @@ impl Kind {
             Kind::String | Kind::Bytes | Kind::Message(_) => WireType::LengthDelimited,
         }
     }
+
+    /// Gets the name of this type as it is written in a `.proto` file, e.g. `int32`.
+    ///
+    /// For message and enum types, this is the full name of the type, e.g. `my.package.MyMessage`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, Kind};
+    /// assert_eq!(Kind::Sfixed64.proto_name(), "sfixed64");
+    ///
+    /// let message_desc = DescriptorPool::global().get_message_by_name("google.protobuf.Duration").unwrap();
+    /// assert_eq!(Kind::Message(message_desc).proto_name(), "google.protobuf.Duration");
+    /// ```
+    pub fn proto_name(&self) -> &str {
+        match self {
+            Kind::Double => "double",
+            Kind::Float => "float",
+            Kind::Int32 => "int32",
+            Kind::Int64 => "int64",
+            Kind::Uint32 => "uint32",
+            Kind::Uint64 => "uint64",
+            Kind::Sint32 => "sint32",
+            Kind::Sint64 => "sint64",
+            Kind::Fixed32 => "fixed32",
+            Kind::Fixed64 => "fixed64",
+            Kind::Sfixed32 => "sfixed32",
+            Kind::Sfixed64 => "sfixed64",
+            Kind::Bool => "bool",
+            Kind::String => "string",
+            Kind::Bytes => "bytes",
+            Kind::Message(m) => m.full_name(),
+            Kind::Enum(e) => e.full_name(),
+        }
+    }
 }
 
 impl fmt::Debug for Kind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Double => write!(f, "double"),
-            Self::Float => write!(f, "float"),
-            Self::Int32 => write!(f, "int32"),
-            Self::Int64 => write!(f, "int64"),
-            Self::Uint32 => write!(f, "uint32"),
-            Self::Uint64 => write!(f, "uint64"),
-            Self::Sint32 => write!(f, "sint32"),
-            Self::Sint64 => write!(f, "sint64"),
-            Self::Fixed32 => write!(f, "fixed32"),
-            Self::Fixed64 => write!(f, "fixed64"),
-            Self::Sfixed32 => write!(f, "sfixed32"),
-            Self::Sfixed64 => write!(f, "sfixed64"),
-            Self::Bool => write!(f, "bool"),
-            Self::String => write!(f, "string"),
-            Self::Bytes => write!(f, "bytes"),
-            Self::Message(m) => write!(f, "{}", m.full_name()),
-            Self::Enum(e) => write!(f, "{}", e.full_name()),
-        }
+        f.write_str(self.proto_name())
     }
 }
 
@@ -165,7 +186,7 @@ impl DescriptorPool {
     /// is a convenient way to generate it as part of your build.
     ///
     /// Unlike when using [`DescriptorPool::from_file_descriptor_set`], any extension options
-    /// defined in the file descriptors are preserved.
+    /// and features defined in the file descriptors are preserved.
     ///
     /// # Errors
     ///
@@ -212,6 +233,10 @@ impl DescriptorPool {
     ///
     /// Any duplicates of files already in the pool will be skipped. Note this may cause issues when trying to add two different versions of a file with the same name.
     ///
+    /// The [`FileDescriptorProto`] type from `prost-types` does not include the `edition` field or the `features`
+    /// options, so files with the `editions` syntax are treated as edition 2023 with the default features. Use
+    /// [`decode_file_descriptor_set()`][DescriptorPool::decode_file_descriptor_set] to preserve them.
+    ///
     /// # Errors
     ///
     /// Returns an error if any of the given file descriptor is invalid, for example if they reference
@@ -251,7 +276,7 @@ impl DescriptorPool {
     /// previously added to the pool.
     ///
     /// Unlike when using [`add_file_descriptor_proto()`][DescriptorPool::add_file_descriptor_proto], any extension options
-    /// defined in the file descriptor are preserved.
+    /// and features defined in the file descriptor are preserved.
     ///
     /// If the file is a duplicate of a file already in the pool, it will be skipped. Note this may cause issues when trying to add two different versions of a file with the same name.
     ///
@@ -278,7 +303,7 @@ impl DescriptorPool {
     /// is a convenient way to generate it as part of your build.
     ///
     /// Unlike when using [`add_file_descriptor_set()`][DescriptorPool::add_file_descriptor_set], any extension options
-    /// defined in the file descriptors are preserved.
+    /// and features defined in the file descriptors are preserved.
     ///
     /// Any duplicates of files already in the pool will be skipped. Note this may cause issues when trying to add two different versions of a file with the same name.
     ///
@@ -317,7 +342,17 @@ impl DescriptorPool {
         }
     }
 
+    /// Returns `true` if any file in this pool includes source code information.
+    ///
+    /// See [`FileDescriptor::has_source_info`] for more details.
+    pub fn has_source_info(&self) -> bool {
+        self.files().any(|file| file.has_source_info())
+    }
+
     /// Gets a iterator over the raw [`FileDescriptorProto`] instances wrapped by this [`DescriptorPool`].
+    ///
+    /// These do not include the `edition` field or any extension options or features. Use
+    /// [`encode()`][DescriptorPool::encode] to preserve them.
     pub fn file_descriptor_protos(
         &self,
     ) -> impl ExactSizeIterator<Item = &FileDescriptorProto> + '_ {
@@ -415,6 +450,9 @@ impl DescriptorPool {
     /// Gets an iterator over all message types defined in these protobuf files.
     ///
     /// The iterator includes nested messages defined in another message.
+    ///
+    /// The order is deterministic: files are visited in the order they were added to the pool, and the definitions
+    /// within each file are visited depth-first in declaration order.
     pub fn all_messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor> + '_ {
         indices(&self.inner.messages).map(|index| MessageDescriptor {
             pool: self.clone(),
@@ -425,6 +463,9 @@ impl DescriptorPool {
     /// Gets an iterator over all enum types defined in these protobuf files.
     ///
     /// The iterator includes nested enums defined in another message.
+    ///
+    /// The order is deterministic: files are visited in the order they were added to the pool, and the definitions
+    /// within each file are visited depth-first in declaration order.
     pub fn all_enums(&self) -> impl ExactSizeIterator<Item = EnumDescriptor> + '_ {
         indices(&self.inner.enums).map(|index| EnumDescriptor {
             pool: self.clone(),
@@ -435,6 +476,9 @@ impl DescriptorPool {
     /// Gets an iterator over all extension fields defined in these protobuf files.
     ///
     /// The iterator includes nested extension fields defined in another message.
+    ///
+    /// The order is deterministic: files are visited in the order they were added to the pool, and the definitions
+    /// within each file are visited depth-first in declaration order.
     pub fn all_extensions(&self) -> impl ExactSizeIterator<Item = ExtensionDescriptor> + '_ {
         indices(&self.inner.extensions).map(|index| ExtensionDescriptor {
             pool: self.clone(),
@@ -442,6 +486,14 @@ impl DescriptorPool {
         })
     }
 
+    /// Gets an iterator over all services defined in these protobuf files.
+    ///
+    /// This is equivalent to [`services`][DescriptorPool::services], since services cannot be nested. Services are
+    /// yielded in the order their files were added to the pool, and in declaration order within each file.
+    pub fn all_services(&self) -> impl ExactSizeIterator<Item = ServiceDescriptor> + '_ {
+        self.services()
+    }
+
     /// Gets a [`MessageDescriptor`] by its fully qualified name, for example `my.package.MessageName`.
     pub fn get_message_by_name(&self, name: &str) -> Option<MessageDescriptor> {
         match self.inner.get_by_name(name) {
@@ -484,6 +536,19 @@ impl DescriptorPool {
         }
     }
 
+    /// Gets an [`ExtensionDescriptor`] by the fully qualified name of the message it extends and its field number.
+    ///
+    /// Extensions declared in any file of the pool are found, including extensions nested within another message.
+    /// Returns `None` if the extended message does not exist, or has no extension with the given number. This is
+    /// equivalent to [`MessageDescriptor::get_extension`].
+    pub fn get_extension_by_number(
+        &self,
+        extendee: &str,
+        number: u32,
+    ) -> Option<ExtensionDescriptor> {
+        self.get_message_by_name(extendee)?.get_extension(number)
+    }
+
     /// Gets an [`ServiceDescriptor`] by its fully qualified name, for example `my.package.MyService`.
     pub fn get_service_by_name(&self, name: &str) -> Option<ServiceDescriptor> {
         match self.inner.get_by_name(name) {
@@ -519,6 +584,12 @@ impl PartialEq for DescriptorPool {
 
 impl Eq for DescriptorPool {}
 
+impl Hash for DescriptorPool {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.inner).hash(state);
+    }
+}
+
 impl FileDescriptor {
     /// Create a new [`FileDescriptor`] referencing the file at `index` within the given [`DescriptorPool`].
     ///
@@ -630,6 +701,19 @@ impl FileDescriptor {
         })
     }
 
+    /// Returns `true` if this file includes source code information, such as the locations and comments of its
+    /// definitions.
+    ///
+    /// Source code information is only present if it was included when the file was compiled, for example by
+    /// passing `--include_source_info` to `protoc`. It is also commonly stripped from descriptors embedded in
+    /// generated code. If this returns `false`, all comments for definitions in this file will appear to be absent.
+    pub fn has_source_info(&self) -> bool {
+        self.file_descriptor_proto()
+            .source_code_info
+            .as_ref()
+            .is_some_and(|info| !info.location.is_empty())
+    }
+
     /// Gets a reference to the raw [`FileDescriptorProto`] wrapped by this [`FileDescriptor`].
     pub fn file_descriptor_proto(&self) -> &FileDescriptorProto {
         &self.inner().prost
@@ -666,6 +750,11 @@ impl FileDescriptor {
         )
     }
 
+    /// Whether this file is marked as deprecated, using the `deprecated` file option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.inner().raw.options)
+    }
+
     fn inner(&self) -> &FileDescriptorInner {
         &self.pool.inner.files[self.index as usize]
     }
@@ -694,7 +783,19 @@ impl MessageDescriptor {
         }
     }
 
-    /// Gets the parent message type if this message type is nested inside a another message, or `None` otherwise
+    /// Gets the parent message type if this message type is nested inside another message, or `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// let pool = DescriptorPool::global();
+    /// let message_desc = pool.get_message_by_name("google.protobuf.DescriptorProto.ExtensionRange").unwrap();
+    /// assert_eq!(message_desc.name(), "ExtensionRange");
+    /// assert_eq!(message_desc.package_name(), "google.protobuf");
+    /// assert_eq!(message_desc.parent_message().unwrap().name(), "DescriptorProto");
+    /// assert_eq!(message_desc.parent_message().unwrap().parent_message(), None);
+    /// ```
     pub fn parent_message(&self) -> Option<MessageDescriptor> {
         self.inner().parent.map(|index| MessageDescriptor {
             pool: self.pool.clone(),
@@ -703,6 +804,8 @@ impl MessageDescriptor {
     }
 
     /// Gets the short name of the message type, e.g. `MyMessage`.
+    ///
+    /// The name does not include the package name, or the names of any messages this type is nested in.
     pub fn name(&self) -> &str {
         self.inner().id.name()
     }
@@ -756,6 +859,88 @@ impl MessageDescriptor {
             })
     }
 
+    /// Gets the dotted path of every field in this message, including fields of nested messages, up to the given
+    /// depth.
+    ///
+    /// Paths use the proto field names, as in a `google.protobuf.FieldMask`. A field of message type is listed
+    /// before the paths of its own fields. List and map fields are listed, but not descended into. Paths contain at
+    /// most `max_depth` components, so a `max_depth` of `1` returns only the fields of this message.
+    ///
+    /// A message type which contains itself, directly or indirectly, is not descended into again, so recursive
+    /// types do not produce an unbounded number of paths. Otherwise, this returns the same paths as
+    /// [`all_field_paths()`][Self::all_field_paths].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// assert_eq!(message_descriptor.enumerate_field_paths(1), ["foo", "bar", "nested"]);
+    /// assert_eq!(message_descriptor.enumerate_field_paths(5), ["foo", "bar", "nested"]);
+    /// ```
+    pub fn enumerate_field_paths(&self, max_depth: usize) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.collect_field_paths("", max_depth, Some(&mut vec![self.clone()]), &mut paths);
+        paths
+    }
+
+    /// Returns `true` if this message type contains itself, either directly or through the fields of other message
+    /// types.
+    ///
+    /// This includes references through list and map fields, but not through extension fields. Recursive message
+    /// types may be nested to any depth, so code which walks the fields of such a type must take care to terminate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// let pool = DescriptorPool::global();
+    /// assert!(pool.get_message_by_name("google.protobuf.Struct").unwrap().is_recursive());
+    /// assert!(!pool.get_message_by_name("google.protobuf.Timestamp").unwrap().is_recursive());
+    /// ```
+    pub fn is_recursive(&self) -> bool {
+        let mut visited = Vec::new();
+        let mut stack = vec![self.clone()];
+        while let Some(message) = stack.pop() {
+            for field in message.fields() {
+                if let Kind::Message(child) = field.kind() {
+                    if child == *self {
+                        return true;
+                    } else if !visited.contains(&child) {
+                        visited.push(child.clone());
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the maximum depth to which messages can be nested within this message type, or `None` if there is no
+    /// limit.
+    ///
+    /// A message type with no message fields has a depth of zero, and each singular, list or map field of a message
+    /// type adds one level of nesting, plus the depth of that type. Map entries count as a level of nesting, so a
+    /// map field whose values are messages adds two levels. Extension fields are not included.
+    ///
+    /// `None` is returned if this type is [recursive][Self::is_recursive], or contains a recursive type. Otherwise,
+    /// decoding a message of this type never exceeds a [`max_recursion_depth`][crate::DecodeOptions::max_recursion_depth]
+    /// of at least the returned value, unless it contains extension fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// let pool = DescriptorPool::global();
+    /// assert_eq!(pool.get_message_by_name("google.protobuf.Timestamp").unwrap().max_nesting_depth(), Some(0));
+    /// assert_eq!(pool.get_message_by_name("google.protobuf.Api").unwrap().max_nesting_depth(), Some(3));
+    /// assert_eq!(pool.get_message_by_name("google.protobuf.Struct").unwrap().max_nesting_depth(), None);
+    /// ```
+    pub fn max_nesting_depth(&self) -> Option<usize> {
+        max_nesting_depth(self, &mut Vec::new(), &mut HashMap::new())
+    }
+
     /// Gets an iterator yielding a [`OneofDescriptor`] for each oneof field defined in this message.
     pub fn oneofs(&self) -> impl ExactSizeIterator<Item = OneofDescriptor> + '_ {
         indices(&self.inner().oneofs).map(|index| OneofDescriptor {
@@ -847,6 +1032,69 @@ impl MessageDescriptor {
             })
     }
 
+    /// Returns the dotted paths of all fields of this message type and the message types nested within it, up to
+    /// `max_depth` fields deep.
+    ///
+    /// Paths are built from field names, following the same rules as `google.protobuf.FieldMask`: a path
+    /// continues into the fields of singular message fields, but not into repeated or map fields. A path for each
+    /// message field is included as well as the paths of its fields. A `max_depth` of `1` returns only the fields of
+    /// this message, and `0` returns no paths.
+    ///
+    /// Paths are returned in depth-first order, with fields in declaration order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DescriptorPool;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// assert_eq!(
+    ///     message_descriptor.all_field_paths(2),
+    ///     ["foo", "bar", "nested", "nested.foo", "nested.bar", "nested.nested"],
+    /// );
+    /// ```
+    pub fn all_field_paths(&self, max_depth: usize) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.collect_field_paths("", max_depth, None, &mut paths);
+        paths
+    }
+
+    /// Collects the field paths of this message type, skipping message types in `ancestors` if it is set.
+    fn collect_field_paths(
+        &self,
+        prefix: &str,
+        max_depth: usize,
+        mut ancestors: Option<&mut Vec<MessageDescriptor>>,
+        paths: &mut Vec<String>,
+    ) {
+        if max_depth == 0 {
+            return;
+        }
+
+        for field in self.fields() {
+            let path = format!("{}{}", prefix, field.name());
+            let message = match field.kind() {
+                Kind::Message(message) if !field.is_list() && !field.is_map() => message,
+                _ => {
+                    paths.push(path);
+                    continue;
+                }
+            };
+
+            let prefix = format!("{}.", path);
+            paths.push(path);
+            match ancestors.as_deref_mut() {
+                Some(ancestors) if ancestors.contains(&message) => {}
+                Some(ancestors) => {
+                    ancestors.push(message.clone());
+                    message.collect_field_paths(&prefix, max_depth - 1, Some(ancestors), paths);
+                    ancestors.pop();
+                }
+                None => message.collect_field_paths(&prefix, max_depth - 1, None, paths),
+            }
+        }
+    }
+
     /// Returns `true` if this is an auto-generated message type to
     /// represent the entry type for a map field.
     //
@@ -867,6 +1115,11 @@ impl MessageDescriptor {
             .unwrap_or(false)
     }
 
+    /// Whether this message type is marked as deprecated, using the `deprecated` message option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
     /// If this is a [map entry](MessageDescriptor::is_map_entry), returns a [`FieldDescriptor`] for the key.
     ///
     /// # Panics
@@ -890,6 +1143,9 @@ impl MessageDescriptor {
     }
 
     /// Gets an iterator over reserved field number ranges in this message.
+    ///
+    /// Like in the [`DescriptorProto`], the end of each range is exclusive, so `reserved 9 to 11;` is returned as
+    /// `9..12`.
     pub fn reserved_ranges(&self) -> impl ExactSizeIterator<Item = Range<u32>> + '_ {
         self.raw()
             .reserved_range
@@ -903,6 +1159,10 @@ impl MessageDescriptor {
     }
 
     /// Gets an iterator over extension field number ranges in this message.
+    ///
+    /// Like in the [`DescriptorProto`], the end of each range is exclusive, so `extensions 110 to 115;` is returned
+    /// as `110..116`. Use [`is_extension_number`][MessageDescriptor::is_extension_number] to check whether a single
+    /// number falls within one of these ranges.
     pub fn extension_ranges(&self) -> impl ExactSizeIterator<Item = Range<u32>> + '_ {
         self.raw()
             .extension_range
@@ -910,6 +1170,12 @@ impl MessageDescriptor {
             .map(|n| (n.start() as u32)..(n.end() as u32))
     }
 
+    /// Returns `true` if `number` is within one of the [extension ranges](MessageDescriptor::extension_ranges)
+    /// declared by this message, and so may be used as the number of an extension field.
+    pub fn is_extension_number(&self, number: u32) -> bool {
+        self.extension_ranges().any(|range| range.contains(&number))
+    }
+
     /// Gets an extension to this message by its number, or `None` if no such extension exists.
     pub fn get_extension(&self, number: u32) -> Option<ExtensionDescriptor> {
         self.extensions().find(|ext| ext.number() == number)
@@ -983,6 +1249,12 @@ impl FieldDescriptor {
         &self.inner().id.path
     }
 
+    /// Gets the index of this field within the parent [`MessageDescriptor`], in the order the fields are declared in
+    /// the `.proto` file.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
     /// Gets a reference to the raw [`FieldDescriptorProto`] wrapped by this [`FieldDescriptor`].
     pub fn field_descriptor_proto(&self) -> &FieldDescriptorProto {
         &self.parent_message().descriptor_proto().field[*self.path().last().unwrap() as usize]
@@ -1041,6 +1313,45 @@ impl FieldDescriptor {
         self.inner().is_packed
     }
 
+    /// Returns the [`WireType`] used to encode this field.
+    ///
+    /// This is [`WireType::LengthDelimited`] for [packed](Self::is_packed) lists, [`WireType::StartGroup`] for
+    /// [groups](Self::is_group), and otherwise the wire type of the [`Kind`] of the field. For repeated and map
+    /// fields, each element is encoded as a separate record with this wire type.
+    pub fn wire_type(&self) -> WireType {
+        if self.is_packed() {
+            WireType::LengthDelimited
+        } else if self.is_group() {
+            WireType::StartGroup
+        } else {
+            self.kind().wire_type()
+        }
+    }
+
+    /// Whether this field is marked as deprecated, using the `deprecated` field option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
+    /// Whether this field is marked with the `debug_redact` field option.
+    ///
+    /// The values of such fields are replaced with a placeholder when a message is formatted using the text format,
+    /// unless disabled using the `redact_debug_fields` format option.
+    pub fn is_debug_redacted(&self) -> bool {
+        self.inner().is_debug_redacted
+    }
+
+    /// Gets the value of the `ctype` field option, which controls the C++ representation of string fields.
+    ///
+    /// Returns [`CType::String`] if the option is not set.
+    pub fn ctype(&self) -> CType {
+        self.raw()
+            .options
+            .as_ref()
+            .map(|o| o.value.ctype())
+            .unwrap_or(CType::String)
+    }
+
     /// The cardinality of this field.
     pub fn cardinality(&self) -> Cardinality {
         self.inner().cardinality
@@ -1050,8 +1361,8 @@ impl FieldDescriptor {
     /// the default value.
     ///
     /// For proto2 messages this returns `true` for all non-repeated fields.
-    /// For proto3 this returns `true` for message fields, and fields contained
-    /// in a `oneof`.
+    /// For proto3 this returns `true` for message fields, fields declared with
+    /// the `optional` keyword, and fields contained in a `oneof`.
     pub fn supports_presence(&self) -> bool {
         self.inner().supports_presence
     }
@@ -1070,7 +1381,19 @@ impl FieldDescriptor {
         })
     }
 
-    pub(crate) fn default_value(&self) -> Option<&Value> {
+    /// Gets the default value of this field.
+    ///
+    /// This is the value returned by [`DynamicMessage::get_field`](crate::DynamicMessage::get_field) when the field is
+    /// not set. It is the explicit `[default = ...]` value for proto2 fields which specify one, the first value of
+    /// the enum type for enum fields, the zero value for other scalar fields, an empty message for message fields,
+    /// and an empty list or map for `repeated` and map fields.
+    ///
+    /// See [`Value::default_value_for_field`] for more details.
+    pub fn default_value(&self) -> Value {
+        Value::default_value_for_field(self)
+    }
+
+    pub(crate) fn explicit_default_value(&self) -> Option<&Value> {
         self.inner().default.as_ref()
     }
 
@@ -1100,7 +1423,7 @@ impl fmt::Debug for FieldDescriptor {
                 "containing_oneof",
                 &self.containing_oneof().map(|o| o.name().to_owned()),
             )
-            .field("default_value", &self.default_value())
+            .field("default_value", &self.explicit_default_value())
             .field("is_group", &self.is_group())
             .field("is_list", &self.is_list())
             .field("is_map", &self.is_map())
@@ -1232,6 +1555,45 @@ impl ExtensionDescriptor {
         self.inner().is_packed
     }
 
+    /// Returns the [`WireType`] used to encode this extension.
+    ///
+    /// This is [`WireType::LengthDelimited`] for [packed](Self::is_packed) lists, [`WireType::StartGroup`] for
+    /// [groups](Self::is_group), and otherwise the wire type of the [`Kind`] of the extension. For repeated and map
+    /// extensions, each element is encoded as a separate record with this wire type.
+    pub fn wire_type(&self) -> WireType {
+        if self.is_packed() {
+            WireType::LengthDelimited
+        } else if self.is_group() {
+            WireType::StartGroup
+        } else {
+            self.kind().wire_type()
+        }
+    }
+
+    /// Whether this field is marked as deprecated, using the `deprecated` field option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
+    /// Whether this extension is marked with the `debug_redact` field option.
+    ///
+    /// The values of such extensions are replaced with a placeholder when a message is formatted using the text format,
+    /// unless disabled using the `redact_debug_fields` format option.
+    pub fn is_debug_redacted(&self) -> bool {
+        self.inner().is_debug_redacted
+    }
+
+    /// Gets the value of the `ctype` field option, which controls the C++ representation of string fields.
+    ///
+    /// Returns [`CType::String`] if the option is not set.
+    pub fn ctype(&self) -> CType {
+        self.raw()
+            .options
+            .as_ref()
+            .map(|o| o.value.ctype())
+            .unwrap_or(CType::String)
+    }
+
     /// The cardinality of this field.
     pub fn cardinality(&self) -> Cardinality {
         self.inner().cardinality
@@ -1258,7 +1620,14 @@ impl ExtensionDescriptor {
         }
     }
 
-    pub(crate) fn default_value(&self) -> Option<&Value> {
+    /// Gets the default value of this extension field.
+    ///
+    /// See [`FieldDescriptor::default_value`] and [`Value::default_value_for_extension`] for more details.
+    pub fn default_value(&self) -> Value {
+        Value::default_value_for_extension(self)
+    }
+
+    pub(crate) fn explicit_default_value(&self) -> Option<&Value> {
         self.inner().default.as_ref()
     }
 
@@ -1303,7 +1672,7 @@ impl fmt::Debug for ExtensionDescriptor {
                 "containing_message",
                 &self.containing_message().name().to_owned(),
             )
-            .field("default_value", &self.default_value())
+            .field("default_value", &self.explicit_default_value())
             .field("is_group", &self.is_group())
             .field("is_list", &self.is_list())
             .field("is_map", &self.is_map())
@@ -1389,6 +1758,23 @@ impl EnumDescriptor {
         )
     }
 
+    /// Whether this enum type is marked as deprecated, using the `deprecated` enum option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
+    /// Returns `true` if this is a closed enum type.
+    ///
+    /// Enums defined in a file with [`Syntax::Proto2`] are closed, meaning that a field of this type may only hold
+    /// one of the numbers defined by the enum. Undefined numbers are rejected when parsing JSON, and by
+    /// [`DynamicMessage::set_field_checked()`]. Enums defined in a file with [`Syntax::Proto3`] are open, and accept any
+    /// number.
+    /// The syntax this is based on is available from [`parent_file().syntax()`](FileDescriptor::syntax). For files
+    /// using [`Syntax::Editions`], this is determined by the `enum_type` feature instead.
+    pub fn is_closed(&self) -> bool {
+        self.inner().is_closed
+    }
+
     /// Gets the default value for the enum type.
     pub fn default_value(&self) -> EnumValueDescriptor {
         EnumValueDescriptor {
@@ -1398,6 +1784,8 @@ impl EnumDescriptor {
     }
 
     /// Gets a [`EnumValueDescriptor`] for the enum value with the given name, or `None` if no such value exists.
+    ///
+    /// If the enum is defined with the `allow_alias` option, any of the names of a value may be used.
     pub fn get_value_by_name(&self, name: &str) -> Option<EnumValueDescriptor> {
         self.inner()
             .value_names
@@ -1424,17 +1812,20 @@ impl EnumDescriptor {
     }
 
     /// Gets an iterator yielding a [`EnumValueDescriptor`] for each value in this enum.
+    ///
+    /// Values are yielded in the order they are declared in the enum definition. If the enum is defined with the
+    /// `allow_alias` option, each alias is yielded separately.
     pub fn values(&self) -> impl ExactSizeIterator<Item = EnumValueDescriptor> + '_ {
-        self.inner()
-            .value_numbers
-            .iter()
-            .map(|&(_, index)| EnumValueDescriptor {
-                parent: self.clone(),
-                index,
-            })
+        indices(&self.inner().values).map(|index| EnumValueDescriptor {
+            parent: self.clone(),
+            index,
+        })
     }
 
     /// Gets an iterator over reserved value number ranges in this enum.
+    ///
+    /// Like in the [`EnumDescriptorProto`], the end of each range is inclusive, so `reserved 9 to 11;` is returned
+    /// as `9..=11`.
     pub fn reserved_ranges(&self) -> impl ExactSizeIterator<Item = RangeInclusive<i32>> + '_ {
         self.raw()
             .reserved_range
@@ -1518,6 +1909,11 @@ impl EnumValueDescriptor {
         )
     }
 
+    /// Whether this enum value is marked as deprecated, using the `deprecated` enum value option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
     /// Gets the number representing this enum value.
     pub fn number(&self) -> i32 {
         self.inner().number
@@ -1693,6 +2089,11 @@ impl ServiceDescriptor {
         )
     }
 
+    /// Whether this service is marked as deprecated, using the `deprecated` service option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
     /// Gets an iterator yielding a [`MethodDescriptor`] for each method defined in this service.
     pub fn methods(&self) -> impl ExactSizeIterator<Item = MethodDescriptor> + '_ {
         indices(&self.inner().methods).map(|index| MethodDescriptor {
@@ -1792,6 +2193,11 @@ impl MethodDescriptor {
         )
     }
 
+    /// Whether this method is marked as deprecated, using the `deprecated` method option.
+    pub fn is_deprecated(&self) -> bool {
+        is_deprecated(&self.raw().options)
+    }
+
     /// Gets the [`MessageDescriptor`] for the input type of this method.
     pub fn input(&self) -> MessageDescriptor {
         MessageDescriptor {
@@ -1889,6 +2295,13 @@ fn decode_options<T>(
     DynamicMessage::decode(message_desc, bytes).unwrap()
 }
 
+fn is_deprecated<T>(option: &Option<Options<T>>) -> bool
+where
+    T: DeprecatedOption,
+{
+    option.as_ref().is_some_and(|o| o.value.is_deprecated())
+}
+
 fn find_message_proto_prost<'a>(
     file: &'a FileDescriptorProto,
     path: &[i32],
@@ -1909,3 +2322,28 @@ fn find_message_proto_prost<'a>(
 
     message.unwrap()
 }
+
+#[allow(clippy::mutable_key_type)]
+fn max_nesting_depth(
+    message: &MessageDescriptor,
+    ancestors: &mut Vec<MessageDescriptor>,
+    depths: &mut HashMap<MessageDescriptor, usize>,
+) -> Option<usize> {
+    if let Some(&depth) = depths.get(message) {
+        return Some(depth);
+    } else if ancestors.contains(message) {
+        return None;
+    }
+
+    ancestors.push(message.clone());
+    let mut depth = 0;
+    for field in message.fields() {
+        if let Kind::Message(child) = field.kind() {
+            depth = depth.max(max_nesting_depth(&child, ancestors, depths)? + 1);
+        }
+    }
+    ancestors.pop();
+
+    depths.insert(message.clone(), depth);
+    Some(depth)
+}
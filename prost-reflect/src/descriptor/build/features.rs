@@ -0,0 +1,49 @@
+use crate::descriptor::types::{
+    feature_set::{EnumType, FieldPresence, MessageEncoding, RepeatedFieldEncoding},
+    FeatureSet, FileDescriptorProto, Options,
+};
+
+impl FeatureSet {
+    /// The features of the given file, which are the defaults for its syntax or edition overridden by its options.
+    pub(super) fn for_file(file: &FileDescriptorProto) -> Self {
+        let defaults = match file.syntax() {
+            "proto3" => FeatureSet {
+                field_presence: Some(FieldPresence::Implicit as i32),
+                enum_type: Some(EnumType::Open as i32),
+                repeated_field_encoding: Some(RepeatedFieldEncoding::Packed as i32),
+                message_encoding: Some(MessageEncoding::LengthPrefixed as i32),
+            },
+            "editions" => FeatureSet {
+                field_presence: Some(FieldPresence::Explicit as i32),
+                enum_type: Some(EnumType::Open as i32),
+                repeated_field_encoding: Some(RepeatedFieldEncoding::Packed as i32),
+                message_encoding: Some(MessageEncoding::LengthPrefixed as i32),
+            },
+            _ => FeatureSet {
+                field_presence: Some(FieldPresence::Explicit as i32),
+                enum_type: Some(EnumType::Closed as i32),
+                repeated_field_encoding: Some(RepeatedFieldEncoding::Expanded as i32),
+                message_encoding: Some(MessageEncoding::LengthPrefixed as i32),
+            },
+        };
+
+        defaults.merged(file.options.as_ref())
+    }
+
+    /// The features of an element nested within the scope of these features, with the given options.
+    ///
+    /// Each feature takes the value set in `options`, or the value of this scope if it is not set.
+    pub(super) fn merged<T>(&self, options: Option<&Options<T>>) -> Self {
+        match options.and_then(Options::features) {
+            Some(features) => FeatureSet {
+                field_presence: features.field_presence.or(self.field_presence),
+                enum_type: features.enum_type.or(self.enum_type),
+                repeated_field_encoding: features
+                    .repeated_field_encoding
+                    .or(self.repeated_field_encoding),
+                message_encoding: features.message_encoding.or(self.message_encoding),
+            },
+            None => self.clone(),
+        }
+    }
+}
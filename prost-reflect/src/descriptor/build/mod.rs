@@ -1,3 +1,4 @@
+mod features;
 mod names;
 mod options;
 mod resolve;
@@ -11,9 +11,9 @@ use crate::{
         error::{DescriptorError, DescriptorErrorKind, Label},
         tag, to_index,
         types::{
-            DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-            FileDescriptorProto, MethodDescriptorProto, OneofDescriptorProto,
-            ServiceDescriptorProto,
+            feature_set::EnumType, DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto,
+            FeatureSet, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+            OneofDescriptorProto, ServiceDescriptorProto, EDITION_2023,
         },
         Definition, DefinitionKind, DescriptorPoolInner, EnumDescriptorInner, EnumIndex,
         EnumValueDescriptorInner, EnumValueIndex, ExtensionIndex, FieldIndex, FileDescriptorInner,
@@ -52,20 +52,10 @@ impl<'a> Visitor for NameVisitor<'a> {
         debug_assert_eq!(to_index(self.pool.files.len()), index);
 
         let syntax = match file.syntax.as_deref() {
-            None | Some("proto2") => Syntax::Proto2,
-            Some("proto3") => Syntax::Proto3,
-            Some(syntax) => {
-                self.errors.push(DescriptorErrorKind::UnknownSyntax {
-                    syntax: syntax.to_owned(),
-                    found: Label::new(
-                        &self.pool.files,
-                        "found here",
-                        index,
-                        join_path(path, &[tag::file::SYNTAX]),
-                    ),
-                });
-                return;
-            }
+            None | Some("proto2") => Some(Syntax::Proto2),
+            Some("proto3") => Some(Syntax::Proto3),
+            Some("editions") if file.edition == Some(EDITION_2023) => Some(Syntax::Editions),
+            Some(_) => None,
         };
 
         if self
@@ -79,13 +69,38 @@ impl<'a> Visitor for NameVisitor<'a> {
             });
         }
         self.pool.files.push(FileDescriptorInner {
-            syntax,
+            syntax: syntax.unwrap_or(Syntax::Proto2),
             raw: file.clone(),
             prost: Default::default(), // the prost descriptor is initialized from the internal descriptor once resolution is complete, to avoid needing to duplicate all modifications
             dependencies: Vec::with_capacity(file.dependency.len()),
             transitive_dependencies: HashSet::default(),
         });
 
+        // The file must be added before reporting errors, so their labels can refer to it.
+        if syntax.is_none() {
+            self.errors.push(if file.syntax() == "editions" {
+                DescriptorErrorKind::UnknownEdition {
+                    edition: file.edition.unwrap_or_default(),
+                    found: Label::new(
+                        &self.pool.files,
+                        "found here",
+                        index,
+                        join_path(path, &[tag::file::EDITION]),
+                    ),
+                }
+            } else {
+                DescriptorErrorKind::UnknownSyntax {
+                    syntax: file.syntax().to_owned(),
+                    found: Label::new(
+                        &self.pool.files,
+                        "found here",
+                        index,
+                        join_path(path, &[tag::file::SYNTAX]),
+                    ),
+                }
+            });
+        }
+
         if !file.package().is_empty() {
             for (i, _) in file.package().match_indices('.') {
                 self.add_name(
@@ -148,6 +163,7 @@ impl<'a> Visitor for NameVisitor<'a> {
         message: MessageIndex,
         _: FieldIndex,
         _: &FieldDescriptorProto,
+        _: &FeatureSet,
     ) {
         self.add_name(
             file,
@@ -230,6 +246,7 @@ impl<'a> Visitor for NameVisitor<'a> {
         parent: Option<MessageIndex>,
         index: EnumIndex,
         enum_: &EnumDescriptorProto,
+        features: &FeatureSet,
     ) {
         self.add_name(
             file,
@@ -239,13 +256,13 @@ impl<'a> Visitor for NameVisitor<'a> {
             DefinitionKind::Enum(index),
         );
 
+        let is_closed = features.enum_type() == EnumType::Closed;
+
         if enum_.value.is_empty() {
             self.errors.push(DescriptorErrorKind::EmptyEnum {
                 found: Label::new(&self.pool.files, "enum defined here", file, path.into()),
             });
-        } else if self.pool.files[file as usize].syntax != Syntax::Proto2
-            && enum_.value[0].number() != 0
-        {
+        } else if !is_closed && enum_.value[0].number() != 0 {
             self.errors
                 .push(DescriptorErrorKind::InvalidProto3EnumDefault {
                     found: Label::new(
@@ -275,6 +292,7 @@ impl<'a> Visitor for NameVisitor<'a> {
             value_numbers: Vec::with_capacity(enum_.value.len()),
             value_names: HashMap::with_capacity(enum_.value.len()),
             allow_alias,
+            is_closed,
         });
     }
 
@@ -315,6 +333,7 @@ impl<'a> Visitor for NameVisitor<'a> {
         _: Option<MessageIndex>,
         index: ExtensionIndex,
         _: &FieldDescriptorProto,
+        _: &FeatureSet,
     ) {
         self.add_name(
             file,
@@ -13,14 +13,14 @@ use crate::{
         tag,
         types::{
             uninterpreted_option, DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto,
-            FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto, OneofDescriptorProto,
-            Options, ServiceDescriptorProto, UninterpretedOption,
+            FeatureSet, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+            OneofDescriptorProto, Options, ServiceDescriptorProto, UninterpretedOption,
         },
         Definition, DefinitionKind, EnumIndex, EnumValueIndex, ExtensionIndex, FieldIndex,
         FileIndex, MessageIndex, MethodIndex, OneofIndex, ServiceIndex, MAP_ENTRY_KEY_NUMBER,
         MAP_ENTRY_VALUE_NUMBER,
     },
-    dynamic::{fmt_string, FieldDescriptorLike},
+    dynamic::{fmt_string, EscapeStyle, FieldDescriptorLike},
     Cardinality, DescriptorError, DescriptorPool, DynamicMessage, EnumDescriptor,
     ExtensionDescriptor, MapKey, MessageDescriptor, ReflectMessage, Value,
 };
@@ -143,6 +143,7 @@ impl<'a> Visitor for OptionsVisitor<'a> {
         _: MessageIndex,
         _: FieldIndex,
         field: &FieldDescriptorProto,
+        _: &FeatureSet,
     ) {
         if let Some(options) = &field.options {
             let path = join_path(path, &[tag::field::OPTIONS]);
@@ -234,6 +235,7 @@ impl<'a> Visitor for OptionsVisitor<'a> {
         _: Option<MessageIndex>,
         _: EnumIndex,
         enum_: &EnumDescriptorProto,
+        _: &FeatureSet,
     ) {
         if let Some(options) = &enum_.options {
             let path = join_path(path, &[tag::enum_::OPTIONS]);
@@ -280,6 +282,7 @@ impl<'a> Visitor for OptionsVisitor<'a> {
         _: Option<MessageIndex>,
         _: ExtensionIndex,
         extension: &FieldDescriptorProto,
+        _: &FeatureSet,
     ) {
         if let Some(options) = &extension.options {
             let path = join_path(path, &[tag::field::OPTIONS]);
@@ -711,7 +714,7 @@ fn fmt_value(option: &UninterpretedOption) -> String {
         value.to_string()
     } else if let Some(value) = &option.string_value {
         let mut string = String::new();
-        fmt_string(&mut string, value).unwrap();
+        fmt_string(&mut string, value, EscapeStyle::Octal).unwrap();
         string
     } else if let Some(value) = &option.aggregate_value {
         value.clone()
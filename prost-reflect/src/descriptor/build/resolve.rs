@@ -12,7 +12,8 @@ use crate::{
         error::{DescriptorError, DescriptorErrorKind, Label},
         find_enum_proto, find_message_proto, tag, to_index,
         types::{
-            field_descriptor_proto, DescriptorProto, EnumValueDescriptorProto,
+            feature_set::{FieldPresence, MessageEncoding, RepeatedFieldEncoding},
+            field_descriptor_proto, DescriptorProto, EnumValueDescriptorProto, FeatureSet,
             FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
             ServiceDescriptorProto,
         },
@@ -22,7 +23,7 @@ use crate::{
         ServiceDescriptorInner, ServiceIndex, RESERVED_MESSAGE_FIELD_NUMBERS,
         VALID_MESSAGE_FIELD_NUMBERS,
     },
-    Cardinality, Syntax, Value,
+    Cardinality, Value,
 };
 
 impl DescriptorPoolInner {
@@ -97,38 +98,34 @@ impl<'a> Visitor for ResolveVisitor<'a> {
         message: MessageIndex,
         index: FieldIndex,
         field: &FieldDescriptorProto,
+        features: &FeatureSet,
     ) {
         debug_assert_eq!(
             to_index(self.pool.messages[message as usize].fields.len()),
             index
         );
 
-        let syntax = self.pool.files[file as usize].syntax;
-
         self.check_field_number(message, field, file, path);
 
-        let cardinality = match field.label() {
-            field_descriptor_proto::Label::Optional => Cardinality::Optional,
-            field_descriptor_proto::Label::Required => Cardinality::Required,
-            field_descriptor_proto::Label::Repeated => Cardinality::Repeated,
-        };
+        let cardinality = resolve_cardinality(field, features);
 
-        let kind =
-            self.resolve_field_type(field.r#type(), field.type_name(), full_name, file, path);
+        let kind = self
+            .resolve_field_type(field.r#type(), field.type_name(), full_name, file, path)
+            .map(|kind| self.resolve_message_encoding(kind, Some(message), features));
 
         let json_name: Box<str> = self.resolve_field_json_name(field, file, path).into();
 
         let is_packed = cardinality == Cardinality::Repeated
             && kind.map_or(false, |k| k.is_packable())
-            && (field
-                .options
-                .as_ref()
-                .map_or(syntax == Syntax::Proto3, |o| o.value.packed()));
+            && resolve_packed(field, features);
+
+        let is_debug_redacted = field.options.as_ref().is_some_and(|o| o.debug_redact());
 
         let supports_presence = field.proto3_optional()
             || field.oneof_index.is_some()
             || (cardinality != Cardinality::Repeated
-                && (kind.map_or(false, |k| k.is_message()) || syntax == Syntax::Proto2));
+                && (kind.map_or(false, |k| k.is_message())
+                    || features.field_presence() != FieldPresence::Implicit));
 
         let default = kind.and_then(|kind| {
             self.parse_field_default_value(kind, field.default_value.as_deref(), file, path)
@@ -152,6 +149,7 @@ impl<'a> Visitor for ResolveVisitor<'a> {
             kind: kind.unwrap_or(KindIndex::Double),
             oneof,
             is_packed,
+            is_debug_redacted,
             supports_presence,
             json_name: json_name.clone(),
             cardinality,
@@ -355,6 +353,7 @@ impl<'a> Visitor for ResolveVisitor<'a> {
         parent_message: Option<MessageIndex>,
         index: ExtensionIndex,
         extension: &FieldDescriptorProto,
+        features: &FeatureSet,
     ) {
         debug_assert_eq!(to_index(self.pool.extensions.len()), index);
 
@@ -371,30 +370,25 @@ impl<'a> Visitor for ResolveVisitor<'a> {
             self.check_field_number(extendee, extension, file, path);
         }
 
-        let syntax = self.pool.files[file as usize].syntax;
-
-        let cardinality = match extension.label() {
-            field_descriptor_proto::Label::Optional => Cardinality::Optional,
-            field_descriptor_proto::Label::Required => Cardinality::Required,
-            field_descriptor_proto::Label::Repeated => Cardinality::Repeated,
-        };
+        let cardinality = resolve_cardinality(extension, features);
 
-        let kind = self.resolve_field_type(
-            extension.r#type(),
-            extension.type_name(),
-            full_name,
-            file,
-            path,
-        );
+        let kind = self
+            .resolve_field_type(
+                extension.r#type(),
+                extension.type_name(),
+                full_name,
+                file,
+                path,
+            )
+            .map(|kind| self.resolve_message_encoding(kind, None, features));
 
         self.resolve_field_json_name(extension, file, path);
 
         let is_packed = cardinality == Cardinality::Repeated
             && kind.map_or(false, |k| k.is_packable())
-            && (extension
-                .options
-                .as_ref()
-                .map_or(syntax == Syntax::Proto3, |o| o.value.packed()));
+            && resolve_packed(extension, features);
+
+        let is_debug_redacted = extension.options.as_ref().is_some_and(|o| o.debug_redact());
 
         let default = kind.and_then(|kind| {
             self.parse_field_default_value(kind, extension.default_value.as_deref(), file, path)
@@ -408,6 +402,7 @@ impl<'a> Visitor for ResolveVisitor<'a> {
             extendee: extendee.unwrap_or(MessageIndex::MAX),
             kind: kind.unwrap_or(KindIndex::Double),
             is_packed,
+            is_debug_redacted,
             cardinality,
             default,
         });
@@ -415,6 +410,36 @@ impl<'a> Visitor for ResolveVisitor<'a> {
 }
 
 impl<'a> ResolveVisitor<'a> {
+    /// Fields of message type which use the delimited encoding are encoded as groups, unless they are map fields.
+    fn resolve_message_encoding(
+        &self,
+        kind: KindIndex,
+        parent: Option<MessageIndex>,
+        features: &FeatureSet,
+    ) -> KindIndex {
+        match kind {
+            KindIndex::Message(message)
+                if features.message_encoding() == MessageEncoding::Delimited
+                    && !self.is_map_entry(message)
+                    && !parent.is_some_and(|parent| self.is_map_entry(parent)) =>
+            {
+                KindIndex::Group(message)
+            }
+            kind => kind,
+        }
+    }
+
+    fn is_map_entry(&self, message: MessageIndex) -> bool {
+        let message = &self.pool.messages[message as usize];
+        find_message_proto(
+            &self.pool.files[message.id.file as usize].raw,
+            &message.id.path,
+        )
+        .options
+        .as_ref()
+        .is_some_and(|o| o.value.map_entry())
+    }
+
     fn resolve_public_dependencies(&self, dependencies: &mut HashSet<FileIndex>, index: FileIndex) {
         let file = &self.pool.files[index as usize];
 
@@ -1007,3 +1032,26 @@ fn test_unescape_c_escape_string() {
     );
     assert_eq!(Err("invalid hex escape"), unescape_c_escape_string(r"\x__"));
 }
+
+fn resolve_cardinality(field: &FieldDescriptorProto, features: &FeatureSet) -> Cardinality {
+    match field.label() {
+        field_descriptor_proto::Label::Optional
+            if features.field_presence() == FieldPresence::LegacyRequired =>
+        {
+            Cardinality::Required
+        }
+        field_descriptor_proto::Label::Optional => Cardinality::Optional,
+        field_descriptor_proto::Label::Required => Cardinality::Required,
+        field_descriptor_proto::Label::Repeated => Cardinality::Repeated,
+    }
+}
+
+/// The `packed` option takes precedence over the `repeated_field_encoding` feature, which is only set in files using
+/// editions.
+fn resolve_packed(field: &FieldDescriptorProto, features: &FeatureSet) -> bool {
+    field
+        .options
+        .as_ref()
+        .and_then(|o| o.value.packed)
+        .unwrap_or(features.repeated_field_encoding() == RepeatedFieldEncoding::Packed)
+}
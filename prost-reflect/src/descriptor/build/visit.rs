@@ -2,13 +2,15 @@ use crate::descriptor::{
     build::DescriptorPoolOffsets,
     tag, to_index,
     types::{
-        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-        FileDescriptorProto, MethodDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto,
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FeatureSet,
+        FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto, OneofDescriptorProto,
+        ServiceDescriptorProto,
     },
     EnumIndex, EnumValueIndex, ExtensionIndex, FieldIndex, FileIndex, MessageIndex, MethodIndex,
     OneofIndex, ServiceIndex,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(super) trait Visitor {
     fn visit_file(&mut self, _path: &[i32], _index: FileIndex, _file: &FileDescriptorProto) {}
 
@@ -31,6 +33,7 @@ pub(super) trait Visitor {
         _message: MessageIndex,
         _index: FieldIndex,
         _field: &FieldDescriptorProto,
+        _features: &FeatureSet,
     ) {
     }
 
@@ -74,6 +77,7 @@ pub(super) trait Visitor {
         _parent_message: Option<MessageIndex>,
         _index: EnumIndex,
         _enum: &EnumDescriptorProto,
+        _features: &FeatureSet,
     ) {
     }
 
@@ -96,6 +100,7 @@ pub(super) trait Visitor {
         _parent_message: Option<MessageIndex>,
         _index: ExtensionIndex,
         _extension: &FieldDescriptorProto,
+        _features: &FeatureSet,
     ) {
     }
 }
@@ -108,6 +113,7 @@ pub(super) fn visit(
     let mut context = Context {
         path: Vec::new(),
         scope: String::new(),
+        features: Vec::new(),
         offsets,
     };
 
@@ -119,6 +125,8 @@ pub(super) fn visit(
 struct Context {
     path: Vec<i32>,
     scope: String,
+    /// The resolved features of the file and each message enclosing the element being visited.
+    features: Vec<FeatureSet>,
     offsets: DescriptorPoolOffsets,
 }
 
@@ -131,6 +139,8 @@ impl Context {
         let index = post_inc(&mut self.offsets.file);
         visitor.visit_file(&self.path, index, file);
 
+        self.features.push(FeatureSet::for_file(file));
+
         self.push_path(tag::file::MESSAGE_TYPE);
         for (i, message) in file.message_type.iter().enumerate() {
             self.push_path(i as i32);
@@ -163,6 +173,8 @@ impl Context {
         }
         self.pop_path();
 
+        self.features.pop().unwrap();
+
         if !file.package().is_empty() {
             self.pop_scope(file.package());
         }
@@ -187,6 +199,14 @@ impl Context {
             message,
         );
 
+        let features = self.features().merged(message.options.as_ref());
+        let oneof_features: Vec<FeatureSet> = message
+            .oneof_decl
+            .iter()
+            .map(|oneof| features.merged(oneof.options.as_ref()))
+            .collect();
+        self.features.push(features);
+
         self.push_path(tag::message::ONEOF_DECL);
         for (i, oneof) in message.oneof_decl.iter().enumerate() {
             self.push_path(i as i32);
@@ -198,7 +218,10 @@ impl Context {
         self.push_path(tag::message::FIELD);
         for (i, field) in message.field.iter().enumerate() {
             self.push_path(i as i32);
-            self.visit_field(field, visitor, file, index, to_index(i));
+            let parent_features = field
+                .oneof_index
+                .and_then(|oneof_index| oneof_features.get(oneof_index as usize));
+            self.visit_field(field, visitor, file, index, to_index(i), parent_features);
             self.pop_path();
         }
         self.pop_path();
@@ -227,6 +250,8 @@ impl Context {
         }
         self.pop_path();
 
+        self.features.pop().unwrap();
+
         self.pop_scope(message.name());
     }
 
@@ -237,9 +262,21 @@ impl Context {
         file: FileIndex,
         message: MessageIndex,
         index: FieldIndex,
+        oneof_features: Option<&FeatureSet>,
     ) {
         self.push_scope(field.name());
-        visitor.visit_field(&self.path, &self.scope, file, message, index, field);
+        let features = oneof_features
+            .unwrap_or_else(|| self.features())
+            .merged(field.options.as_ref());
+        visitor.visit_field(
+            &self.path,
+            &self.scope,
+            file,
+            message,
+            index,
+            field,
+            &features,
+        );
         self.pop_scope(field.name());
     }
 
@@ -301,7 +338,16 @@ impl Context {
         self.push_scope(enum_.name());
 
         let index = post_inc(&mut self.offsets.enum_);
-        visitor.visit_enum(&self.path, &self.scope, file, parent_message, index, enum_);
+        let features = self.features().merged(enum_.options.as_ref());
+        visitor.visit_enum(
+            &self.path,
+            &self.scope,
+            file,
+            parent_message,
+            index,
+            enum_,
+            &features,
+        );
 
         self.pop_scope(enum_.name());
 
@@ -336,6 +382,7 @@ impl Context {
     ) {
         self.push_scope(extension.name());
         let index = post_inc(&mut self.offsets.extension);
+        let features = self.features().merged(extension.options.as_ref());
         visitor.visit_extension(
             &self.path,
             &self.scope,
@@ -343,10 +390,15 @@ impl Context {
             parent_message,
             index,
             extension,
+            &features,
         );
         self.pop_scope(extension.name());
     }
 
+    fn features(&self) -> &FeatureSet {
+        self.features.last().unwrap()
+    }
+
     fn push_path(&mut self, path: i32) {
         self.path.push(path);
     }
@@ -21,6 +21,10 @@ pub(super) enum DescriptorErrorKind {
         syntax: String,
         found: Label,
     },
+    UnknownEdition {
+        edition: i32,
+        found: Label,
+    },
     DuplicateFileName {
         name: String,
     },
@@ -310,6 +314,7 @@ impl DescriptorErrorKind {
         match self {
             DescriptorErrorKind::MissingRequiredField { label } => Some(label),
             DescriptorErrorKind::UnknownSyntax { found, .. } => Some(found),
+            DescriptorErrorKind::UnknownEdition { found, .. } => Some(found),
             DescriptorErrorKind::DuplicateFileName { .. } => None,
             DescriptorErrorKind::FileNotFound { found, .. } => Some(found),
             DescriptorErrorKind::InvalidImportIndex => None,
@@ -349,6 +354,9 @@ impl DescriptorErrorKind {
             DescriptorErrorKind::UnknownSyntax { found, .. } => {
                 found.resolve_span(file, source);
             }
+            DescriptorErrorKind::UnknownEdition { found, .. } => {
+                found.resolve_span(file, source);
+            }
             DescriptorErrorKind::DuplicateFileName { .. } => {}
             DescriptorErrorKind::FileNotFound { found, .. } => {
                 found.resolve_span(file, source);
@@ -453,6 +461,9 @@ impl fmt::Display for DescriptorErrorKind {
             DescriptorErrorKind::UnknownSyntax { syntax, .. } => {
                 write!(f, "unknown syntax '{}'", syntax)
             }
+            DescriptorErrorKind::UnknownEdition { edition, .. } => {
+                write!(f, "unsupported edition '{}'", edition)
+            }
             DescriptorErrorKind::DuplicateFileName { name, .. } => {
                 write!(
                     f,
@@ -625,8 +636,11 @@ impl miette::Diagnostic for DescriptorErrorKind {
 
         match self {
             DescriptorErrorKind::MissingRequiredField { .. } => None,
-            DescriptorErrorKind::UnknownSyntax { .. } => {
-                Some(Box::new("valid values are 'proto2' and 'proto3'"))
+            DescriptorErrorKind::UnknownSyntax { .. } => Some(Box::new(
+                "valid values are 'proto2', 'proto3' and 'editions'",
+            )),
+            DescriptorErrorKind::UnknownEdition { .. } => {
+                Some(Box::new("the only supported edition is 2023"))
             }
             DescriptorErrorKind::DuplicateFileName { .. } => None,
             DescriptorErrorKind::FileNotFound { .. } => None,
@@ -685,6 +699,9 @@ impl miette::Diagnostic for DescriptorErrorKind {
             DescriptorErrorKind::UnknownSyntax { found: defined, .. } => {
                 spans.extend(defined.to_span());
             }
+            DescriptorErrorKind::UnknownEdition { found, .. } => {
+                spans.extend(found.to_span());
+            }
             DescriptorErrorKind::DuplicateFileName { .. } => {}
             DescriptorErrorKind::FileNotFound { found, .. } => {
                 spans.extend(found.to_span());
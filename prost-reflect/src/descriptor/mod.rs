@@ -47,6 +47,10 @@ pub enum Syntax {
     Proto2,
     /// The `proto3` syntax.
     Proto3,
+    /// The `editions` syntax, where the behavior of each element is determined by its features.
+    ///
+    /// Only edition 2023 is currently supported.
+    Editions,
 }
 
 /// The type of a protobuf message field.
@@ -175,7 +179,7 @@ enum DefinitionKind {
 }
 
 /// A single source file containing protobuf messages and services.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct FileDescriptor {
     pool: DescriptorPool,
     index: FileIndex,
@@ -191,7 +195,12 @@ struct FileDescriptorInner {
 }
 
 /// A protobuf message definition.
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Message descriptors are compared and hashed by identity: two descriptors are equal if they refer to the same
+/// message in the same [`DescriptorPool`] instance, so they can be used as keys of a `HashMap` without comparing full
+/// names. Descriptors for the same message obtained from different pools, even ones decoded from the same bytes, are
+/// not equal.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct MessageDescriptor {
     pool: DescriptorPool,
     index: MessageIndex,
@@ -210,7 +219,7 @@ struct MessageDescriptorInner {
 }
 
 /// A oneof field in a protobuf message.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct OneofDescriptor {
     message: MessageDescriptor,
     index: OneofIndex,
@@ -222,8 +231,11 @@ struct OneofDescriptorInner {
     fields: Vec<FieldIndex>,
 }
 
-/// A protobuf message definition.
-#[derive(Clone, PartialEq, Eq)]
+/// A protobuf message field definition.
+///
+/// Like [`MessageDescriptor`], field descriptors are compared and hashed by identity, using their pool, parent
+/// message and position within it.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct FieldDescriptor {
     message: MessageDescriptor,
     index: FieldIndex,
@@ -237,13 +249,14 @@ struct FieldDescriptorInner {
     kind: KindIndex,
     oneof: Option<OneofIndex>,
     is_packed: bool,
+    is_debug_redacted: bool,
     supports_presence: bool,
     cardinality: Cardinality,
     default: Option<Value>,
 }
 
 /// A protobuf extension field definition.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct ExtensionDescriptor {
     pool: DescriptorPool,
     index: ExtensionIndex,
@@ -258,12 +271,16 @@ pub struct ExtensionDescriptorInner {
     extendee: MessageIndex,
     kind: KindIndex,
     is_packed: bool,
+    is_debug_redacted: bool,
     cardinality: Cardinality,
     default: Option<Value>,
 }
 
 /// A protobuf enum type.
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Like [`MessageDescriptor`], enum descriptors are compared and hashed by identity within their
+/// [`DescriptorPool`].
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct EnumDescriptor {
     pool: DescriptorPool,
     index: EnumIndex,
@@ -277,10 +294,11 @@ struct EnumDescriptorInner {
     value_numbers: Vec<(i32, EnumValueIndex)>,
     value_names: HashMap<Box<str>, EnumValueIndex>,
     allow_alias: bool,
+    is_closed: bool,
 }
 
 /// A value in a protobuf enum type.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct EnumValueDescriptor {
     parent: EnumDescriptor,
     index: EnumValueIndex,
@@ -293,7 +311,7 @@ struct EnumValueDescriptorInner {
 }
 
 /// A protobuf service definition.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct ServiceDescriptor {
     pool: DescriptorPool,
     index: ServiceIndex,
@@ -306,7 +324,7 @@ struct ServiceDescriptorInner {
 }
 
 /// A method definition for a [`ServiceDescriptor`].
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct MethodDescriptor {
     service: ServiceDescriptor,
     index: MethodIndex,
@@ -2,8 +2,8 @@ use prost_types::{
     field_descriptor_proto::{self, Label, Type},
     source_code_info::Location,
     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
-    SourceCodeInfo,
+    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, OneofDescriptorProto,
+    ServiceDescriptorProto, SourceCodeInfo,
 };
 
 use crate::DescriptorPool;
@@ -504,3 +504,497 @@ fn error_source_location() {
         "myfile.proto:2:9: name 'my.package.Foo' is defined twice"
     );
 }
+
+#[test]
+fn has_source_info() {
+    let file = FileDescriptorProto {
+        name: Some("myfile.proto".to_owned()),
+        package: Some("my.package".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("Foo".to_owned()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let file_with_source_info = FileDescriptorProto {
+        name: Some("myfile2.proto".to_owned()),
+        source_code_info: Some(SourceCodeInfo {
+            location: vec![Location {
+                path: vec![4, 0],
+                span: vec![0, 0, 13],
+                leading_comments: Some(" A message.\n".to_owned()),
+                ..Default::default()
+            }],
+        }),
+        ..file.clone()
+    };
+    let file_with_empty_source_info = FileDescriptorProto {
+        name: Some("myfile3.proto".to_owned()),
+        package: Some("my.other.package".to_owned()),
+        source_code_info: Some(SourceCodeInfo::default()),
+        ..file.clone()
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file.clone(), file_with_empty_source_info],
+    })
+    .unwrap();
+    assert!(!pool.has_source_info());
+    assert!(!pool
+        .get_file_by_name("myfile.proto")
+        .unwrap()
+        .has_source_info());
+    assert!(!pool
+        .get_file_by_name("myfile3.proto")
+        .unwrap()
+        .has_source_info());
+
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![
+            FileDescriptorProto {
+                package: Some("my.package2".to_owned()),
+                ..file
+            },
+            file_with_source_info,
+        ],
+    })
+    .unwrap();
+    assert!(pool.has_source_info());
+    assert!(!pool
+        .get_file_by_name("myfile.proto")
+        .unwrap()
+        .has_source_info());
+    assert!(pool
+        .get_file_by_name("myfile2.proto")
+        .unwrap()
+        .has_source_info());
+}
+
+#[test]
+fn field_supports_presence() {
+    let field = |name: &str, number: i32, label: Label, r#type: Type| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(label as i32),
+        r#type: Some(r#type as i32),
+        type_name: (r#type == Type::Message).then(|| ".MyMessage".to_owned()),
+        ..Default::default()
+    };
+
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![
+            FileDescriptorProto {
+                name: Some("proto3.proto".to_owned()),
+                syntax: Some("proto3".to_owned()),
+                message_type: vec![DescriptorProto {
+                    name: Some("MyMessage".to_owned()),
+                    field: vec![
+                        field("implicit", 1, Label::Optional, Type::Int32),
+                        FieldDescriptorProto {
+                            oneof_index: Some(0),
+                            proto3_optional: Some(true),
+                            ..field("explicit", 2, Label::Optional, Type::Int32)
+                        },
+                        FieldDescriptorProto {
+                            oneof_index: Some(1),
+                            ..field("oneof_member", 3, Label::Optional, Type::String)
+                        },
+                        field("message", 4, Label::Optional, Type::Message),
+                        field("repeated_message", 5, Label::Repeated, Type::Message),
+                    ],
+                    oneof_decl: vec![
+                        OneofDescriptorProto {
+                            name: Some("_explicit".to_owned()),
+                            ..Default::default()
+                        },
+                        OneofDescriptorProto {
+                            name: Some("my_oneof".to_owned()),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            FileDescriptorProto {
+                name: Some("proto2.proto".to_owned()),
+                package: Some("proto2".to_owned()),
+                syntax: Some("proto2".to_owned()),
+                message_type: vec![DescriptorProto {
+                    name: Some("MyMessage".to_owned()),
+                    field: vec![
+                        field("optional", 1, Label::Optional, Type::Int32),
+                        field("required", 2, Label::Required, Type::Int32),
+                        field("repeated", 3, Label::Repeated, Type::Int32),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ],
+    };
+
+    let descriptor_pool = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap();
+    let presence = |message: &str| {
+        descriptor_pool
+            .get_message_by_name(message)
+            .unwrap()
+            .fields()
+            .map(|field| (field.name().to_owned(), field.supports_presence()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        presence("MyMessage"),
+        [
+            ("implicit".to_owned(), false),
+            ("explicit".to_owned(), true),
+            ("oneof_member".to_owned(), true),
+            ("message".to_owned(), true),
+            ("repeated_message".to_owned(), false),
+        ]
+    );
+    assert_eq!(
+        presence("proto2.MyMessage"),
+        [
+            ("optional".to_owned(), true),
+            ("required".to_owned(), true),
+            ("repeated".to_owned(), false),
+        ]
+    );
+}
+
+#[test]
+fn editions_features() {
+    use prost::Message;
+
+    use crate::{
+        descriptor::types::{self, feature_set, FeatureSet, Options},
+        Cardinality, Syntax,
+    };
+
+    fn features<T: Default>(features: FeatureSet) -> Option<Options<T>> {
+        #[derive(Clone, PartialEq, Message)]
+        struct OptionsFeatures {
+            #[prost(message, optional, tag = "50")]
+            features: Option<FeatureSet>,
+        }
+
+        Some(Options {
+            encoded: OptionsFeatures {
+                features: Some(features),
+            }
+            .encode_to_vec(),
+            value: T::default(),
+        })
+    }
+
+    let no_features = FeatureSet {
+        field_presence: None,
+        enum_type: None,
+        repeated_field_encoding: None,
+        message_encoding: None,
+    };
+    let field = |name: &str, number: i32, label: Label, r#type: Type, type_name: &str| {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(r#type as i32),
+            type_name: (!type_name.is_empty()).then(|| type_name.to_owned()),
+            ..Default::default()
+        }
+    };
+    let enum_ = |name: &str, number: i32| EnumDescriptorProto {
+        name: Some(name.to_owned()),
+        value: vec![EnumValueDescriptorProto {
+            name: Some(format!("{}_VALUE", name)),
+            number: Some(number),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let mut file = types::FileDescriptorProto::from_prost(FileDescriptorProto {
+        name: Some("editions.proto".to_owned()),
+        package: Some("editions".to_owned()),
+        syntax: Some("editions".to_owned()),
+        message_type: vec![
+            DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![
+                    field("explicit", 1, Label::Optional, Type::Int32, ""),
+                    field("implicit", 2, Label::Optional, Type::Int32, ""),
+                    field("packed", 3, Label::Repeated, Type::Int32, ""),
+                    field("expanded", 4, Label::Repeated, Type::Int32, ""),
+                    field("required", 5, Label::Optional, Type::Int32, ""),
+                    field("delimited", 6, Label::Optional, Type::Message, "MyMessage"),
+                    field("message", 7, Label::Optional, Type::Message, "MyMessage"),
+                ],
+                ..Default::default()
+            },
+            DescriptorProto {
+                name: Some("ImplicitMessage".to_owned()),
+                field: vec![field("implicit", 1, Label::Optional, Type::Int32, "")],
+                ..Default::default()
+            },
+        ],
+        enum_type: vec![enum_("OPEN", 0), enum_("CLOSED", 1)],
+        ..Default::default()
+    });
+    file.edition = Some(1000);
+    let fields = &mut file.message_type[0].field;
+    fields[1].options = features(FeatureSet {
+        field_presence: Some(feature_set::FieldPresence::Implicit as i32),
+        ..no_features.clone()
+    });
+    fields[3].options = features(FeatureSet {
+        repeated_field_encoding: Some(feature_set::RepeatedFieldEncoding::Expanded as i32),
+        ..no_features.clone()
+    });
+    fields[4].options = features(FeatureSet {
+        field_presence: Some(feature_set::FieldPresence::LegacyRequired as i32),
+        ..no_features.clone()
+    });
+    fields[5].options = features(FeatureSet {
+        message_encoding: Some(feature_set::MessageEncoding::Delimited as i32),
+        ..no_features.clone()
+    });
+    file.message_type[1].options = features(FeatureSet {
+        field_presence: Some(feature_set::FieldPresence::Implicit as i32),
+        ..no_features.clone()
+    });
+    file.enum_type[1].options = features(FeatureSet {
+        enum_type: Some(feature_set::EnumType::Closed as i32),
+        ..no_features
+    });
+
+    let encoded = types::FileDescriptorSet {
+        file: vec![file.clone()],
+    }
+    .encode_to_vec();
+    let pool = DescriptorPool::decode(encoded.as_slice()).unwrap();
+    let file_desc = pool.get_file_by_name("editions.proto").unwrap();
+    assert_eq!(file_desc.syntax(), Syntax::Editions);
+
+    let message = pool.get_message_by_name("editions.MyMessage").unwrap();
+    let field = |name: &str| message.get_field_by_name(name).unwrap();
+    assert!(field("explicit").supports_presence());
+    assert!(!field("implicit").supports_presence());
+    assert!(field("packed").is_packed());
+    assert!(!field("expanded").is_packed());
+    assert_eq!(field("required").cardinality(), Cardinality::Required);
+    assert_eq!(field("explicit").cardinality(), Cardinality::Optional);
+    assert!(field("delimited").is_group());
+    assert!(!field("message").is_group());
+
+    let implicit_message = pool
+        .get_message_by_name("editions.ImplicitMessage")
+        .unwrap();
+    assert!(!implicit_message
+        .get_field_by_name("implicit")
+        .unwrap()
+        .supports_presence());
+
+    assert!(!pool.get_enum_by_name("editions.OPEN").unwrap().is_closed());
+    assert!(pool
+        .get_enum_by_name("editions.CLOSED")
+        .unwrap()
+        .is_closed());
+
+    file.edition = Some(1001);
+    let encoded = types::FileDescriptorSet { file: vec![file] }.encode_to_vec();
+    let err = DescriptorPool::decode(encoded.as_slice()).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported edition '1001'");
+}
+
+#[test]
+fn editions_from_prost_types() {
+    use crate::Syntax;
+
+    let file = FileDescriptorProto {
+        name: Some("editions.proto".to_owned()),
+        package: Some("editions".to_owned()),
+        syntax: Some("editions".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("MyMessage".to_owned()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("explicit".to_owned()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    r#type: Some(Type::Int32 as i32),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("packed".to_owned()),
+                    number: Some(2),
+                    label: Some(Label::Repeated as i32),
+                    r#type: Some(Type::Int32 as i32),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_proto(file).unwrap();
+    assert_eq!(
+        pool.get_file_by_name("editions.proto").unwrap().syntax(),
+        Syntax::Editions
+    );
+
+    let message = pool.get_message_by_name("editions.MyMessage").unwrap();
+    assert!(message
+        .get_field_by_name("explicit")
+        .unwrap()
+        .supports_presence());
+    assert!(message.get_field_by_name("packed").unwrap().is_packed());
+
+    let round_tripped = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: pool.file_descriptor_protos().cloned().collect(),
+    })
+    .unwrap();
+    assert_eq!(
+        round_tripped
+            .get_file_by_name("editions.proto")
+            .unwrap()
+            .syntax(),
+        Syntax::Editions
+    );
+}
+
+#[test]
+fn proto3_packed_with_options() {
+    let field = |name: &str, number: i32, packed: Option<bool>| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(Label::Repeated as i32),
+        r#type: Some(Type::Int32 as i32),
+        options: Some(prost_types::FieldOptions {
+            deprecated: Some(true),
+            packed,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            package: Some("my.package".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("MyMessage".to_owned()),
+                field: vec![
+                    field("default", 1, None),
+                    field("packed", 2, Some(true)),
+                    field("unpacked", 3, Some(false)),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    })
+    .unwrap();
+
+    let message = pool.get_message_by_name("my.package.MyMessage").unwrap();
+    assert!(message.get_field_by_name("default").unwrap().is_packed());
+    assert!(message.get_field_by_name("packed").unwrap().is_packed());
+    assert!(!message.get_field_by_name("unpacked").unwrap().is_packed());
+}
+
+#[test]
+fn debug_redact_option() {
+    use prost::Message;
+
+    use crate::{
+        descriptor::types::{self, Options},
+        DynamicMessage, Value,
+    };
+
+    // The `debug_redact` option is field 16 of `FieldOptions`, which is not yet included in `prost-types`.
+    let debug_redact = || {
+        Some(Options {
+            encoded: b"\x80\x01\x01".to_vec(),
+            value: Default::default(),
+        })
+    };
+    let field = |name: &str, number: i32, label: Label| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        label: Some(label as i32),
+        r#type: Some(Type::String as i32),
+        ..Default::default()
+    };
+
+    let mut file = types::FileDescriptorProto::from_prost(FileDescriptorProto {
+        name: Some("redact.proto".to_owned()),
+        package: Some("redact".to_owned()),
+        syntax: Some("proto3".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("Credentials".to_owned()),
+            field: vec![
+                field("user", 1, Label::Optional),
+                field("password", 2, Label::Optional),
+                field("recovery_codes", 3, Label::Repeated),
+            ],
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    file.message_type[0].field[1].options = debug_redact();
+    file.message_type[0].field[2].options = debug_redact();
+
+    let encoded = types::FileDescriptorSet { file: vec![file] }.encode_to_vec();
+    let pool = DescriptorPool::decode(encoded.as_slice()).unwrap();
+    let message_desc = pool.get_message_by_name("redact.Credentials").unwrap();
+    assert!(!message_desc.get_field(1).unwrap().is_debug_redacted());
+    assert!(message_desc.get_field(2).unwrap().is_debug_redacted());
+    assert!(message_desc.get_field(3).unwrap().is_debug_redacted());
+
+    let mut message = DynamicMessage::new(message_desc);
+    message.set_field_by_name("user", Value::String("alice".to_owned()));
+    message.set_field_by_name("password", Value::String("hunter2".to_owned()));
+    message.set_field_by_name(
+        "recovery_codes",
+        Value::List(vec![Value::String("1234".to_owned())]),
+    );
+    assert_eq!(
+        message.to_string(),
+        "user:\"alice\",password:[REDACTED],recovery_codes:[REDACTED]"
+    );
+
+    #[cfg(feature = "text-format")]
+    {
+        use crate::text_format::FormatOptions;
+
+        let options = FormatOptions::new().pretty(true);
+        assert_eq!(
+            message.to_text_format_with_options(&options),
+            "user: \"alice\"\npassword: [REDACTED]\nrecovery_codes: [REDACTED]"
+        );
+        let options = FormatOptions::new().redact_debug_fields(false);
+        assert_eq!(
+            message.to_text_format_with_options(&options),
+            "user:\"alice\",password:\"hunter2\",recovery_codes:[\"1234\"]"
+        );
+    }
+}
+
+#[test]
+fn unknown_syntax() {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("myfile.proto".to_owned()),
+            syntax: Some("proto4".to_owned()),
+            ..Default::default()
+        }],
+    };
+
+    let err = DescriptorPool::from_file_descriptor_set(file_descriptor_set).unwrap_err();
+    assert_eq!(err.to_string(), "unknown syntax 'proto4'");
+}
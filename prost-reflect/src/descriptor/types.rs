@@ -44,6 +44,8 @@ pub(crate) struct FileDescriptorProto {
     pub source_code_info: Option<SourceCodeInfo>,
     #[prost(string, optional, tag = "12")]
     pub syntax: Option<String>,
+    #[prost(int32, optional, tag = "14")]
+    pub edition: Option<i32>,
 }
 
 #[derive(Clone, PartialEq, Message)]
@@ -176,6 +178,60 @@ pub(crate) struct Options<T> {
     pub(crate) value: T,
 }
 
+/// The value of the `edition` field of files using edition 2023.
+pub(crate) const EDITION_2023: i32 = 1000;
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct FeatureSet {
+    #[prost(enumeration = "feature_set::FieldPresence", optional, tag = "1")]
+    pub field_presence: Option<i32>,
+    #[prost(enumeration = "feature_set::EnumType", optional, tag = "2")]
+    pub enum_type: Option<i32>,
+    #[prost(
+        enumeration = "feature_set::RepeatedFieldEncoding",
+        optional,
+        tag = "3"
+    )]
+    pub repeated_field_encoding: Option<i32>,
+    #[prost(enumeration = "feature_set::MessageEncoding", optional, tag = "5")]
+    pub message_encoding: Option<i32>,
+}
+
+pub(crate) mod feature_set {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub(crate) enum FieldPresence {
+        Unknown = 0,
+        Explicit = 1,
+        Implicit = 2,
+        LegacyRequired = 3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub(crate) enum EnumType {
+        Unknown = 0,
+        Open = 1,
+        Closed = 2,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub(crate) enum RepeatedFieldEncoding {
+        Unknown = 0,
+        Packed = 1,
+        Expanded = 2,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub(crate) enum MessageEncoding {
+        Unknown = 0,
+        LengthPrefixed = 1,
+        Delimited = 2,
+    }
+}
+
 impl FileDescriptorProto {
     pub(crate) fn from_prost(file: prost_types::FileDescriptorProto) -> FileDescriptorProto {
         FileDescriptorProto {
@@ -206,6 +262,9 @@ impl FileDescriptorProto {
                 .collect(),
             options: file.options.map(Options::from_prost),
             source_code_info: file.source_code_info,
+            // The prost-types descriptor has no `edition` field, so files using editions are assumed to use the only
+            // supported edition.
+            edition: (file.syntax.as_deref() == Some("editions")).then_some(EDITION_2023),
             syntax: file.syntax,
         }
     }
@@ -510,6 +569,64 @@ where
     }
 }
 
+impl<T> Options<T> {
+    /// Decodes the `features` field, which is not yet included in the options types from `prost-types`.
+    pub(crate) fn features(&self) -> Option<FeatureSet> {
+        #[derive(Clone, PartialEq, Message)]
+        struct OptionsFeatures {
+            #[prost(message, optional, tag = "50")]
+            features: Option<FeatureSet>,
+        }
+
+        OptionsFeatures::decode(self.encoded.as_slice())
+            .ok()
+            .and_then(|options| options.features)
+    }
+}
+
+impl Options<FieldOptions> {
+    /// Decodes the `debug_redact` field, which is not yet included in the options types from `prost-types`.
+    pub(crate) fn debug_redact(&self) -> bool {
+        #[derive(Clone, PartialEq, Message)]
+        struct OptionsDebugRedact {
+            #[prost(bool, optional, tag = "16")]
+            debug_redact: Option<bool>,
+        }
+
+        OptionsDebugRedact::decode(self.encoded.as_slice())
+            .ok()
+            .and_then(|options| options.debug_redact)
+            .unwrap_or(false)
+    }
+}
+
+/// An options message which includes the standard `deprecated` option.
+pub(crate) trait DeprecatedOption {
+    fn is_deprecated(&self) -> bool;
+}
+
+macro_rules! impl_deprecated_option {
+    ($($ty:ty),*) => {
+        $(
+            impl DeprecatedOption for $ty {
+                fn is_deprecated(&self) -> bool {
+                    self.deprecated()
+                }
+            }
+        )*
+    };
+}
+
+impl_deprecated_option!(
+    FileOptions,
+    MessageOptions,
+    FieldOptions,
+    EnumOptions,
+    EnumValueOptions,
+    ServiceOptions,
+    MethodOptions
+);
+
 impl<T> fmt::Debug for Options<T>
 where
     T: fmt::Debug,
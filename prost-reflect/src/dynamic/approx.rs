@@ -0,0 +1,88 @@
+use crate::{DynamicMessage, Value};
+
+impl DynamicMessage {
+    /// Compares this message with `other`, allowing floating-point values to differ by a relative tolerance.
+    ///
+    /// Two `float` or `double` values are considered equal if they are exactly equal, or if the difference between
+    /// them is at most `epsilon` times the larger of their magnitudes. This applies to floating-point values anywhere
+    /// in the message, including in repeated fields, map values and nested messages. All other values, including
+    /// unknown fields, must be exactly equal.
+    ///
+    /// `NaN` values are considered equal to each other. Use [`approx_eq_with`][Self::approx_eq_with] to treat them
+    /// as unequal, as [`PartialEq`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// let message_descriptor = DescriptorPool::global().get_message_by_name("google.protobuf.DoubleValue").unwrap();
+    /// let mut a = DynamicMessage::new(message_descriptor.clone());
+    /// a.set_field_by_name("value", Value::F64(1.0));
+    /// let mut b = DynamicMessage::new(message_descriptor);
+    /// b.set_field_by_name("value", Value::F64(1.0 + 1e-12));
+    ///
+    /// assert_ne!(a, b);
+    /// assert!(a.approx_eq(&b, 1e-9));
+    /// assert!(!a.approx_eq(&b, 1e-15));
+    /// ```
+    pub fn approx_eq(&self, other: &DynamicMessage, epsilon: f64) -> bool {
+        self.approx_eq_with(other, epsilon, true)
+    }
+
+    /// Compares this message with `other`, allowing floating-point values to differ by a relative tolerance.
+    ///
+    /// If `nan_equal` is `true`, `NaN` values are considered equal to each other. Otherwise, a `NaN` value is not
+    /// equal to any value, including itself. See [`approx_eq`][Self::approx_eq] for more details.
+    pub fn approx_eq_with(&self, other: &DynamicMessage, epsilon: f64, nan_equal: bool) -> bool {
+        if self.desc != other.desc {
+            return false;
+        }
+
+        let mut lhs = self.iter_fields_sorted();
+        let mut rhs = other.iter_fields_sorted();
+        loop {
+            match (lhs.next(), rhs.next()) {
+                (None, None) => break,
+                (Some((l_field, l_value)), Some((r_field, r_value))) => {
+                    if l_field != r_field || !value_approx_eq(l_value, r_value, epsilon, nan_equal)
+                    {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        self.unknown_fields().eq(other.unknown_fields())
+    }
+}
+
+fn value_approx_eq(lhs: &Value, rhs: &Value, epsilon: f64, nan_equal: bool) -> bool {
+    match (lhs, rhs) {
+        (Value::F32(l), Value::F32(r)) => float_approx_eq(*l as f64, *r as f64, epsilon, nan_equal),
+        (Value::F64(l), Value::F64(r)) => float_approx_eq(*l, *r, epsilon, nan_equal),
+        (Value::Message(l), Value::Message(r)) => l.approx_eq_with(r, epsilon, nan_equal),
+        (Value::List(l), Value::List(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .zip(r)
+                    .all(|(l, r)| value_approx_eq(l, r, epsilon, nan_equal))
+        }
+        (Value::Map(l), Value::Map(r)) => {
+            l.len() == r.len()
+                && l.iter().all(|(key, l)| {
+                    r.get(key)
+                        .is_some_and(|r| value_approx_eq(l, r, epsilon, nan_equal))
+                })
+        }
+        _ => lhs == rhs,
+    }
+}
+
+fn float_approx_eq(lhs: f64, rhs: f64, epsilon: f64, nan_equal: bool) -> bool {
+    if lhs.is_nan() || rhs.is_nan() {
+        nan_equal && lhs.is_nan() && rhs.is_nan()
+    } else {
+        lhs == rhs || (lhs - rhs).abs() <= epsilon * lhs.abs().max(rhs.abs())
+    }
+}
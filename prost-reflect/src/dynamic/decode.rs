@@ -0,0 +1,720 @@
+use std::io::{self, Read};
+
+use prost::{
+    bytes::{Buf, Bytes},
+    encoding::{self, DecodeContext, WireType},
+    DecodeError, Message,
+};
+
+use super::{fields::FieldDescriptorLike, is_wire_type_compatible, UnknownField};
+use crate::{DynamicMessage, Kind, MessageDescriptor, Value};
+
+/// The maximum depth of nested messages supported by [`DynamicMessage::decode_lenient()`], matching the recursion
+/// limit used by prost.
+const RECURSION_LIMIT: u32 = 100;
+
+/// Options to control decoding of messages from the protobuf binary format.
+///
+/// Used by [`DynamicMessage::decode_with_options()`].
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    max_field_size: Option<usize>,
+    max_message_size: Option<usize>,
+    max_recursion_depth: Option<u32>,
+    lazy: bool,
+    deny_unknown_fields: bool,
+}
+
+impl DynamicMessage {
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer, with custom
+    /// options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DecodeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let options = DecodeOptions::new().max_field_size(1);
+    /// assert!(DynamicMessage::decode_with_options(message_descriptor.clone(), b"\x1a\x00".as_ref(), &options).is_ok());
+    /// assert!(DynamicMessage::decode_with_options(message_descriptor, b"\x1a\x02\x10\x42".as_ref(), &options).is_err());
+    /// ```
+    pub fn decode_with_options<B>(
+        desc: MessageDescriptor,
+        mut buf: B,
+        options: &DecodeOptions,
+    ) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+    {
+        options.check_message_size(buf.remaining())?;
+        let mut bytes = buf.copy_to_bytes(buf.remaining());
+        options.check(&desc, &bytes)?;
+
+        let mut message = DynamicMessage::new(desc);
+        if options.lazy {
+            message.merge_lazy(&mut bytes)?;
+        } else {
+            message.merge(bytes)?;
+        }
+        Ok(message)
+    }
+
+    /// Decodes a sequence of length-delimited instances of the message type specified by the [`MessageDescriptor`]
+    /// from a reader.
+    ///
+    /// Each message must be prefixed by its length, encoded as a varint, as written by
+    /// [`encode_length_delimited`][Message::encode_length_delimited]. Messages are read one at a time, until the
+    /// reader reaches end-of-file. If an error occurs, the iterator yields it and then stops. Errors from the reader
+    /// are returned unchanged, and errors decoding a message are returned with the kind
+    /// [`InvalidData`][io::ErrorKind::InvalidData].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let bytes = b"\x03\x08\x96\x01\x02\x08\x01".as_ref();
+    /// let messages: Vec<_> = DynamicMessage::decode_stream(message_descriptor, bytes)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(messages.len(), 2);
+    /// assert_eq!(messages[1].get_field_by_name("foo").unwrap().as_ref(), &Value::I32(1));
+    /// ```
+    pub fn decode_stream<R>(
+        desc: MessageDescriptor,
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<DynamicMessage>>
+    where
+        R: Read,
+    {
+        let mut reader = Some(reader);
+        std::iter::from_fn(move || {
+            let result = match read_length_delimited(reader.as_mut()?) {
+                Ok(Some(bytes)) => {
+                    DynamicMessage::decode(desc.clone(), bytes.as_slice()).map_err(io::Error::from)
+                }
+                Ok(None) => return None,
+                Err(err) => Err(err),
+            };
+            if result.is_err() {
+                reader = None;
+            }
+            Some(result)
+        })
+    }
+
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from a reader.
+    ///
+    /// The reader is read until end-of-file, and its contents are decoded as a single message. To read a sequence
+    /// of length-delimited messages, use [`decode_stream()`][Self::decode_stream] instead.
+    ///
+    /// The contents of the reader are buffered in memory before decoding, since prost's [`Buf`] trait cannot
+    /// report errors from the underlying reader. Errors from the reader are returned unchanged, and errors decoding
+    /// the message are returned with the kind [`InvalidData`][io::ErrorKind::InvalidData].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let reader = std::io::Cursor::new(b"\x08\x96\x01".to_vec());
+    /// let dynamic_message = DynamicMessage::decode_from_reader(message_descriptor, reader).unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// ```
+    pub fn decode_from_reader<R>(desc: MessageDescriptor, mut reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(DynamicMessage::decode(desc, buf.as_slice())?)
+    }
+
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from a hex-encoded string.
+    ///
+    /// Each byte must be written as two hexadecimal digits, in either upper or lower case. Leading and trailing
+    /// whitespace is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode_hex(message_descriptor.clone(), "089601").unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// assert!(DynamicMessage::decode_hex(message_descriptor, "08960").is_err());
+    /// ```
+    pub fn decode_hex(desc: MessageDescriptor, s: &str) -> Result<Self, DecodeError> {
+        let bytes = decode_hex(s.trim())?;
+        DynamicMessage::decode(desc, bytes.as_slice())
+    }
+
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer, treating
+    /// fields encoded with an unexpected wire type as unknown fields.
+    ///
+    /// This allows decoding a message written using a newer version of its schema, in which the type of a field was
+    /// changed incompatibly (for example, from `int32` to a message). Such fields would cause
+    /// [`decode()`][Message::decode] to fail, but are instead preserved in [`unknown_fields()`][Self::unknown_fields],
+    /// and are re-encoded unchanged. Fields of nested messages, including groups, repeated messages and map values,
+    /// are decoded in the same way.
+    ///
+    /// A field number may hold either a value or unknown fields, but not both. If a field is encoded with both the
+    /// expected wire type and an unexpected one, the last occurrence wins.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// // Field 1 ('foo') is an int32, but is encoded here as a string.
+    /// let bytes = b"\x0a\x03abc\x1a\x02\x10\x42".as_ref();
+    /// assert!(DynamicMessage::decode(message_descriptor.clone(), bytes).is_err());
+    ///
+    /// let dynamic_message = DynamicMessage::decode_lenient(message_descriptor, bytes).unwrap();
+    /// assert!(!dynamic_message.has_field_by_name("foo"));
+    /// assert_eq!(dynamic_message.unknown_fields().count(), 1);
+    /// assert_eq!(dynamic_message.encode_to_vec(), bytes);
+    /// ```
+    pub fn decode_lenient<B>(desc: MessageDescriptor, mut buf: B) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut bytes = buf.copy_to_bytes(buf.remaining());
+        let mut message = DynamicMessage::new(desc);
+        message.merge_lenient(&mut bytes, RECURSION_LIMIT, None)?;
+        Ok(message)
+    }
+
+    /// Merges the fields in `buf` into this message, treating fields with an unexpected wire type as unknown fields.
+    /// If `group` is set, decoding stops at the end tag of the group with that field number.
+    fn merge_lenient(
+        &mut self,
+        buf: &mut Bytes,
+        depth: u32,
+        group: Option<u32>,
+    ) -> Result<(), DecodeError> {
+        if depth == 0 {
+            return Err(DecodeError::new("recursion limit reached"));
+        }
+
+        while buf.has_remaining() {
+            let (number, wire_type) = encoding::decode_key(buf)?;
+            if wire_type == WireType::EndGroup && group == Some(number) {
+                return Ok(());
+            }
+
+            if let Some(field_desc) = self.desc.get_field(number) {
+                if is_wire_type_compatible(wire_type, &field_desc) {
+                    let value = self.fields.get_mut(&field_desc);
+                    merge_value_lenient(value, &field_desc, wire_type, buf, depth)?;
+                    continue;
+                }
+            } else if let Some(extension_desc) = self.desc.get_extension(number) {
+                if is_wire_type_compatible(wire_type, &extension_desc) {
+                    let value = self.fields.get_mut(&extension_desc);
+                    merge_value_lenient(value, &extension_desc, wire_type, buf, depth)?;
+                    continue;
+                }
+            }
+
+            let field =
+                UnknownField::decode_value(number, wire_type, buf, DecodeContext::default())?;
+            self.fields.replace_with_unknown(number, field);
+        }
+
+        match group {
+            Some(_) => Err(DecodeError::new("unexpected end of group")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl DynamicMessage {
+    /// Merges the fields in `buf` into this message, without decoding non-group message fields until they are
+    /// accessed.
+    pub(super) fn merge_lazy(&mut self, buf: &mut Bytes) -> Result<(), DecodeError> {
+        while buf.has_remaining() {
+            let (number, wire_type) = encoding::decode_key(buf)?;
+            if wire_type == WireType::LengthDelimited {
+                if let Some(field_desc) = self.desc.get_field(number) {
+                    if let Some((message_desc, bytes)) = split_lazy_message(&field_desc, buf)? {
+                        let value = self.fields.get_mut(&field_desc);
+                        merge_value_lazy(value, message_desc, bytes)?;
+                        continue;
+                    }
+                } else if let Some(extension_desc) = self.desc.get_extension(number) {
+                    if let Some((message_desc, bytes)) = split_lazy_message(&extension_desc, buf)? {
+                        let value = self.fields.get_mut(&extension_desc);
+                        merge_value_lazy(value, message_desc, bytes)?;
+                        continue;
+                    }
+                }
+            }
+
+            Message::merge_field(self, number, wire_type, buf, DecodeContext::default())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits off the contents of a length-delimited message field which can be decoded lazily. Returns `None`, without
+/// consuming any bytes, if the field is not a singular or repeated message field.
+fn split_lazy_message(
+    field_desc: &impl FieldDescriptorLike,
+    buf: &mut Bytes,
+) -> Result<Option<(MessageDescriptor, Bytes)>, DecodeError> {
+    let message_desc = match field_desc.kind() {
+        Kind::Message(message_desc) if !field_desc.is_group() && !field_desc.is_map() => {
+            message_desc
+        }
+        _ => return Ok(None),
+    };
+
+    let len = encoding::decode_varint(buf)?;
+    if len > buf.remaining() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    Ok(Some((message_desc, buf.split_to(len as usize))))
+}
+
+fn merge_value_lazy(
+    value: &mut Value,
+    message_desc: MessageDescriptor,
+    mut bytes: Bytes,
+) -> Result<(), DecodeError> {
+    let message = match value {
+        Value::Message(message) => message,
+        Value::List(values) => {
+            values.push(Value::Message(DynamicMessage::new(message_desc.clone())));
+            values.last_mut().unwrap().as_message_mut().unwrap()
+        }
+        value => panic!("expected message or list value, found {:?}", value),
+    };
+
+    if message.fields.merge_pending(&message_desc, bytes.clone()) {
+        Ok(())
+    } else {
+        message.merge_lazy(&mut bytes)
+    }
+}
+
+fn merge_value_lenient(
+    value: &mut Value,
+    field_desc: &impl FieldDescriptorLike,
+    wire_type: WireType,
+    buf: &mut Bytes,
+    depth: u32,
+) -> Result<(), DecodeError> {
+    let message_desc = match field_desc.kind() {
+        Kind::Message(message_desc) => message_desc,
+        _ => return value.merge_field(field_desc, wire_type, buf, DecodeContext::default(), false),
+    };
+
+    // The contents of a group continue up to its end tag, rather than being length-delimited.
+    let mut nested;
+    let (buf, group) = if field_desc.is_group() {
+        (buf, Some(field_desc.number()))
+    } else {
+        let len = encoding::decode_varint(buf)?;
+        if len > buf.remaining() as u64 {
+            return Err(DecodeError::new("buffer underflow"));
+        }
+        nested = buf.split_to(len as usize);
+        (&mut nested, None)
+    };
+
+    if !field_desc.is_list() && !field_desc.is_map() {
+        return value
+            .as_message_mut()
+            .unwrap()
+            .merge_lenient(buf, depth - 1, group);
+    }
+
+    let mut message = DynamicMessage::new(message_desc);
+    message.merge_lenient(buf, depth - 1, group)?;
+    if field_desc.is_map() {
+        let key_desc = message.desc.map_entry_key_field();
+        let value_desc = message.desc.map_entry_value_field();
+        let key = message
+            .get_field(&key_desc)
+            .into_owned()
+            .into_map_key()
+            .unwrap();
+        let entry_value = message.get_field(&value_desc).into_owned();
+        value.as_map_mut().unwrap().insert(key, entry_value);
+    } else {
+        value.as_list_mut().unwrap().push(Value::Message(message));
+    }
+
+    Ok(())
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, DecodeError> {
+    if s.len() % 2 != 0 {
+        return Err(DecodeError::new("invalid hex: odd number of digits"));
+    }
+
+    let digit = |index: usize| {
+        let byte = s.as_bytes()[index];
+        char::from(byte).to_digit(16).ok_or_else(|| {
+            DecodeError::new(format!(
+                "invalid hex: unexpected byte {:#04x} at offset {}",
+                byte, index
+            ))
+        })
+    };
+    (0..s.len())
+        .step_by(2)
+        .map(|index| Ok((digit(index)? << 4 | digit(index + 1)?) as u8))
+        .collect()
+}
+
+/// Reads a single length-delimited message, returning `None` if the reader is at end-of-file.
+fn read_length_delimited(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = 0u64;
+    for i in 0..10 {
+        let byte = match read_byte(reader)? {
+            Some(byte) => byte,
+            None if i == 0 => return Ok(None),
+            None => return Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+
+        // The tenth byte may only contribute the most significant bit of a 64-bit value.
+        if i == 9 && byte > 1 {
+            break;
+        }
+
+        len |= u64::from(byte & 0x7f) << (7 * i);
+        if byte < 0x80 {
+            let mut buf = Vec::new();
+            reader.by_ref().take(len).read_to_end(&mut buf)?;
+            if buf.len() as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            return Ok(Some(buf));
+        }
+    }
+
+    Err(io::Error::new(io::ErrorKind::InvalidData, "invalid varint"))
+}
+
+/// Reads a single byte, retrying if the read is interrupted. Returns `None` at end-of-file.
+fn read_byte(reader: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Creates a new instance of [`DecodeOptions`], with the default options chosen to be consistent with
+    /// [`DynamicMessage::decode()`].
+    pub const fn new() -> Self {
+        DecodeOptions {
+            max_field_size: None,
+            max_message_size: None,
+            max_recursion_depth: None,
+            lazy: false,
+            deny_unknown_fields: false,
+        }
+    }
+
+    /// Sets the maximum size in bytes of any single length-delimited field, such as a string, bytes or message
+    /// field.
+    ///
+    /// Fields nested within messages or groups are also checked. Decoding a message containing a field which exceeds
+    /// this limit fails with an error naming the field number.
+    ///
+    /// By default there is no limit.
+    pub const fn max_field_size(mut self, size: usize) -> Self {
+        self.max_field_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum size in bytes of the encoded message.
+    ///
+    /// Decoding a message larger than this limit fails with an error, before any fields are decoded.
+    ///
+    /// By default there is no limit.
+    pub const fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum depth of nested messages.
+    ///
+    /// The fields of the message being decoded are at depth zero, and each nested message, group or map entry
+    /// increases the depth by one. Decoding a message nested more deeply than this limit fails with an error,
+    /// instead of continuing to recurse.
+    ///
+    /// Setting this limit requires scanning the encoded message before it is decoded. By default, the message is not
+    /// scanned, and only the limit of `100` enforced by prost while decoding applies. Values larger than this have no
+    /// effect unless prost's `no-recursion-limit` feature is enabled.
+    pub const fn max_recursion_depth(mut self, depth: u32) -> Self {
+        self.max_recursion_depth = Some(depth);
+        self
+    }
+
+    /// Whether to defer decoding nested messages until they are first accessed.
+    ///
+    /// If enabled, the contents of singular and repeated message fields are stored in their encoded form when the
+    /// message is decoded. Each nested message is decoded the first time any of its fields are accessed, for example
+    /// using [`DynamicMessage::get_field()`], and the result is cached. Nested messages which are never accessed are
+    /// re-encoded from their original bytes without being decoded. This can greatly reduce the cost of decoding
+    /// large messages when only a few of their fields are used.
+    ///
+    /// Map fields and groups are always decoded immediately. Because nested messages are not fully decoded, errors
+    /// in their contents are not reported by [`DynamicMessage::decode_with_options()`]. If a nested message turns
+    /// out to be invalid when it is accessed, any fields which cannot be decoded are treated as unknown fields, and
+    /// the nested message is re-encoded from its original bytes unless it is modified.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DecodeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let bytes = b"\x08\x96\x01\x1a\x02\x10\x42".as_ref();
+    /// let options = DecodeOptions::new().lazy(true);
+    /// let dynamic_message = DynamicMessage::decode_with_options(message_descriptor, bytes, &options).unwrap();
+    /// assert_eq!(dynamic_message.encode_to_vec(), bytes);
+    ///
+    /// // The nested message is decoded here.
+    /// let nested = dynamic_message.get_field_by_name("nested").unwrap();
+    /// assert_eq!(nested.as_message().unwrap().get_field_by_name("bar").unwrap().as_ref(), &Value::I32(66));
+    /// ```
+    pub const fn lazy(mut self, yes: bool) -> Self {
+        self.lazy = yes;
+        self
+    }
+
+    /// Whether to fail if the message contains a field which is not defined in its descriptor.
+    ///
+    /// By default, fields with unrecognized numbers are preserved in
+    /// [`unknown_fields()`][DynamicMessage::unknown_fields]. If this option is enabled, decoding instead fails with an
+    /// error naming the field number and wire type of the first such field. Fields of nested messages, groups and map
+    /// entries are also checked. Extension fields are only recognized if they are present in the descriptor pool of
+    /// the message being decoded.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DecodeOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// // Field 4 is not defined in the message.
+    /// let bytes = b"\x08\x96\x01\x20\x01".as_ref();
+    /// assert!(DynamicMessage::decode(message_descriptor.clone(), bytes).is_ok());
+    ///
+    /// let options = DecodeOptions::new().deny_unknown_fields(true);
+    /// let err = DynamicMessage::decode_with_options(message_descriptor, bytes, &options).unwrap_err();
+    /// assert!(err.to_string().contains("unknown field 4 with wire type Varint"));
+    /// ```
+    pub const fn deny_unknown_fields(mut self, yes: bool) -> Self {
+        self.deny_unknown_fields = yes;
+        self
+    }
+
+    /// Checks that an encoded message of `len` bytes does not exceed the maximum message size.
+    fn check_message_size(&self, len: usize) -> Result<(), DecodeError> {
+        match self.max_message_size {
+            Some(max_message_size) if len > max_message_size => Err(DecodeError::new(format!(
+                "message of {} bytes exceeds the maximum message size of {} bytes",
+                len, max_message_size
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Scans the encoded message and checks that it does not exceed any of the other limits set in these options.
+    fn check(&self, desc: &MessageDescriptor, buf: &[u8]) -> Result<(), DecodeError> {
+        if let Some(max_recursion_depth) = self.max_recursion_depth {
+            check_recursion_depth(desc, &mut &buf[..], 0, max_recursion_depth, None)?;
+        }
+
+        if self.deny_unknown_fields {
+            check_unknown_fields(desc, &mut &buf[..], None)?;
+        }
+
+        match self.max_field_size {
+            Some(max_field_size) => check_field_sizes(&mut &buf[..], max_field_size),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Scans the fields of an encoded message, recursing into nested messages and groups to check that they do not
+/// exceed `max_depth`. If `group` is set, the scan stops at the end tag of the group with that field number.
+fn check_recursion_depth(
+    desc: &MessageDescriptor,
+    buf: &mut &[u8],
+    depth: u32,
+    max_depth: u32,
+    group: Option<u32>,
+) -> Result<(), DecodeError> {
+    while buf.has_remaining() {
+        let (number, wire_type) = encoding::decode_key(buf)?;
+        if wire_type == WireType::EndGroup {
+            return if group == Some(number) {
+                Ok(())
+            } else {
+                Err(DecodeError::new("unexpected end group tag"))
+            };
+        }
+
+        let kind = match desc.get_field(number) {
+            Some(field_desc) => Some(field_desc.kind()),
+            None => desc
+                .get_extension(number)
+                .map(|extension_desc| extension_desc.kind()),
+        };
+
+        match (wire_type, kind) {
+            (WireType::LengthDelimited | WireType::StartGroup, Some(Kind::Message(child))) => {
+                if depth >= max_depth {
+                    return Err(DecodeError::new(format!(
+                        "message exceeds the maximum recursion depth of {}",
+                        max_depth
+                    )));
+                }
+
+                if wire_type == WireType::StartGroup {
+                    check_recursion_depth(&child, buf, depth + 1, max_depth, Some(number))?;
+                } else {
+                    let len = encoding::decode_varint(buf)?;
+                    if len > buf.remaining() as u64 {
+                        return Err(DecodeError::new("buffer underflow"));
+                    }
+                    let (mut nested, rest) = buf.split_at(len as usize);
+                    check_recursion_depth(&child, &mut nested, depth + 1, max_depth, None)?;
+                    *buf = rest;
+                }
+            }
+            _ => encoding::skip_field(wire_type, number, buf, DecodeContext::default())?,
+        }
+    }
+
+    match group {
+        Some(_) => Err(DecodeError::new("unexpected end of group")),
+        None => Ok(()),
+    }
+}
+
+/// Scans the fields of an encoded message, recursing into nested messages and groups to check that every field is
+/// defined in its descriptor. If `group` is set, the scan stops at the end tag of the group with that field number.
+fn check_unknown_fields(
+    desc: &MessageDescriptor,
+    buf: &mut &[u8],
+    group: Option<u32>,
+) -> Result<(), DecodeError> {
+    while buf.has_remaining() {
+        let (number, wire_type) = encoding::decode_key(buf)?;
+        if wire_type == WireType::EndGroup {
+            return if group == Some(number) {
+                Ok(())
+            } else {
+                Err(DecodeError::new("unexpected end group tag"))
+            };
+        }
+
+        let kind = match desc.get_field(number) {
+            Some(field_desc) => field_desc.kind(),
+            None => match desc.get_extension(number) {
+                Some(extension_desc) => extension_desc.kind(),
+                None => {
+                    return Err(DecodeError::new(format!(
+                        "unknown field {} with wire type {:?} in message '{}'",
+                        number,
+                        wire_type,
+                        desc.full_name()
+                    )))
+                }
+            },
+        };
+
+        match (wire_type, kind) {
+            (WireType::StartGroup, Kind::Message(child)) => {
+                check_unknown_fields(&child, buf, Some(number))?;
+            }
+            (WireType::LengthDelimited, Kind::Message(child)) => {
+                let len = encoding::decode_varint(buf)?;
+                if len > buf.remaining() as u64 {
+                    return Err(DecodeError::new("buffer underflow"));
+                }
+                let (mut nested, rest) = buf.split_at(len as usize);
+                check_unknown_fields(&child, &mut nested, None)?;
+                *buf = rest;
+            }
+            _ => encoding::skip_field(wire_type, number, buf, DecodeContext::default())?,
+        }
+    }
+
+    match group {
+        Some(_) => Err(DecodeError::new("unexpected end of group")),
+        None => Ok(()),
+    }
+}
+
+/// Scans the fields of an encoded message, checking that no length-delimited field is larger than
+/// `max_field_size`.
+fn check_field_sizes(buf: &mut &[u8], max_field_size: usize) -> Result<(), DecodeError> {
+    // Fields nested within a length-delimited field are never larger than their parent, so only the outermost
+    // fields need to be checked. The contents of groups are not length-delimited, so they are scanned as well.
+    while buf.has_remaining() {
+        let (number, wire_type) = encoding::decode_key(buf)?;
+        let len = match wire_type {
+            WireType::Varint => {
+                encoding::decode_varint(buf)?;
+                0
+            }
+            WireType::SixtyFourBit => 8,
+            WireType::ThirtyTwoBit => 4,
+            WireType::LengthDelimited => {
+                let len = encoding::decode_varint(buf)?;
+                if len > max_field_size as u64 {
+                    return Err(DecodeError::new(format!(
+                        "field {} exceeds the maximum field size of {} bytes",
+                        number, max_field_size
+                    )));
+                }
+                len as usize
+            }
+            WireType::StartGroup | WireType::EndGroup => 0,
+        };
+
+        if len > buf.remaining() {
+            return Err(DecodeError::new("buffer underflow"));
+        }
+        buf.advance(len);
+    }
+
+    Ok(())
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
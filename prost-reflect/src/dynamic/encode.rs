@@ -0,0 +1,432 @@
+use prost::{
+    bytes::{Buf, BufMut},
+    encoding::{self, DecodeContext, WireType},
+    DecodeError, EncodeError, Message,
+};
+
+use super::{
+    fields::{FieldDescriptorLike, PackedOverride, ValueAndDescriptor},
+    message::WireSink,
+    unknown::UnknownField,
+};
+use crate::{
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value,
+};
+
+/// Options to control encoding of messages to the protobuf binary format.
+///
+/// Used by [`DynamicMessage::encode_with_options()`].
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    include_unknown_fields: bool,
+    /// The containing message and number of each field which is forced to use the unpacked encoding.
+    unpacked_fields: Vec<(MessageDescriptor, u32)>,
+}
+
+impl DynamicMessage {
+    /// Encodes the message to a buffer.
+    ///
+    /// The encoded message is appended to any existing contents of `buf`, so several messages can be written to a
+    /// single buffer. Any unknown fields are included in the output. This is equivalent to [`Message::encode`], but
+    /// does not require the trait to be imported.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::bytes::BytesMut;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x20\x01".as_ref()).unwrap();
+    /// let mut buf = BytesMut::new();
+    /// dynamic_message.encode(&mut buf).unwrap();
+    /// dynamic_message.encode(&mut buf).unwrap();
+    /// assert_eq!(buf.as_ref(), b"\x08\x96\x01\x20\x01\x08\x96\x01\x20\x01");
+    /// ```
+    pub fn encode<B>(&self, buf: &mut B) -> Result<(), EncodeError>
+    where
+        B: BufMut,
+    {
+        Message::encode(self, buf)
+    }
+
+    /// Encodes the message to a buffer, with custom options.
+    ///
+    /// Messages decoded with [`decode_preserving_wire_order()`](Self::decode_preserving_wire_order) keep their wire
+    /// order, as with [`encode()`](Self::encode).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, EncodeOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x20\x01".as_ref()).unwrap();
+    /// let mut buf = Vec::new();
+    /// let options = EncodeOptions::new().include_unknown_fields(false);
+    /// dynamic_message.encode_with_options(&mut buf, &options).unwrap();
+    /// assert_eq!(buf, b"\x08\x96\x01");
+    /// ```
+    pub fn encode_with_options<B>(
+        &self,
+        buf: &mut B,
+        options: &EncodeOptions,
+    ) -> Result<(), EncodeError>
+    where
+        B: BufMut,
+    {
+        if options.include_unknown_fields && options.unpacked_fields.is_empty() {
+            Message::encode(self, buf)
+        } else {
+            Message::encode(&WithOptions(self, options), buf)
+        }
+    }
+
+    fn encode_raw_with_options(&self, buf: &mut impl BufMut, options: &EncodeOptions) {
+        if let Some(wire_order) = self.fields.wire_order() {
+            let mut sink = EncodeWithOptionsSink {
+                buf,
+                message: &self.desc,
+                options,
+            };
+            return self.write_in_wire_order(wire_order, &mut sink);
+        }
+
+        for field in self.fields.iter(&self.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    encode_field_with_options(&value, &field_desc, &self.desc, buf, options)
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    encode_field_with_options(&value, &extension_desc, &self.desc, buf, options)
+                }
+                ValueAndDescriptor::Unknown(unknowns) => {
+                    if options.include_unknown_fields {
+                        unknowns.encode_raw(buf);
+                    }
+                }
+            }
+        }
+    }
+
+    fn encoded_len_with_options(&self, options: &EncodeOptions) -> usize {
+        if let Some(wire_order) = self.fields.wire_order() {
+            let mut sink = EncodedLenWithOptionsSink {
+                len: 0,
+                message: &self.desc,
+                options,
+            };
+            self.write_in_wire_order(wire_order, &mut sink);
+            return sink.len;
+        }
+
+        self.fields
+            .iter(&self.desc)
+            .map(|field| match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    field_encoded_len_with_options(&value, &field_desc, &self.desc, options)
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    field_encoded_len_with_options(&value, &extension_desc, &self.desc, options)
+                }
+                ValueAndDescriptor::Unknown(unknowns) => {
+                    if options.include_unknown_fields {
+                        unknowns.encoded_len()
+                    } else {
+                        0
+                    }
+                }
+            })
+            .sum()
+    }
+}
+
+/// Encodes a field of `message`, including any messages nested within it, using the given options.
+fn encode_field_with_options(
+    value: &Value,
+    field_desc: &impl FieldDescriptorLike,
+    message: &MessageDescriptor,
+    buf: &mut impl BufMut,
+    options: &EncodeOptions,
+) {
+    let number = field_desc.number();
+    match (value, field_desc.kind()) {
+        (Value::Message(nested), _) if field_desc.is_group() => {
+            encoding::encode_key(number, WireType::StartGroup, buf);
+            nested.encode_raw_with_options(buf, options);
+            encoding::encode_key(number, WireType::EndGroup, buf);
+        }
+        (Value::Message(nested), _) => {
+            encoding::encode_key(number, WireType::LengthDelimited, buf);
+            encoding::encode_varint(nested.encoded_len_with_options(options) as u64, buf);
+            nested.encode_raw_with_options(buf, options);
+        }
+        (Value::List(values), Kind::Message(_)) => {
+            for value in values {
+                encode_field_with_options(value, field_desc, message, buf, options);
+            }
+        }
+        (Value::Map(values), Kind::Message(entry_desc)) => {
+            let key_desc = entry_desc.map_entry_key_field();
+            let value_desc = entry_desc.map_entry_value_field();
+            for (key, value) in values {
+                encode_map_entry_with_options(
+                    number,
+                    key,
+                    value,
+                    &key_desc,
+                    &value_desc,
+                    buf,
+                    options,
+                );
+            }
+        }
+        (Value::List(_), _) if field_desc.is_packed() && options.is_unpacked(message, number) => {
+            value.encode_field(&PackedOverride::new(field_desc, false), buf)
+        }
+        _ => value.encode_field(field_desc, buf),
+    }
+}
+
+/// Gets the encoded length of a field of `message` when written by [`encode_field_with_options`].
+fn field_encoded_len_with_options(
+    value: &Value,
+    field_desc: &impl FieldDescriptorLike,
+    message: &MessageDescriptor,
+    options: &EncodeOptions,
+) -> usize {
+    let number = field_desc.number();
+    match (value, field_desc.kind()) {
+        (Value::Message(nested), _) if field_desc.is_group() => {
+            2 * encoding::key_len(number) + nested.encoded_len_with_options(options)
+        }
+        (Value::Message(nested), _) => {
+            let len = nested.encoded_len_with_options(options);
+            encoding::key_len(number) + encoding::encoded_len_varint(len as u64) + len
+        }
+        (Value::List(values), Kind::Message(_)) => values
+            .iter()
+            .map(|value| field_encoded_len_with_options(value, field_desc, message, options))
+            .sum(),
+        (Value::Map(values), Kind::Message(entry_desc)) => {
+            let key_desc = entry_desc.map_entry_key_field();
+            let value_desc = entry_desc.map_entry_value_field();
+            values
+                .iter()
+                .map(|(key, value)| {
+                    map_entry_encoded_len_with_options(
+                        number,
+                        key,
+                        value,
+                        &key_desc,
+                        &value_desc,
+                        options,
+                    )
+                })
+                .sum()
+        }
+        (Value::List(_), _) if field_desc.is_packed() && options.is_unpacked(message, number) => {
+            value.encoded_len(&PackedOverride::new(field_desc, false))
+        }
+        _ => value.encoded_len(field_desc),
+    }
+}
+
+fn encode_map_entry_with_options(
+    number: u32,
+    key: &MapKey,
+    value: &Value,
+    key_desc: &FieldDescriptor,
+    value_desc: &FieldDescriptor,
+    buf: &mut impl BufMut,
+    options: &EncodeOptions,
+) {
+    let entry_desc = value_desc.parent_message();
+    let len = key.encoded_len(key_desc)
+        + field_encoded_len_with_options(value, value_desc, entry_desc, options);
+    encoding::encode_key(number, WireType::LengthDelimited, buf);
+    encoding::encode_varint(len as u64, buf);
+    key.encode_field(key_desc, buf);
+    encode_field_with_options(value, value_desc, entry_desc, buf, options);
+}
+
+fn map_entry_encoded_len_with_options(
+    number: u32,
+    key: &MapKey,
+    value: &Value,
+    key_desc: &FieldDescriptor,
+    value_desc: &FieldDescriptor,
+    options: &EncodeOptions,
+) -> usize {
+    let entry_desc = value_desc.parent_message();
+    let len = key.encoded_len(key_desc)
+        + field_encoded_len_with_options(value, value_desc, entry_desc, options);
+    encoding::key_len(number) + encoding::encoded_len_varint(len as u64) + len
+}
+
+/// Encodes the records of `message`, written in wire order, using the given options.
+struct EncodeWithOptionsSink<'a, B> {
+    buf: &'a mut B,
+    message: &'a MessageDescriptor,
+    options: &'a EncodeOptions,
+}
+
+impl<B: BufMut> WireSink for EncodeWithOptionsSink<'_, B> {
+    fn field(&mut self, value: &Value, field_desc: &impl FieldDescriptorLike) {
+        encode_field_with_options(value, field_desc, self.message, self.buf, self.options)
+    }
+
+    fn map_entry(
+        &mut self,
+        number: u32,
+        key: &MapKey,
+        value: &Value,
+        key_desc: &FieldDescriptor,
+        value_desc: &FieldDescriptor,
+    ) {
+        encode_map_entry_with_options(
+            number,
+            key,
+            value,
+            key_desc,
+            value_desc,
+            self.buf,
+            self.options,
+        )
+    }
+
+    fn unknown(&mut self, unknown: &UnknownField) {
+        if self.options.include_unknown_fields {
+            unknown.encode(self.buf)
+        }
+    }
+}
+
+/// Sums the encoded lengths of the records of `message`, written in wire order, using the given options.
+struct EncodedLenWithOptionsSink<'a> {
+    len: usize,
+    message: &'a MessageDescriptor,
+    options: &'a EncodeOptions,
+}
+
+impl WireSink for EncodedLenWithOptionsSink<'_> {
+    fn field(&mut self, value: &Value, field_desc: &impl FieldDescriptorLike) {
+        self.len += field_encoded_len_with_options(value, field_desc, self.message, self.options);
+    }
+
+    fn map_entry(
+        &mut self,
+        number: u32,
+        key: &MapKey,
+        value: &Value,
+        key_desc: &FieldDescriptor,
+        value_desc: &FieldDescriptor,
+    ) {
+        self.len += map_entry_encoded_len_with_options(
+            number,
+            key,
+            value,
+            key_desc,
+            value_desc,
+            self.options,
+        );
+    }
+
+    fn unknown(&mut self, unknown: &UnknownField) {
+        if self.options.include_unknown_fields {
+            self.len += unknown.encoded_len();
+        }
+    }
+}
+
+/// A message encoded with custom options.
+///
+/// [`EncodeError`] can only be constructed by prost, so this allows [`Message::encode`] to check the capacity of the
+/// buffer before the message is written directly into it. It is never decoded into.
+#[derive(Debug)]
+struct WithOptions<'a>(&'a DynamicMessage, &'a EncodeOptions);
+
+impl Message for WithOptions<'_> {
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        self.0.encode_raw_with_options(buf, self.1)
+    }
+
+    fn merge_field(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        encoding::skip_field(wire_type, number, buf, ctx)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len_with_options(self.1)
+    }
+
+    fn clear(&mut self) {}
+}
+
+impl EncodeOptions {
+    /// Creates a new instance of [`EncodeOptions`], with the default options chosen to be consistent with
+    /// [`Message::encode()`].
+    pub const fn new() -> Self {
+        EncodeOptions {
+            include_unknown_fields: true,
+            unpacked_fields: Vec::new(),
+        }
+    }
+
+    /// Whether to include unknown fields in the output.
+    ///
+    /// If set to `false`, unknown fields are omitted from this message and any message nested within it. The
+    /// message itself is not modified.
+    ///
+    /// The default value is `true`.
+    pub const fn include_unknown_fields(mut self, yes: bool) -> Self {
+        self.include_unknown_fields = yes;
+        self
+    }
+
+    /// Encodes the given `repeated` field using the unpacked encoding, even if it is packed according to its
+    /// descriptor.
+    ///
+    /// This may be called multiple times to override several fields, including fields of nested message types. It
+    /// is useful when communicating with an old decoder which cannot read packed fields. Decoders are required to
+    /// accept both encodings, so the output can still be decoded by any conforming implementation.
+    ///
+    /// By default, the packed-ness of each field's descriptor is used.
+    pub fn force_unpacked(mut self, field: &FieldDescriptor) -> Self {
+        self.unpacked_fields
+            .push((field.parent_message().clone(), field.number()));
+        self
+    }
+
+    /// Encodes the given `repeated` extension field using the unpacked encoding, even if it is packed according to
+    /// its descriptor.
+    ///
+    /// See [`force_unpacked()`](Self::force_unpacked) for details.
+    pub fn force_unpacked_extension(mut self, extension: &ExtensionDescriptor) -> Self {
+        self.unpacked_fields
+            .push((extension.containing_message(), extension.number()));
+        self
+    }
+
+    fn is_unpacked(&self, message: &MessageDescriptor, number: u32) -> bool {
+        self.unpacked_fields
+            .iter()
+            .any(|(unpacked_message, unpacked_number)| {
+                unpacked_message == message && *unpacked_number == number
+            })
+    }
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,13 +3,24 @@ use std::{
     collections::btree_map::{self, BTreeMap},
     fmt,
     mem::replace,
+    ops::Range,
+};
+
+use once_cell::sync::OnceCell;
+use prost::{
+    bytes::{Buf, Bytes},
+    encoding::{self, DecodeContext},
 };
 
 use crate::{
-    ExtensionDescriptor, FieldDescriptor, Kind, MessageDescriptor, OneofDescriptor, Value,
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor,
+    OneofDescriptor, Value,
 };
 
-use super::unknown::{UnknownField, UnknownFieldSet};
+use super::{
+    unknown::{UnknownField, UnknownFieldSet},
+    FieldOrExtension,
+};
 
 pub(crate) trait FieldDescriptorLike: fmt::Debug {
     #[cfg(feature = "text-format")]
@@ -32,11 +43,66 @@ pub(crate) trait FieldDescriptorLike: fmt::Debug {
 }
 
 /// A set of extension fields in a protobuf message.
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone)]
 pub(super) struct DynamicMessageFieldSet {
     fields: BTreeMap<u32, ValueOrUnknown>,
 }
 
+/// The key of the [`ExtraState`] entry in the fields of a message, if present. Field numbers start at 1, so this never
+/// collides with a field.
+const EXTRA_STATE_NUMBER: u32 = 0;
+
+/// State used by messages which preserve wire order or are decoded lazily.
+///
+/// This is stored as an entry in the fields of the message, rather than alongside them, to avoid increasing the size
+/// of messages which use neither feature.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(super) struct ExtraState {
+    /// The records of the message in the order they were decoded, if wire order is being preserved.
+    wire_order: Option<Vec<WireRecord>>,
+    /// The encoded fields of a lazily decoded message, until they are modified.
+    pending: Option<PendingFields>,
+}
+
+/// A run of consecutive records with the same field number, in the order they were decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct WireRecord {
+    pub(super) number: u32,
+    pub(super) elements: WireElements,
+}
+
+/// The part of a field's value which was decoded from a [`WireRecord`].
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum WireElements {
+    /// The whole value of a singular field.
+    Single,
+    /// A range of the elements of a list field, or of the unknown fields with this number, each encoded as a
+    /// separate record.
+    Unpacked(Range<usize>),
+    /// A range of the elements of a list field, encoded as a single packed record.
+    Packed(Range<usize>),
+    /// The keys of the entries of a map field, each encoded as a separate record.
+    MapKeys(Vec<MapKey>),
+}
+
+/// The encoded contents of a message which may not have been decoded yet.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct PendingFields {
+    desc: MessageDescriptor,
+    /// Each buffer is a complete encoded message, which are merged in order when decoded.
+    bytes: Vec<Bytes>,
+    /// The decoded fields, initialized the first time they are accessed.
+    decoded: OnceCell<DecodedFields>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DecodedFields {
+    fields: BTreeMap<u32, ValueOrUnknown>,
+    /// Whether all fields were decoded successfully. If not, the records which could not be decoded are stored as
+    /// unknown fields, and the message is still encoded from the original bytes until it is modified.
+    valid: bool,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(super) enum ValueOrUnknown {
     /// Used to implement draining iterators.
@@ -45,6 +111,8 @@ pub(super) enum ValueOrUnknown {
     Value(Value),
     /// One or more unknown fields.
     Unknown(UnknownFieldSet),
+    /// Additional state of the message, stored with the key [`EXTRA_STATE_NUMBER`].
+    Extra(Box<ExtraState>),
 }
 
 pub(super) enum ValueAndDescriptor<'a> {
@@ -54,10 +122,133 @@ pub(super) enum ValueAndDescriptor<'a> {
 }
 
 impl DynamicMessageFieldSet {
+    fn extra(&self) -> Option<&ExtraState> {
+        match self.fields.get(&EXTRA_STATE_NUMBER) {
+            Some(ValueOrUnknown::Extra(extra)) => Some(extra),
+            _ => None,
+        }
+    }
+
+    fn extra_mut(&mut self) -> Option<&mut ExtraState> {
+        match self.fields.get_mut(&EXTRA_STATE_NUMBER) {
+            Some(ValueOrUnknown::Extra(extra)) => Some(extra),
+            _ => None,
+        }
+    }
+
+    fn extra_or_default(&mut self) -> &mut ExtraState {
+        match self
+            .fields
+            .entry(EXTRA_STATE_NUMBER)
+            .or_insert_with(|| ValueOrUnknown::Extra(Box::default()))
+        {
+            ValueOrUnknown::Extra(extra) => extra,
+            _ => unreachable!(),
+        }
+    }
+
+    fn map(&self) -> &BTreeMap<u32, ValueOrUnknown> {
+        match self.extra().and_then(|extra| extra.pending.as_ref()) {
+            Some(pending) => {
+                &pending
+                    .decoded
+                    .get_or_init(|| decode_pending(pending))
+                    .fields
+            }
+            None => &self.fields,
+        }
+    }
+
+    fn map_mut(&mut self) -> &mut BTreeMap<u32, ValueOrUnknown> {
+        if let Some(pending) = self.extra_mut().and_then(|extra| extra.pending.take()) {
+            let decoded = match pending.decoded.get() {
+                Some(_) => pending.decoded.into_inner().unwrap(),
+                None => decode_pending(&pending),
+            };
+            self.fields.extend(decoded.fields);
+        }
+        &mut self.fields
+    }
+
+    /// Adds the encoded message in `bytes` to the fields which will be decoded when this message is first accessed.
+    ///
+    /// Returns `false` if the fields of this message have already been set or accessed, in which case `bytes` should
+    /// be merged normally.
+    pub(super) fn merge_pending(&mut self, desc: &MessageDescriptor, bytes: Bytes) -> bool {
+        let is_empty = self
+            .fields
+            .keys()
+            .all(|&number| number == EXTRA_STATE_NUMBER);
+        let extra = self.extra_or_default();
+        match &mut extra.pending {
+            Some(pending) if pending.decoded.get().is_none() => {
+                pending.bytes.push(bytes);
+                true
+            }
+            None if is_empty => {
+                extra.pending = Some(PendingFields {
+                    desc: desc.clone(),
+                    bytes: vec![bytes],
+                    decoded: OnceCell::new(),
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Gets the encoded fields of a lazily decoded message, if they have not been accessed yet, or could not be
+    /// decoded.
+    pub(super) fn pending(&self) -> Option<&[Bytes]> {
+        match self.extra().and_then(|extra| extra.pending.as_ref()) {
+            Some(pending) if pending.decoded.get().map_or(true, |decoded| !decoded.valid) => {
+                Some(&pending.bytes)
+            }
+            _ => None,
+        }
+    }
+
+    pub(super) fn preserve_wire_order(&mut self) {
+        let extra = self.extra_or_default();
+        if extra.wire_order.is_none() {
+            extra.wire_order = Some(Vec::new());
+        }
+    }
+
+    pub(super) fn preserves_wire_order(&self) -> bool {
+        self.wire_order().is_some()
+    }
+
+    /// Records that `elements` of the field with the given number were decoded from the next record on the wire.
+    pub(super) fn record_wire_order(&mut self, number: u32, elements: WireElements) {
+        if let Some(wire_order) = self.extra_mut().and_then(|extra| extra.wire_order.as_mut()) {
+            let elements = match wire_order.last_mut() {
+                Some(last) if last.number == number => match last.elements.coalesce(elements) {
+                    Ok(()) => return,
+                    Err(elements) => elements,
+                },
+                _ => elements,
+            };
+            wire_order.push(WireRecord { number, elements });
+        }
+    }
+
+    /// Discards the recorded wire order of a field whose value has been replaced.
+    fn forget_wire_order(&mut self, number: u32) {
+        if let Some(wire_order) = self.extra_mut().and_then(|extra| extra.wire_order.as_mut()) {
+            wire_order.retain(|record| record.number != number);
+        }
+    }
+
+    pub(super) fn wire_order(&self) -> Option<&[WireRecord]> {
+        self.extra().and_then(|extra| extra.wire_order.as_deref())
+    }
+
     fn get_value(&self, number: u32) -> Option<&Value> {
-        match self.fields.get(&number) {
+        match self.map().get(&number) {
             Some(ValueOrUnknown::Value(value)) => Some(value),
-            Some(ValueOrUnknown::Unknown(_) | ValueOrUnknown::Taken) | None => None,
+            Some(ValueOrUnknown::Unknown(_) | ValueOrUnknown::Taken | ValueOrUnknown::Extra(_))
+            | None => None,
         }
     }
 
@@ -76,7 +267,7 @@ impl DynamicMessageFieldSet {
 
     pub(super) fn get_mut(&mut self, desc: &impl FieldDescriptorLike) -> &mut Value {
         self.clear_oneof_fields(desc);
-        match self.fields.entry(desc.number()) {
+        match self.map_mut().entry(desc.number()) {
             btree_map::Entry::Occupied(entry) => match entry.into_mut() {
                 ValueOrUnknown::Value(value) => value,
                 value => {
@@ -99,7 +290,8 @@ impl DynamicMessageFieldSet {
         );
 
         self.clear_oneof_fields(desc);
-        self.fields
+        self.forget_wire_order(desc.number());
+        self.map_mut()
             .insert(desc.number(), ValueOrUnknown::Value(value));
     }
 
@@ -114,9 +306,9 @@ impl DynamicMessageFieldSet {
     }
 
     pub(crate) fn add_unknown(&mut self, number: u32, unknown: UnknownField) {
-        match self.fields.entry(number) {
+        match self.map_mut().entry(number) {
             btree_map::Entry::Occupied(mut entry) => match entry.get_mut() {
-                ValueOrUnknown::Value(_) => {
+                ValueOrUnknown::Value(_) | ValueOrUnknown::Extra(_) => {
                     panic!("expected no field to be found with number {}", number)
                 }
                 value @ ValueOrUnknown::Taken => {
@@ -132,12 +324,22 @@ impl DynamicMessageFieldSet {
         }
     }
 
+    /// Adds an unknown field, discarding any value already set for the same field number.
+    pub(super) fn replace_with_unknown(&mut self, number: u32, unknown: UnknownField) {
+        if self.get_value(number).is_some() {
+            self.map_mut().remove(&number);
+        }
+        self.add_unknown(number, unknown);
+    }
+
     pub(super) fn clear(&mut self, desc: &impl FieldDescriptorLike) {
-        self.fields.remove(&desc.number());
+        self.forget_wire_order(desc.number());
+        self.map_mut().remove(&desc.number());
     }
 
     pub(crate) fn take(&mut self, desc: &impl FieldDescriptorLike) -> Option<Value> {
-        match self.fields.remove(&desc.number()) {
+        self.forget_wire_order(desc.number());
+        match self.map_mut().remove(&desc.number()) {
             Some(ValueOrUnknown::Value(value)) if desc.has(&value) => Some(value),
             _ => None,
         }
@@ -147,32 +349,26 @@ impl DynamicMessageFieldSet {
         &'a self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = ValueAndDescriptor> + 'a {
-        self.fields
+        self.map()
             .iter()
-            .filter_map(move |(&number, value)| match value {
-                ValueOrUnknown::Value(value) => {
-                    if let Some(field) = message.get_field(number) {
-                        if field.has(value) {
-                            Some(ValueAndDescriptor::Field(Cow::Borrowed(value), field))
-                        } else {
-                            None
-                        }
-                    } else if let Some(extension) = message.get_extension(number) {
-                        if extension.has(value) {
-                            Some(ValueAndDescriptor::Extension(
-                                Cow::Borrowed(value),
-                                extension,
-                            ))
-                        } else {
-                            None
-                        }
-                    } else {
-                        panic!("no field found with number {}", number)
-                    }
-                }
-                ValueOrUnknown::Unknown(unknown) => Some(ValueAndDescriptor::Unknown(unknown)),
-                ValueOrUnknown::Taken => None,
-            })
+            .filter_map(move |(&number, value)| value_and_descriptor(message, number, value))
+    }
+
+    pub(super) fn numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.map()
+            .keys()
+            .copied()
+            .filter(|&number| number != EXTRA_STATE_NUMBER)
+    }
+
+    pub(super) fn get_by_number<'a>(
+        &'a self,
+        message: &MessageDescriptor,
+        number: u32,
+    ) -> Option<ValueAndDescriptor<'a>> {
+        self.map()
+            .get(&number)
+            .and_then(|value| value_and_descriptor(message, number, value))
     }
 
     #[cfg(feature = "serde")]
@@ -185,7 +381,7 @@ impl DynamicMessageFieldSet {
             .filter(move |f| !f.supports_presence() || self.has(f))
             .map(move |f| ValueAndDescriptor::Field(self.get(&f), f));
         let others = self
-            .fields
+            .map()
             .iter()
             .filter_map(move |(&number, value)| match value {
                 ValueOrUnknown::Value(value) => {
@@ -203,7 +399,7 @@ impl DynamicMessageFieldSet {
                     }
                 }
                 ValueOrUnknown::Unknown(unknown) => Some(ValueAndDescriptor::Unknown(unknown)),
-                ValueOrUnknown::Taken => None,
+                ValueOrUnknown::Taken | ValueOrUnknown::Extra(_) => None,
             });
         fields.chain(others)
     }
@@ -212,7 +408,7 @@ impl DynamicMessageFieldSet {
         &'a self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = (FieldDescriptor, &'a Value)> + 'a {
-        self.fields.iter().filter_map(move |(&number, value)| {
+        self.map().iter().filter_map(move |(&number, value)| {
             let value = match value {
                 ValueOrUnknown::Value(value) => value,
                 _ => return None,
@@ -233,7 +429,7 @@ impl DynamicMessageFieldSet {
         &'a self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = (ExtensionDescriptor, &'a Value)> + 'a {
-        self.fields.iter().filter_map(move |(&number, value)| {
+        self.map().iter().filter_map(move |(&number, value)| {
             let value = match value {
                 ValueOrUnknown::Value(value) => value,
                 _ => return None,
@@ -250,60 +446,93 @@ impl DynamicMessageFieldSet {
         })
     }
 
-    pub(super) fn iter_unknown(&self) -> impl Iterator<Item = &'_ UnknownField> {
-        self.fields.values().flat_map(move |value| match value {
-            ValueOrUnknown::Taken | ValueOrUnknown::Value(_) => [].iter(),
-            ValueOrUnknown::Unknown(unknowns) => unknowns.iter(),
-        })
-    }
-
-    pub(crate) fn iter_fields_mut<'a>(
-        &'a mut self,
+    pub(super) fn iter_fields_and_extensions<'a>(
+        &'a self,
         message: &'a MessageDescriptor,
-    ) -> impl Iterator<Item = (FieldDescriptor, &'a mut Value)> + 'a {
-        self.fields.iter_mut().filter_map(move |(&number, value)| {
+    ) -> impl Iterator<Item = (FieldOrExtension, &'a Value)> + 'a {
+        self.map().iter().filter_map(move |(&number, value)| {
             let value = match value {
                 ValueOrUnknown::Value(value) => value,
                 _ => return None,
             };
-            let field = match message.get_field(number) {
-                Some(field) => field,
-                _ => return None,
-            };
-            if field.has(value) {
-                Some((field, value))
+            if let Some(field) = message.get_field(number) {
+                if field.has(value) {
+                    Some((FieldOrExtension::Field(field), value))
+                } else {
+                    None
+                }
+            } else if let Some(extension) = message.get_extension(number) {
+                if extension.has(value) {
+                    Some((FieldOrExtension::Extension(extension), value))
+                } else {
+                    None
+                }
             } else {
                 None
             }
         })
     }
 
+    pub(super) fn iter_unknown(&self) -> impl Iterator<Item = &'_ UnknownField> {
+        self.map().values().flat_map(move |value| match value {
+            ValueOrUnknown::Taken | ValueOrUnknown::Value(_) | ValueOrUnknown::Extra(_) => {
+                [].iter()
+            }
+            ValueOrUnknown::Unknown(unknowns) => unknowns.iter(),
+        })
+    }
+
+    pub(crate) fn iter_fields_mut<'a>(
+        &'a mut self,
+        message: &'a MessageDescriptor,
+    ) -> impl Iterator<Item = (FieldDescriptor, &'a mut Value)> + 'a {
+        self.map_mut()
+            .iter_mut()
+            .filter_map(move |(&number, value)| {
+                let value = match value {
+                    ValueOrUnknown::Value(value) => value,
+                    _ => return None,
+                };
+                let field = match message.get_field(number) {
+                    Some(field) => field,
+                    _ => return None,
+                };
+                if field.has(value) {
+                    Some((field, value))
+                } else {
+                    None
+                }
+            })
+    }
+
     pub(crate) fn iter_extensions_mut<'a>(
         &'a mut self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = (ExtensionDescriptor, &'a mut Value)> + 'a {
-        self.fields.iter_mut().filter_map(move |(&number, value)| {
-            let value = match value {
-                ValueOrUnknown::Value(value) => value,
-                _ => return None,
-            };
-            let field = match message.get_extension(number) {
-                Some(field) => field,
-                _ => return None,
-            };
-            if field.has(value) {
-                Some((field, value))
-            } else {
-                None
-            }
-        })
+        self.map_mut()
+            .iter_mut()
+            .filter_map(move |(&number, value)| {
+                let value = match value {
+                    ValueOrUnknown::Value(value) => value,
+                    _ => return None,
+                };
+                let field = match message.get_extension(number) {
+                    Some(field) => field,
+                    _ => return None,
+                };
+                if field.has(value) {
+                    Some((field, value))
+                } else {
+                    None
+                }
+            })
     }
 
     pub(crate) fn take_fields<'a>(
         &'a mut self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = (FieldDescriptor, Value)> + 'a {
-        self.fields
+        self.map_mut()
             .iter_mut()
             .filter_map(move |(&number, value_or_unknown)| {
                 let value = match value_or_unknown {
@@ -329,7 +558,7 @@ impl DynamicMessageFieldSet {
         &'a mut self,
         message: &'a MessageDescriptor,
     ) -> impl Iterator<Item = (ExtensionDescriptor, Value)> + 'a {
-        self.fields
+        self.map_mut()
             .iter_mut()
             .filter_map(move |(&number, value_or_unknown)| {
                 let value = match value_or_unknown {
@@ -352,7 +581,7 @@ impl DynamicMessageFieldSet {
     }
 
     pub(crate) fn take_unknown(&mut self) -> impl Iterator<Item = UnknownField> + '_ {
-        self.fields
+        self.map_mut()
             .values_mut()
             .flat_map(move |value_or_unknown| match value_or_unknown {
                 ValueOrUnknown::Unknown(_) => replace(value_or_unknown, ValueOrUnknown::Taken)
@@ -362,8 +591,162 @@ impl DynamicMessageFieldSet {
             })
     }
 
+    /// Gets the number of unknown fields with the given number.
+    pub(super) fn unknown_len(&self, number: u32) -> usize {
+        match self.map().get(&number) {
+            Some(ValueOrUnknown::Unknown(unknowns)) => unknowns.iter().len(),
+            _ => 0,
+        }
+    }
+
+    pub(super) fn unknown_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.map()
+            .iter()
+            .filter_map(|(&number, value)| match value {
+                ValueOrUnknown::Unknown(_) => Some(number),
+                _ => None,
+            })
+    }
+
+    /// Decodes the unknown fields with the same number as `desc` into a value of the field.
+    ///
+    /// If any of the unknown fields cannot be decoded as a value of the field, they are all left unchanged.
+    pub(super) fn reparse_unknown(&mut self, desc: &impl FieldDescriptorLike) {
+        let unknowns = match self.map().get(&desc.number()) {
+            Some(ValueOrUnknown::Unknown(unknowns)) => unknowns,
+            _ => return,
+        };
+
+        let mut value = desc.default_value();
+        for unknown in unknowns.iter() {
+            let mut bytes = Vec::with_capacity(unknown.encoded_len());
+            unknown.encode(&mut bytes);
+            let mut buf = bytes.as_slice();
+            let result = encoding::decode_key(&mut buf).and_then(|(_, wire_type)| {
+                value.merge_field(desc, wire_type, &mut buf, DecodeContext::default(), false)
+            });
+            if result.is_err() {
+                return;
+            }
+        }
+
+        self.set(desc, value);
+    }
+
     pub(super) fn clear_all(&mut self) {
-        self.fields.clear();
+        self.fields
+            .retain(|&number, _| number == EXTRA_STATE_NUMBER);
+        if let Some(extra) = self.extra_mut() {
+            extra.pending = None;
+            if let Some(wire_order) = &mut extra.wire_order {
+                wire_order.clear();
+            }
+        }
+    }
+}
+
+impl PartialEq for DynamicMessageFieldSet {
+    fn eq(&self, other: &Self) -> bool {
+        let is_field = |(&number, _): &(&u32, &ValueOrUnknown)| number != EXTRA_STATE_NUMBER;
+        self.map()
+            .iter()
+            .filter(is_field)
+            .eq(other.map().iter().filter(is_field))
+    }
+}
+
+impl WireElements {
+    /// Extends these elements with those decoded from the following record with the same number, if they can be
+    /// written back as the same records. Otherwise, returns `next` unchanged.
+    fn coalesce(&mut self, next: WireElements) -> Result<(), WireElements> {
+        match (self, next) {
+            (WireElements::Single, WireElements::Single) => Ok(()),
+            (WireElements::Unpacked(range), WireElements::Unpacked(next))
+                if range.end == next.start =>
+            {
+                range.end = next.end;
+                Ok(())
+            }
+            (WireElements::MapKeys(keys), WireElements::MapKeys(next)) => {
+                keys.extend(next);
+                Ok(())
+            }
+            (_, next) => Err(next),
+        }
+    }
+}
+
+/// Decodes the fields of a lazily decoded message.
+///
+/// If the message is invalid, each of its records is instead stored as an unknown field, and then any which are
+/// valid values of a known field are decoded.
+fn decode_pending(pending: &PendingFields) -> DecodedFields {
+    let mut message = DynamicMessage::new(pending.desc.clone());
+    if pending
+        .bytes
+        .iter()
+        .all(|bytes| message.merge_lazy(&mut bytes.clone()).is_ok())
+    {
+        return DecodedFields {
+            fields: message.fields.fields,
+            valid: true,
+        };
+    }
+
+    let mut message = DynamicMessage::new(pending.desc.clone());
+    for bytes in &pending.bytes {
+        let mut buf = bytes.clone();
+        while buf.has_remaining() {
+            let field = encoding::decode_key(&mut buf).and_then(|(number, wire_type)| {
+                UnknownField::decode_value(number, wire_type, &mut buf, DecodeContext::default())
+            });
+            match field {
+                Ok(field) => message.fields.add_unknown(field.number(), field),
+                Err(_) => break,
+            }
+        }
+    }
+    for field_desc in pending.desc.fields() {
+        message.fields.reparse_unknown(&field_desc);
+    }
+    for extension_desc in pending.desc.extensions() {
+        message.fields.reparse_unknown(&extension_desc);
+    }
+
+    DecodedFields {
+        fields: message.fields.fields,
+        valid: false,
+    }
+}
+
+fn value_and_descriptor<'a>(
+    message: &MessageDescriptor,
+    number: u32,
+    value: &'a ValueOrUnknown,
+) -> Option<ValueAndDescriptor<'a>> {
+    match value {
+        ValueOrUnknown::Value(value) => {
+            if let Some(field) = message.get_field(number) {
+                if field.has(value) {
+                    Some(ValueAndDescriptor::Field(Cow::Borrowed(value), field))
+                } else {
+                    None
+                }
+            } else if let Some(extension) = message.get_extension(number) {
+                if extension.has(value) {
+                    Some(ValueAndDescriptor::Extension(
+                        Cow::Borrowed(value),
+                        extension,
+                    ))
+                } else {
+                    None
+                }
+            } else {
+                panic!("no field found with number {}", number)
+            }
+        }
+        ValueOrUnknown::Unknown(unknown) => Some(ValueAndDescriptor::Unknown(unknown)),
+        ValueOrUnknown::Taken | ValueOrUnknown::Extra(_) => None,
     }
 }
 
@@ -371,25 +754,99 @@ impl ValueOrUnknown {
     fn unwrap_value_mut(&mut self) -> &mut Value {
         match self {
             ValueOrUnknown::Value(value) => value,
-            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Taken => unreachable!(),
+            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Taken | ValueOrUnknown::Extra(_) => {
+                unreachable!()
+            }
         }
     }
 
     fn unwrap_value(self) -> Value {
         match self {
             ValueOrUnknown::Value(value) => value,
-            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Taken => unreachable!(),
+            ValueOrUnknown::Unknown(_) | ValueOrUnknown::Taken | ValueOrUnknown::Extra(_) => {
+                unreachable!()
+            }
         }
     }
 
     fn unwrap_unknown(self) -> UnknownFieldSet {
         match self {
             ValueOrUnknown::Unknown(unknowns) => unknowns,
-            ValueOrUnknown::Value(_) | ValueOrUnknown::Taken => unreachable!(),
+            ValueOrUnknown::Value(_) | ValueOrUnknown::Taken | ValueOrUnknown::Extra(_) => {
+                unreachable!()
+            }
         }
     }
 }
 
+/// Wraps a field descriptor to override whether it uses the packed encoding.
+#[derive(Debug)]
+pub(super) struct PackedOverride<'a, T> {
+    desc: &'a T,
+    packed: bool,
+}
+
+impl<'a, T> PackedOverride<'a, T> {
+    pub(super) fn new(desc: &'a T, packed: bool) -> Self {
+        PackedOverride { desc, packed }
+    }
+}
+
+impl<'a, T: FieldDescriptorLike> FieldDescriptorLike for PackedOverride<'a, T> {
+    #[cfg(feature = "text-format")]
+    fn text_name(&self) -> &str {
+        self.desc.text_name()
+    }
+
+    fn number(&self) -> u32 {
+        self.desc.number()
+    }
+
+    fn default_value(&self) -> Value {
+        self.desc.default_value()
+    }
+
+    fn is_default_value(&self, value: &Value) -> bool {
+        self.desc.is_default_value(value)
+    }
+
+    fn is_valid(&self, value: &Value) -> bool {
+        self.desc.is_valid(value)
+    }
+
+    fn containing_oneof(&self) -> Option<OneofDescriptor> {
+        self.desc.containing_oneof()
+    }
+
+    fn supports_presence(&self) -> bool {
+        self.desc.supports_presence()
+    }
+
+    fn kind(&self) -> Kind {
+        self.desc.kind()
+    }
+
+    fn is_group(&self) -> bool {
+        self.desc.is_group()
+    }
+
+    fn is_list(&self) -> bool {
+        self.desc.is_list()
+    }
+
+    fn is_map(&self) -> bool {
+        self.desc.is_map()
+    }
+
+    fn is_packed(&self) -> bool {
+        self.packed
+    }
+
+    fn is_packable(&self) -> bool {
+        self.desc.is_packable()
+    }
+}
+
 impl FieldDescriptorLike for FieldDescriptor {
     #[cfg(feature = "text-format")]
     fn text_name(&self) -> &str {
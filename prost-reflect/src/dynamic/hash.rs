@@ -0,0 +1,86 @@
+use std::hash::{Hash, Hasher};
+
+use crate::{dynamic::sorted_map_entries, DynamicMessage, Value};
+
+impl DynamicMessage {
+    /// Feeds the contents of this message into the given [`Hasher`].
+    ///
+    /// The result does not depend on the order in which fields were set or decoded, or on the iteration order of map
+    /// fields. Fields and extension fields are hashed in ascending order of field number, followed by unknown fields,
+    /// and map entries are hashed in ascending order of their keys, matching the deterministic encoding of the
+    /// message.
+    ///
+    /// The hash is a function of the bit patterns of the message's values, not of [`PartialEq`]: two messages
+    /// whose values have identical bit patterns always produce the same hash. In particular, floating-point values
+    /// are hashed by [`f64::to_bits`], so `0.0` and `-0.0` hash differently even though they compare equal, while
+    /// `NaN` values with the same bits hash equally even though they never compare equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::hash_map::DefaultHasher;
+    /// # use std::hash::Hasher;
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let hash = |message: &DynamicMessage| {
+    ///     let mut hasher = DefaultHasher::new();
+    ///     message.stable_hash(&mut hasher);
+    ///     hasher.finish()
+    /// };
+    ///
+    /// let mut a = DynamicMessage::new(message_descriptor.clone());
+    /// a.set_field_by_name("foo", Value::I32(150));
+    /// let b = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// assert_eq!(hash(&a), hash(&b));
+    /// ```
+    pub fn stable_hash<H: Hasher>(&self, hasher: &mut H) {
+        for (field, value) in self.iter_fields_sorted() {
+            field.number().hash(hasher);
+            hash_value(value, hasher);
+        }
+
+        for unknown in self.unknown_fields() {
+            unknown.number().hash(hasher);
+            let mut bytes = Vec::new();
+            unknown.encode(&mut bytes);
+            bytes.hash(hasher);
+        }
+
+        // Field numbers are never zero, so this marks the end of the message unambiguously.
+        0u32.hash(hasher);
+    }
+}
+
+fn hash_value<H: Hasher>(value: &Value, hasher: &mut H) {
+    match value {
+        Value::Bool(value) => (0u8, value).hash(hasher),
+        Value::I32(value) => (1u8, value).hash(hasher),
+        Value::I64(value) => (2u8, value).hash(hasher),
+        Value::U32(value) => (3u8, value).hash(hasher),
+        Value::U64(value) => (4u8, value).hash(hasher),
+        Value::F32(value) => (5u8, value.to_bits()).hash(hasher),
+        Value::F64(value) => (6u8, value.to_bits()).hash(hasher),
+        Value::String(value) => (7u8, value).hash(hasher),
+        Value::Bytes(value) => (8u8, value.as_ref()).hash(hasher),
+        Value::EnumNumber(value) => (9u8, value).hash(hasher),
+        Value::Message(message) => {
+            10u8.hash(hasher);
+            message.stable_hash(hasher);
+        }
+        Value::List(values) => {
+            (11u8, values.len()).hash(hasher);
+            for value in values {
+                hash_value(value, hasher);
+            }
+        }
+        Value::Map(values) => {
+            (12u8, values.len()).hash(hasher);
+            for (key, value) in sorted_map_entries(values) {
+                key.hash(hasher);
+                hash_value(value, hasher);
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use prost::{
     bytes::{Buf, BufMut},
     encoding::{DecodeContext, WireType},
@@ -10,7 +12,7 @@ use crate::{
 };
 
 use super::{
-    fields::{FieldDescriptorLike, ValueAndDescriptor},
+    fields::{FieldDescriptorLike, PackedOverride, ValueAndDescriptor, WireElements, WireRecord},
     unknown::UnknownField,
 };
 
@@ -19,6 +21,17 @@ impl Message for DynamicMessage {
     where
         Self: Sized,
     {
+        if let Some(pending) = self.fields.pending() {
+            for bytes in pending {
+                buf.put_slice(bytes);
+            }
+            return;
+        }
+
+        if let Some(wire_order) = self.fields.wire_order() {
+            return self.write_in_wire_order(wire_order, &mut EncodeSink(buf));
+        }
+
         for field in self.fields.iter(&self.desc) {
             match field {
                 ValueAndDescriptor::Field(value, field_desc) => {
@@ -42,15 +55,20 @@ impl Message for DynamicMessage {
     where
         Self: Sized,
     {
+        if self.fields.preserves_wire_order() {
+            return self.merge_field_preserving_wire_order(number, wire_type, buf, ctx);
+        }
+
         if let Some(field_desc) = self.desc.get_field(number) {
             self.get_field_mut(&field_desc)
-                .merge_field(&field_desc, wire_type, buf, ctx)
+                .merge_field(&field_desc, wire_type, buf, ctx, false)
         } else if let Some(extension_desc) = self.desc.get_extension(number) {
             self.get_extension_mut(&extension_desc).merge_field(
                 &extension_desc,
                 wire_type,
                 buf,
                 ctx,
+                false,
             )
         } else {
             let field = UnknownField::decode_value(number, wire_type, buf, ctx)?;
@@ -60,6 +78,16 @@ impl Message for DynamicMessage {
     }
 
     fn encoded_len(&self) -> usize {
+        if let Some(pending) = self.fields.pending() {
+            return pending.iter().map(|bytes| bytes.len()).sum();
+        }
+
+        if let Some(wire_order) = self.fields.wire_order() {
+            let mut sink = EncodedLenSink(0);
+            self.write_in_wire_order(wire_order, &mut sink);
+            return sink.0;
+        }
+
         let mut len = 0;
         for field in self.fields.iter(&self.desc) {
             match field {
@@ -80,6 +108,273 @@ impl Message for DynamicMessage {
     }
 }
 
+impl DynamicMessage {
+    /// Merges a field, recording which part of its value was decoded from this record.
+    fn merge_field_preserving_wire_order(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        let elements = if let Some(field_desc) = self.desc.get_field(number) {
+            merge_value_record(
+                self.get_field_mut(&field_desc),
+                &field_desc,
+                wire_type,
+                buf,
+                ctx,
+            )?
+        } else if let Some(extension_desc) = self.desc.get_extension(number) {
+            merge_value_record(
+                self.get_extension_mut(&extension_desc),
+                &extension_desc,
+                wire_type,
+                buf,
+                ctx,
+            )?
+        } else {
+            let field = UnknownField::decode_value(number, wire_type, buf, ctx)?;
+            let index = self.fields.unknown_len(number);
+            self.fields.add_unknown(number, field);
+            WireElements::Unpacked(index..index + 1)
+        };
+
+        self.fields.record_wire_order(number, elements);
+        Ok(())
+    }
+
+    /// Writes each record in the order it was decoded, followed by any remaining fields in field number order.
+    pub(super) fn write_in_wire_order(&self, wire_order: &[WireRecord], sink: &mut impl WireSink) {
+        let mut written: HashMap<u32, Written> = HashMap::new();
+        for record in wire_order {
+            if let Some(field) = self.fields.get_by_number(&self.desc, record.number) {
+                let written = written.entry(record.number).or_default();
+                write_record(&field, Some(&record.elements), written, sink);
+            }
+        }
+
+        for number in self.fields.numbers() {
+            if let Some(field) = self.fields.get_by_number(&self.desc, number) {
+                let written = written.entry(number).or_default();
+                write_record(&field, None, written, sink);
+            }
+        }
+    }
+}
+
+/// Merges a record into the value of a field, returning the part of the value which was decoded from it.
+fn merge_value_record(
+    value: &mut Value,
+    field_desc: &impl FieldDescriptorLike,
+    wire_type: WireType,
+    buf: &mut impl Buf,
+    ctx: DecodeContext,
+) -> Result<WireElements, DecodeError> {
+    if let Value::Map(values) = value {
+        let mut entry = Value::Map(HashMap::with_capacity(1));
+        entry.merge_field(field_desc, wire_type, buf, ctx, true)?;
+        let keys = match entry {
+            Value::Map(entry) => entry
+                .into_iter()
+                .map(|(key, value)| {
+                    values.insert(key.clone(), value);
+                    key
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+        return Ok(WireElements::MapKeys(keys));
+    }
+
+    let start = value.as_list().map(|values| values.len());
+    value.merge_field(field_desc, wire_type, buf, ctx, true)?;
+    Ok(match (start, value.as_list()) {
+        (Some(start), Some(values))
+            if wire_type == WireType::LengthDelimited && field_desc.is_packable() =>
+        {
+            WireElements::Packed(start..values.len())
+        }
+        (Some(start), Some(values)) => WireElements::Unpacked(start..values.len()),
+        _ => WireElements::Single,
+    })
+}
+
+/// The parts of a field's value which have already been written in wire order.
+#[derive(Default)]
+struct Written<'a> {
+    /// Whether the whole value has been written.
+    all: bool,
+    /// The number of leading elements of a list, or of a set of unknown fields, which have been written.
+    elements: usize,
+    /// The keys of the map entries which have been written.
+    keys: HashSet<&'a MapKey>,
+}
+
+/// Writes the part of a field described by `elements` which has not been written yet, or all of the remaining value
+/// if `elements` is `None`.
+fn write_record<'a>(
+    field: &ValueAndDescriptor<'_>,
+    elements: Option<&'a WireElements>,
+    written: &mut Written<'a>,
+    sink: &mut impl WireSink,
+) {
+    match field {
+        ValueAndDescriptor::Field(value, field_desc) => {
+            write_value_record(value, field_desc, elements, written, sink)
+        }
+        ValueAndDescriptor::Extension(value, extension_desc) => {
+            write_value_record(value, extension_desc, elements, written, sink)
+        }
+        ValueAndDescriptor::Unknown(unknowns) => {
+            let end = match elements {
+                Some(WireElements::Unpacked(range)) => range.end,
+                _ => usize::MAX,
+            };
+            for unknown in next_elements(unknowns.iter().as_slice(), end, &mut written.elements) {
+                sink.unknown(unknown);
+            }
+        }
+    }
+}
+
+fn write_value_record<'a>(
+    value: &Value,
+    field_desc: &impl FieldDescriptorLike,
+    elements: Option<&'a WireElements>,
+    written: &mut Written<'a>,
+    sink: &mut impl WireSink,
+) {
+    if written.all {
+        return;
+    }
+
+    match (value, field_desc.kind()) {
+        (Value::List(values), _) => {
+            let (end, packed) = match elements {
+                Some(WireElements::Unpacked(range)) => (range.end, false),
+                Some(WireElements::Packed(range)) => (range.end, true),
+                _ => (usize::MAX, field_desc.is_packed()),
+            };
+            let next = next_elements(values, end, &mut written.elements);
+            if !next.is_empty() {
+                if packed && next.len() == values.len() {
+                    sink.field(value, &PackedOverride::new(field_desc, true));
+                } else if packed {
+                    sink.field(
+                        &Value::List(next.to_vec()),
+                        &PackedOverride::new(field_desc, true),
+                    );
+                } else {
+                    for value in next {
+                        sink.field(value, field_desc);
+                    }
+                }
+            }
+        }
+        (Value::Map(values), Kind::Message(map_entry)) => {
+            let key_desc = map_entry.map_entry_key_field();
+            let value_desc = map_entry.map_entry_value_field();
+            let number = field_desc.number();
+            match elements {
+                Some(WireElements::MapKeys(keys)) => {
+                    for key in keys {
+                        if let Some(value) = values.get(key) {
+                            if written.keys.insert(key) {
+                                sink.map_entry(number, key, value, &key_desc, &value_desc);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    for (key, value) in values {
+                        if !written.keys.contains(key) {
+                            sink.map_entry(number, key, value, &key_desc, &value_desc);
+                        }
+                    }
+                    written.all = true;
+                }
+            }
+        }
+        (value, _) => {
+            sink.field(value, field_desc);
+            written.all = true;
+        }
+    }
+}
+
+/// Returns the elements before `end` which have not been written yet, and marks them as written.
+fn next_elements<'a, T>(elements: &'a [T], end: usize, written: &mut usize) -> &'a [T] {
+    let end = end.min(elements.len());
+    let start = (*written).min(end);
+    *written = (*written).max(end);
+    &elements[start..end]
+}
+
+/// Receives the records of a message as they are written by [`DynamicMessage::write_in_wire_order`].
+pub(super) trait WireSink {
+    fn field(&mut self, value: &Value, field_desc: &impl FieldDescriptorLike);
+
+    fn map_entry(
+        &mut self,
+        number: u32,
+        key: &MapKey,
+        value: &Value,
+        key_desc: &FieldDescriptor,
+        value_desc: &FieldDescriptor,
+    );
+
+    fn unknown(&mut self, unknown: &UnknownField);
+}
+
+/// Encodes records to a buffer.
+struct EncodeSink<'a, B>(&'a mut B);
+
+impl<B: BufMut> WireSink for EncodeSink<'_, B> {
+    fn field(&mut self, value: &Value, field_desc: &impl FieldDescriptorLike) {
+        value.encode_field(field_desc, self.0)
+    }
+
+    fn map_entry(
+        &mut self,
+        number: u32,
+        key: &MapKey,
+        value: &Value,
+        key_desc: &FieldDescriptor,
+        value_desc: &FieldDescriptor,
+    ) {
+        encode_map_entry(number, key, value, key_desc, value_desc, self.0)
+    }
+
+    fn unknown(&mut self, unknown: &UnknownField) {
+        unknown.encode(self.0)
+    }
+}
+
+/// Sums the encoded lengths of records.
+struct EncodedLenSink(usize);
+
+impl WireSink for EncodedLenSink {
+    fn field(&mut self, value: &Value, field_desc: &impl FieldDescriptorLike) {
+        self.0 += value.encoded_len(field_desc);
+    }
+
+    fn map_entry(
+        &mut self,
+        number: u32,
+        key: &MapKey,
+        value: &Value,
+        key_desc: &FieldDescriptor,
+        value_desc: &FieldDescriptor,
+    ) {
+        self.0 += map_entry_encoded_len(number, key, value, key_desc, value_desc);
+    }
+
+    fn unknown(&mut self, unknown: &UnknownField) {
+        self.0 += unknown.encoded_len();
+    }
+}
+
 impl Value {
     pub(super) fn encode_field<B>(&self, field_desc: &impl FieldDescriptorLike, buf: &mut B)
     where
@@ -254,13 +549,7 @@ impl Value {
                 let value_desc = map_entry.get_field(MAP_ENTRY_VALUE_NUMBER).unwrap();
 
                 for (key, value) in values {
-                    let len = key.encoded_len(&key_desc) + value.encoded_len(&value_desc);
-
-                    prost::encoding::encode_key(number, WireType::LengthDelimited, buf);
-                    prost::encoding::encode_varint(len as u64, buf);
-
-                    key.encode_field(&key_desc, buf);
-                    value.encode_field(&value_desc, buf);
+                    encode_map_entry(number, key, value, &key_desc, &value_desc, buf);
                 }
             }
             (value, ty) => panic!(
@@ -276,6 +565,7 @@ impl Value {
         wire_type: WireType,
         buf: &mut B,
         ctx: DecodeContext,
+        preserve_wire_order: bool,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -330,6 +620,9 @@ impl Value {
                 prost::encoding::int32::merge(wire_type, value, buf, ctx)
             }
             (Value::Message(message), Kind::Message(_)) => {
+                if preserve_wire_order {
+                    message.fields.preserve_wire_order();
+                }
                 if field_desc.is_group() {
                     prost::encoding::group::merge(field_desc.number(), wire_type, message, buf, ctx)
                 } else {
@@ -340,13 +633,19 @@ impl Value {
                 if wire_type == WireType::LengthDelimited && field_desc.is_packable() {
                     prost::encoding::merge_loop(values, buf, ctx, |values, buf, ctx| {
                         let mut value = Value::default_value(&field_kind);
-                        value.merge_field(field_desc, field_kind.wire_type(), buf, ctx)?;
+                        value.merge_field(
+                            field_desc,
+                            field_kind.wire_type(),
+                            buf,
+                            ctx,
+                            preserve_wire_order,
+                        )?;
                         values.push(value);
                         Ok(())
                     })
                 } else {
                     let mut value = Value::default_value(&field_kind);
-                    value.merge_field(field_desc, wire_type, buf, ctx)?;
+                    value.merge_field(field_desc, wire_type, buf, ctx, preserve_wire_order)?;
                     values.push(value);
                     Ok(())
                 }
@@ -365,9 +664,13 @@ impl Value {
                         let (number, wire_type) = prost::encoding::decode_key(buf)?;
                         match number {
                             MAP_ENTRY_KEY_NUMBER => key.merge_field(&key_desc, wire_type, buf, ctx),
-                            MAP_ENTRY_VALUE_NUMBER => {
-                                value.merge_field(&value_desc, wire_type, buf, ctx)
-                            }
+                            MAP_ENTRY_VALUE_NUMBER => value.merge_field(
+                                &value_desc,
+                                wire_type,
+                                buf,
+                                ctx,
+                                preserve_wire_order,
+                            ),
                             _ => prost::encoding::skip_field(wire_type, number, buf, ctx),
                         }
                     },
@@ -525,13 +828,10 @@ impl Value {
                 let key_desc = map_entry.map_entry_key_field();
                 let value_desc = map_entry.map_entry_value_field();
 
-                let key_len = prost::encoding::key_len(number);
                 values
                     .iter()
                     .map(|(key, value)| {
-                        let len = key.encoded_len(&key_desc) + value.encoded_len(&value_desc);
-
-                        key_len + prost::encoding::encoded_len_varint(len as u64) + len
+                        map_entry_encoded_len(number, key, value, &key_desc, &value_desc)
                     })
                     .sum::<usize>()
             }
@@ -544,7 +844,7 @@ impl Value {
 }
 
 impl MapKey {
-    fn encode_field<B>(&self, field_desc: &FieldDescriptor, buf: &mut B)
+    pub(super) fn encode_field<B>(&self, field_desc: &FieldDescriptor, buf: &mut B)
     where
         B: BufMut,
     {
@@ -645,7 +945,7 @@ impl MapKey {
         }
     }
 
-    fn encoded_len(&self, field_desc: &FieldDescriptor) -> usize {
+    pub(super) fn encoded_len(&self, field_desc: &FieldDescriptor) -> usize {
         if !field_desc.supports_presence() && self.is_default(&field_desc.kind()) {
             return 0;
         }
@@ -690,6 +990,36 @@ impl MapKey {
     }
 }
 
+fn encode_map_entry<B>(
+    number: u32,
+    key: &MapKey,
+    value: &Value,
+    key_desc: &FieldDescriptor,
+    value_desc: &FieldDescriptor,
+    buf: &mut B,
+) where
+    B: BufMut,
+{
+    let len = key.encoded_len(key_desc) + value.encoded_len(value_desc);
+
+    prost::encoding::encode_key(number, WireType::LengthDelimited, buf);
+    prost::encoding::encode_varint(len as u64, buf);
+
+    key.encode_field(key_desc, buf);
+    value.encode_field(value_desc, buf);
+}
+
+fn map_entry_encoded_len(
+    number: u32,
+    key: &MapKey,
+    value: &Value,
+    key_desc: &FieldDescriptor,
+    value_desc: &FieldDescriptor,
+) -> usize {
+    let len = key.encoded_len(key_desc) + value.encoded_len(value_desc);
+    prost::encoding::key_len(number) + prost::encoding::encoded_len_varint(len as u64) + len
+}
+
 fn encode_packed_list<T, I, B, E, L>(number: u32, iter: I, buf: &mut B, encode: E, encoded_len: L)
 where
     I: IntoIterator<Item = T> + Clone,
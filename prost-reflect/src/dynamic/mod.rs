@@ -6,30 +6,50 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
 pub mod text_format;
 
+mod approx;
+mod decode;
+mod encode;
 mod fields;
+mod hash;
 mod message;
 #[cfg(feature = "serde")]
 mod serde;
 #[cfg(not(feature = "text-format"))]
 mod text_format;
 mod unknown;
+mod view;
+mod visit;
+mod wkt;
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fmt::{self, Write},
+};
 
-use std::{borrow::Cow, collections::HashMap, error::Error, fmt};
-
+pub use self::decode::DecodeOptions;
+pub use self::encode::EncodeOptions;
 #[cfg(feature = "serde")]
-pub use self::serde::{DeserializeOptions, SerializeOptions};
+pub use self::serde::{BytesEncoding, DeserializeOptions, FieldAliasError, SerializeOptions};
 pub use self::unknown::UnknownField;
+pub use self::view::DynamicMessageView;
+pub use self::visit::{Visitor, VisitorMut};
 
 pub(crate) use self::fields::FieldDescriptorLike;
+pub(crate) use self::text_format::EscapeStyle;
 
 use prost::{
     bytes::{Buf, Bytes},
+    encoding::WireType,
     DecodeError, Message,
 };
 
-use self::fields::DynamicMessageFieldSet;
+use self::fields::{DynamicMessageFieldSet, ValueAndDescriptor};
 use crate::{
-    descriptor::Kind, ExtensionDescriptor, FieldDescriptor, MessageDescriptor, ReflectMessage,
+    descriptor::{Kind, GOOGLE_APIS_DOMAIN, GOOGLE_PROD_DOMAIN},
+    Cardinality, DescriptorPool, EnumDescriptor, ExtensionDescriptor, FieldDescriptor,
+    MessageDescriptor, OneofDescriptor, ReflectMessage,
 };
 
 /// [`DynamicMessage`] provides encoding, decoding and reflection of a protobuf message.
@@ -98,6 +118,11 @@ pub enum MapKey {
 pub enum SetFieldError {
     /// The field was not found.
     NotFound,
+    /// No field with the given name was found (see [`DynamicMessage::set_fields_from()`]).
+    FieldNameNotFound {
+        /// The name of the field which could not be found.
+        name: String,
+    },
     /// The value type was not compatible with the field type (see [`Value::is_valid_for_field`]).
     InvalidType {
         /// The descriptor for the field which could not be set.
@@ -105,6 +130,32 @@ pub enum SetFieldError {
         /// The invalid value.
         value: Value,
     },
+    /// The value contained an enum number which is not defined by the field's enum type, and the enum is closed
+    /// (see [`DynamicMessage::set_field_checked()`]).
+    InvalidEnumNumber {
+        /// The descriptor for the field which could not be set.
+        field: FieldDescriptor,
+        /// The undefined enum number.
+        number: i32,
+    },
+}
+
+/// The descriptor for either a field or an extension field of a message.
+///
+/// Yielded by [`DynamicMessage::iter_fields_sorted()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldOrExtension {
+    /// A field defined in the message type.
+    Field(FieldDescriptor),
+    /// An extension field.
+    Extension(ExtensionDescriptor),
+}
+
+/// Error type returned when converting a [`Value`] into a Rust type using [`TryFrom`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryFromValueError {
+    expected: &'static str,
+    value: Value,
 }
 
 impl DynamicMessage {
@@ -119,6 +170,12 @@ impl DynamicMessage {
     /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer and merges it into a
     /// new instance of [`DynamicMessage`].
     ///
+    /// If `buf` is a [`Bytes`], the values of `bytes` fields are stored as slices of it rather than being copied,
+    /// which avoids a separate allocation for each field when decoding messages containing large blobs. The same
+    /// applies to [`decode_with_options()`][Self::decode_with_options] and
+    /// [`decode_lenient()`][Self::decode_lenient]. Note that `string` fields are always copied, because they are
+    /// stored as a [`String`].
+    ///
     /// # Examples
     ///
     /// ```
@@ -139,6 +196,114 @@ impl DynamicMessage {
         Ok(message)
     }
 
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from the buffer, recording the
+    /// order in which fields appear on the wire.
+    ///
+    /// When the returned message, or any message nested within it, is encoded, fields are written in the order they
+    /// were decoded, including unknown fields which were interleaved with known fields. This allows forwarding a
+    /// message without reordering its fields. Each record is written back with the encoding it was decoded with, so
+    /// map entries keep their order and packed records of unpacked fields, or unpacked records of packed fields, are
+    /// unchanged. Any fields added after decoding, including fields whose value is replaced with
+    /// [`set_field`](Self::set_field) or cleared, are written after the decoded fields, in field number order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let bytes = b"\x1a\x02\x10\x42\x08\x96\x01".as_ref();
+    /// let dynamic_message = DynamicMessage::decode_preserving_wire_order(message_descriptor, bytes).unwrap();
+    /// assert_eq!(dynamic_message.encode_to_vec(), bytes);
+    /// ```
+    pub fn decode_preserving_wire_order<B>(
+        desc: MessageDescriptor,
+        buf: B,
+    ) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut message = DynamicMessage::new(desc);
+        message.fields.preserve_wire_order();
+        message.merge(buf)?;
+        Ok(message)
+    }
+
+    /// Returns the length of this message when encoded in the protobuf binary format, without encoding it.
+    ///
+    /// This includes the length of any unknown fields. It is equivalent to [`Message::encoded_len`], but does not
+    /// require the trait to be imported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("foo", Value::I32(150));
+    /// assert_eq!(dynamic_message.encoded_len(), 3);
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        Message::encoded_len(self)
+    }
+
+    /// Returns the difference in encoded length between this message and `other`, in bytes.
+    ///
+    /// The result is positive if this message is larger than `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let old = DynamicMessage::new(message_descriptor.clone());
+    /// let mut new = DynamicMessage::new(message_descriptor);
+    /// new.set_field_by_name("foo", Value::I32(150));
+    /// assert_eq!(new.size_delta(&old), 3);
+    /// assert_eq!(old.size_delta(&new), -3);
+    /// ```
+    pub fn size_delta(&self, other: &DynamicMessage) -> i64 {
+        self.encoded_len() as i64 - other.encoded_len() as i64
+    }
+
+    /// Returns the difference in encoded length between this message and `other` for each field number, in bytes.
+    ///
+    /// Fields, extension fields and unknown fields are all included, keyed by their field number. Field numbers whose
+    /// encoded length is the same in both messages are omitted. The values of the returned map sum to
+    /// [`size_delta()`][Self::size_delta].
+    ///
+    /// The two messages do not need to have the same type, although the result is most meaningful when they do.
+    pub fn size_delta_by_field(&self, other: &DynamicMessage) -> HashMap<u32, i64> {
+        let mut deltas = HashMap::new();
+        self.add_encoded_len_by_field(&mut deltas, 1);
+        other.add_encoded_len_by_field(&mut deltas, -1);
+        deltas.retain(|_, delta| *delta != 0);
+        deltas
+    }
+
+    fn add_encoded_len_by_field(&self, lens: &mut HashMap<u32, i64>, sign: i64) {
+        let mut add =
+            |number: u32, len: usize| *lens.entry(number).or_insert(0) += sign * len as i64;
+        for field in self.fields.iter(&self.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    add(field_desc.number(), value.encoded_len(&field_desc))
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    add(extension_desc.number(), value.encoded_len(&extension_desc))
+                }
+                ValueAndDescriptor::Unknown(unknowns) => {
+                    for unknown in unknowns.iter() {
+                        add(unknown.number(), unknown.encoded_len());
+                    }
+                }
+            }
+        }
+    }
+
     /// Returns `true` if this message has the given field set.
     ///
     /// If the field type supports distinguishing whether a value has been set (see [`supports_presence`][FieldDescriptor::supports_presence]),
@@ -192,7 +357,49 @@ impl DynamicMessage {
         self.fields.has(field_desc)
     }
 
+    /// Returns the field which is currently set in the given oneof, or `None` if no field of the oneof is set.
+    ///
+    /// A field is considered set if [`has_field`](Self::has_field) returns `true` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let oneof = message_descriptor.oneofs().next().unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// assert_eq!(dynamic_message.oneof_case(&oneof), None);
+    ///
+    /// dynamic_message.set_field_by_name("bar", Value::I32(5));
+    /// assert_eq!(dynamic_message.oneof_case(&oneof).unwrap().name(), "bar");
+    /// ```
+    pub fn oneof_case(&self, oneof_desc: &OneofDescriptor) -> Option<FieldDescriptor> {
+        oneof_desc
+            .fields()
+            .find(|field_desc| self.fields.has(field_desc))
+    }
+
     /// Gets the value of the given field, or the default value if it is unset.
+    ///
+    /// If the field is set, a reference to its value is returned as [`Cow::Borrowed`], without cloning it. Only
+    /// when the field is unset is a new default value created and returned as [`Cow::Owned`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::borrow::Cow;
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x96\x01".as_ref()).unwrap();
+    /// let foo = message_descriptor.get_field_by_name("foo").unwrap();
+    /// let nested = message_descriptor.get_field_by_name("nested").unwrap();
+    /// assert!(matches!(dynamic_message.get_field(&foo), Cow::Borrowed(&Value::I32(150))));
+    /// assert!(matches!(dynamic_message.get_field(&nested), Cow::Owned(Value::Message(_))));
+    /// ```
     pub fn get_field(&self, field_desc: &FieldDescriptor) -> Cow<'_, Value> {
         self.fields.get(field_desc)
     }
@@ -203,6 +410,39 @@ impl DynamicMessage {
         self.fields.get_mut(field_desc)
     }
 
+    /// Gets a mutable reference to the message stored in the given singular message field. If the field is not set,
+    /// it is first set to an empty message of the field's type.
+    ///
+    /// Returns `None` if the field is not a singular message field, for example if it is a repeated or scalar field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor.clone());
+    /// let nested = message_descriptor.get_field_by_name("nested").unwrap();
+    /// dynamic_message
+    ///     .get_or_insert_message(&nested)
+    ///     .unwrap()
+    ///     .set_field_by_name("bar", Value::I32(66));
+    /// assert_eq!(dynamic_message.encode_to_vec(), b"\x1a\x02\x10\x42");
+    ///
+    /// let foo = message_descriptor.get_field_by_name("foo").unwrap();
+    /// assert!(dynamic_message.get_or_insert_message(&foo).is_none());
+    /// ```
+    pub fn get_or_insert_message(
+        &mut self,
+        field_desc: &FieldDescriptor,
+    ) -> Option<&mut DynamicMessage> {
+        if field_desc.is_list() || field_desc.is_map() || field_desc.kind().as_message().is_none() {
+            return None;
+        }
+        self.fields.get_mut(field_desc).as_message_mut()
+    }
+
     /// Sets the value of the given field.
     ///
     /// # Panics
@@ -248,6 +488,41 @@ impl DynamicMessage {
         }
     }
 
+    /// Tries to set the value of the given field, additionally checking that any enum numbers in the value are valid.
+    ///
+    /// As well as the checks performed by [`try_set_field()`](DynamicMessage::try_set_field), this returns
+    /// [`SetFieldError::InvalidEnumNumber`] if the field has a [closed](EnumDescriptor::is_closed) enum type and the
+    /// value, or any element of a repeated or map field, is a number not defined by the enum. Open enums accept any
+    /// number, so are not checked. String values are always valid UTF-8, since [`Value::String`] holds a Rust
+    /// [`String`].
+    ///
+    /// Nested messages are not checked, since their fields must have been set separately.
+    pub fn set_field_checked(
+        &mut self,
+        field_desc: &FieldDescriptor,
+        value: Value,
+    ) -> Result<(), SetFieldError> {
+        if let Kind::Enum(enum_desc) = value_kind(field_desc) {
+            if enum_desc.is_closed() && value.is_valid_for_field(field_desc) {
+                let numbers: Box<dyn Iterator<Item = &Value>> = match &value {
+                    Value::List(values) => Box::new(values.iter()),
+                    Value::Map(values) => Box::new(values.values()),
+                    value => Box::new(std::iter::once(value)),
+                };
+                for number in numbers.filter_map(Value::as_enum_number) {
+                    if enum_desc.get_value(number).is_none() {
+                        return Err(SetFieldError::InvalidEnumNumber {
+                            field: field_desc.clone(),
+                            number,
+                        });
+                    }
+                }
+            }
+        }
+
+        self.try_set_field(field_desc, value)
+    }
+
     /// Clears the given field.
     ///
     /// After calling this method, `has_field` will return false for the field,
@@ -256,6 +531,28 @@ impl DynamicMessage {
         self.fields.clear(field_desc);
     }
 
+    /// Clears all fields of the message, including extension fields and unknown fields.
+    ///
+    /// The message keeps its descriptor, so it can be reused to hold a new value of the same type. After calling
+    /// this method, the message is equal to [`DynamicMessage::new`] and encodes to an empty buffer. This is
+    /// equivalent to [`Message::clear`], but does not require the trait to be imported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x20\x01".as_ref()).unwrap();
+    /// dynamic_message.clear();
+    /// assert!(!dynamic_message.has_field_by_name("foo"));
+    /// assert_eq!(dynamic_message.unknown_fields().count(), 0);
+    /// assert_eq!(dynamic_message.encoded_len(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.fields.clear_all();
+    }
+
     /// Returns `true` if this message has a field set with the given number.
     ///
     /// See [`has_field`][Self::has_field] for more details.
@@ -409,6 +706,44 @@ impl DynamicMessage {
         }
     }
 
+    /// Sets the value of each field in `fields`, looking fields up by name.
+    ///
+    /// Fields are set in order, as if by [`try_set_field_by_name`][Self::try_set_field_by_name]. If a field is not
+    /// found, or a value has an invalid type, an error is returned immediately and the remaining fields are not set.
+    /// Fields set before the error occurred are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, SetFieldError};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_fields_from([
+    ///     ("foo".to_owned(), Value::I32(150)),
+    ///     ("bar".to_owned(), Value::I32(66)),
+    /// ]).unwrap();
+    /// assert_eq!(dynamic_message.encode_to_vec(), b"\x08\x96\x01\x10\x42");
+    ///
+    /// assert_eq!(
+    ///     dynamic_message.set_fields_from([("notfound".to_owned(), Value::I32(5))]),
+    ///     Err(SetFieldError::FieldNameNotFound { name: "notfound".to_owned() }),
+    /// );
+    /// ```
+    pub fn set_fields_from<I>(&mut self, fields: I) -> Result<(), SetFieldError>
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        for (name, value) in fields {
+            match self.desc.get_field_by_name(&name) {
+                Some(field_desc) => self.try_set_field(&field_desc, value)?,
+                None => return Err(SetFieldError::FieldNameNotFound { name }),
+            }
+        }
+        Ok(())
+    }
+
     /// Clears the field with the given name.
     ///
     /// If no field with the given name exists, this method does nothing.
@@ -420,6 +755,30 @@ impl DynamicMessage {
         }
     }
 
+    /// Finds the first element of the repeated message field `field_desc` whose `key_field_desc` field is equal to
+    /// `key_value`.
+    ///
+    /// Returns `None` if the field is not a repeated message field, if `key_field_desc` is not a field of its
+    /// message type, or if no element has a matching key.
+    pub fn find_in_repeated(
+        &self,
+        field_desc: &FieldDescriptor,
+        key_field_desc: &FieldDescriptor,
+        key_value: &Value,
+    ) -> Option<&DynamicMessage> {
+        if field_desc.kind().as_message() != Some(key_field_desc.parent_message()) {
+            return None;
+        }
+
+        match self.get_field(field_desc) {
+            Cow::Borrowed(Value::List(values)) => values
+                .iter()
+                .filter_map(Value::as_message)
+                .find(|message| message.get_field(key_field_desc).as_ref() == key_value),
+            _ => None,
+        }
+    }
+
     /// Clears the value for the given field, and returns it.
     ///
     /// Returns the value if [`has_field`](Self::has_field) was `true`, or `None` otherwise.
@@ -499,6 +858,48 @@ impl DynamicMessage {
         self.fields.iter_fields(&self.desc)
     }
 
+    /// Gets an iterator over every field declared by this message type, yielding either its value or, if the field
+    /// is not set, its default value.
+    ///
+    /// Fields are yielded in declaration order. Fields which are part of a oneof, including proto3 `optional` fields,
+    /// are only yielded if they are set. Extension fields are not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::new(message_descriptor);
+    /// let fields: Vec<_> = dynamic_message
+    ///     .iter_all_fields()
+    ///     .map(|(field, value)| (field.name().to_owned(), value.into_owned()))
+    ///     .collect();
+    /// assert_eq!(fields.len(), 2);
+    /// assert_eq!(fields[0], ("foo".to_owned(), Value::I32(0)));
+    /// assert_eq!(fields[1].0, "nested");
+    /// ```
+    pub fn iter_all_fields(&self) -> impl Iterator<Item = (FieldDescriptor, Cow<'_, Value>)> {
+        self.desc
+            .fields()
+            .filter(|field_desc| {
+                field_desc.containing_oneof().is_none() || self.fields.has(field_desc)
+            })
+            .map(|field_desc| {
+                let value = self.fields.get(&field_desc);
+                (field_desc, value)
+            })
+    }
+
+    /// Gets an iterator over all fields and extension fields of this message, in ascending order of field number.
+    ///
+    /// The iterator will yield all fields for which [`has_field`](Self::has_field) returns `true`, and all
+    /// extension fields for which [`has_extension`](Self::has_extension) returns `true`.
+    pub fn iter_fields_sorted(&self) -> impl Iterator<Item = (FieldOrExtension, &'_ Value)> {
+        self.fields.iter_fields_and_extensions(&self.desc)
+    }
+
     /// Gets an iterator returning mutable references to all fields of this message.
     ///
     /// The iterator will yield all fields for which [`has_field`](Self::has_field) returns `true`.
@@ -555,6 +956,46 @@ impl DynamicMessage {
         self.fields.take_unknown()
     }
 
+    /// Decodes any unknown fields whose numbers match a field or extension of this message's type.
+    ///
+    /// Each matching unknown field is decoded into a value of the corresponding field, and removed from
+    /// [`unknown_fields()`][Self::unknown_fields]. Unknown fields which cannot be decoded as a value of the field, for
+    /// example because they were encoded with an incompatible wire type, are left unchanged. Unknown fields of nested
+    /// messages are not affected.
+    pub fn reparse_unknown_fields(&mut self) {
+        let numbers: Vec<u32> = self.fields.unknown_numbers().collect();
+        for number in numbers {
+            if let Some(field_desc) = self.desc.get_field(number) {
+                self.fields.reparse_unknown(&field_desc);
+            } else if let Some(extension_desc) = self.desc.get_extension(number) {
+                self.fields.reparse_unknown(&extension_desc);
+            }
+        }
+    }
+
+    /// Converts a strongly-typed message into a new [`DynamicMessage`] of the same type.
+    ///
+    /// This is the inverse of [`transcode_to`][Self::transcode_to], and is equivalent to
+    /// [`ReflectMessage::transcode_to_dynamic`]. The message is encoded, then decoded using the descriptor returned
+    /// by [`ReflectMessage::descriptor`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, ReflectMessage, Value};
+    /// let timestamp = prost_types::Timestamp { seconds: 1, nanos: 2 };
+    /// let dynamic_message = DynamicMessage::from_message(&timestamp);
+    /// assert_eq!(dynamic_message.descriptor().full_name(), "google.protobuf.Timestamp");
+    /// assert_eq!(dynamic_message.get_field_by_name("seconds").unwrap().as_ref(), &Value::I64(1));
+    /// assert_eq!(dynamic_message.transcode_to::<prost_types::Timestamp>().unwrap(), timestamp);
+    /// ```
+    pub fn from_message<T>(message: &T) -> Self
+    where
+        T: ReflectMessage,
+    {
+        message.transcode_to_dynamic()
+    }
+
     /// Merge a strongly-typed message into this one.
     ///
     /// The message should be compatible with the type specified by
@@ -580,6 +1021,433 @@ impl DynamicMessage {
         let buf = self.encode_to_vec();
         T::decode(buf.as_slice())
     }
+
+    /// Converts this message to a message of the type specified by `target`, matching fields by name.
+    ///
+    /// Each field is copied to the field of the same name in `target`, regardless of its field number, if the value
+    /// is compatible with the type of the target field. Nested messages are converted recursively. Fields which are
+    /// not found in `target` or have incompatible types are dropped, as are extension fields and unknown fields.
+    ///
+    /// This differs from [`transcode_to`][Self::transcode_to], which matches fields by their number.
+    pub fn remap_to(&self, target: MessageDescriptor) -> DynamicMessage {
+        let mut message = DynamicMessage::new(target);
+        for (field_desc, value) in self.fields() {
+            if let Some(target_field_desc) = message.desc.get_field_by_name(field_desc.name()) {
+                if let Some(value) = value.remap_to_field(&target_field_desc) {
+                    message.set_field(&target_field_desc, value);
+                }
+            }
+        }
+        message
+    }
+
+    /// Packs this message into a new `google.protobuf.Any` message.
+    ///
+    /// The `type_url` field is set to the full name of this message's type, prefixed by `type_url_prefix`, which
+    /// defaults to `type.googleapis.com/`. The `value` field is set to the encoded bytes of this message.
+    ///
+    /// If a custom prefix is used, pass the same prefix to `SerializeOptions::type_url_prefix()` and
+    /// `FormatOptions::type_url_prefix()` so that the packed message can still be expanded when serializing to JSON
+    /// or formatting as text, and use `DeserializeOptions::any_resolver()` to resolve it when deserializing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let any = dynamic_message.pack_into_any(None);
+    /// assert_eq!(any.get_field_by_name("type_url").unwrap().as_str(), Some("type.googleapis.com/package.MyMessage"));
+    /// assert_eq!(any.unpack_any(&pool).unwrap(), dynamic_message);
+    /// ```
+    pub fn pack_into_any(&self, type_url_prefix: Option<&str>) -> DynamicMessage {
+        let type_url_prefix = type_url_prefix.unwrap_or(GOOGLE_APIS_DOMAIN);
+        let type_url = if type_url_prefix.ends_with('/') {
+            format!("{}{}", type_url_prefix, self.desc.full_name())
+        } else {
+            format!("{}/{}", type_url_prefix, self.desc.full_name())
+        };
+
+        let any_desc = self
+            .desc
+            .parent_pool()
+            .get_message_by_name("google.protobuf.Any")
+            .unwrap_or_else(|| prost_types::Any::default().descriptor());
+        let mut any = DynamicMessage::new(any_desc);
+        any.set_field_by_name("type_url", Value::String(type_url));
+        any.set_field_by_name("value", Value::Bytes(self.encode_to_vec().into()));
+        any
+    }
+
+    /// Unpacks this `google.protobuf.Any` message, looking up the type named by its `type_url` field in `pool`.
+    ///
+    /// This is the inverse of [`pack_into_any`][Self::pack_into_any]. An error is returned if this message is not a
+    /// `google.protobuf.Any`, if the type is not found in the pool, or if the `value` field cannot be decoded as that
+    /// type.
+    pub fn unpack_any(&self, pool: &DescriptorPool) -> Result<DynamicMessage, DecodeError> {
+        if self.desc.full_name() != "google.protobuf.Any" {
+            return Err(DecodeError::new(format!(
+                "expected a message of type 'google.protobuf.Any', but found '{}'",
+                self.desc.full_name()
+            )));
+        }
+
+        let any = self.transcode_to::<prost_types::Any>()?;
+        let message_name = match any.type_url.rsplit_once('/') {
+            Some((_, message_name)) => message_name,
+            None => {
+                return Err(DecodeError::new(format!(
+                    "invalid type URL '{}'",
+                    any.type_url
+                )))
+            }
+        };
+        let desc = pool
+            .get_message_by_name(message_name)
+            .ok_or_else(|| DecodeError::new(format!("message '{}' not found", message_name)))?;
+        DynamicMessage::decode(desc, any.value.as_slice())
+    }
+
+    /// Returns the enum values found in enum fields of this message and any message nested within it, grouped by
+    /// enum type.
+    ///
+    /// Only fields which are set are inspected (see [`has_field`][Self::has_field]). Unknown fields are ignored.
+    #[allow(clippy::mutable_key_type)]
+    pub fn enum_values_used(&self) -> HashMap<EnumDescriptor, HashSet<i32>> {
+        let mut used = HashMap::new();
+        self.collect_enum_values_used(&mut used);
+        used
+    }
+
+    #[allow(clippy::mutable_key_type)]
+    fn collect_enum_values_used(&self, used: &mut HashMap<EnumDescriptor, HashSet<i32>>) {
+        for field in self.fields.iter(&self.desc) {
+            match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    value.collect_enum_values_used(&field_desc.kind(), used)
+                }
+                ValueAndDescriptor::Extension(value, extension_desc) => {
+                    value.collect_enum_values_used(&extension_desc.kind(), used)
+                }
+                ValueAndDescriptor::Unknown(_) => (),
+            }
+        }
+    }
+
+    /// Returns the paths of all `required` fields which are not set in this message or any message nested within it.
+    ///
+    /// Fields of nested messages are separated by `.`, elements of repeated fields are identified by their index
+    /// (e.g. `field[0].name`) and values of map fields by their key (e.g. `field["key"].name`). Extension fields are
+    /// identified by their full name in square brackets.
+    ///
+    /// Required fields are only supported in proto2 files. Encoding a message with missing required fields produces
+    /// bytes which other protobuf implementations may fail to decode.
+    pub fn find_missing_required_fields(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        self.find_missing_required_fields_with_prefix("", &mut missing);
+        missing
+    }
+
+    /// Returns `true` if all `required` fields are set in this message and any message nested within it.
+    ///
+    /// This is equivalent to checking that [`find_missing_required_fields`][Self::find_missing_required_fields]
+    /// returns an empty list.
+    pub fn is_initialized(&self) -> bool {
+        self.find_missing_required_fields().is_empty()
+    }
+
+    /// Returns `true` if the encoded form of this message can be decoded as the `target` message type without losing
+    /// or misinterpreting any data.
+    ///
+    /// Every field which is set in this message must have a field of the same number in `target`, with a compatible
+    /// wire type. Repeated fields are only compatible with repeated fields, map fields are only compatible with map
+    /// fields, and message fields are checked recursively against the corresponding message type of `target`.
+    /// Unknown fields are compatible unless `target` defines a field with the same number and a different wire type.
+    ///
+    /// Note that fields with the same wire type may still be interpreted differently, for example an `int32` field
+    /// decoded as a `sint32` field.
+    pub fn is_compatible_with(&self, target: &MessageDescriptor) -> bool {
+        self.fields.iter(&self.desc).all(|field| match field {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                is_field_compatible(&field_desc, &value, target)
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                is_field_compatible(&extension_desc, &value, target)
+            }
+            ValueAndDescriptor::Unknown(unknowns) => {
+                unknowns
+                    .iter()
+                    .all(|unknown| match target.get_field(unknown.number()) {
+                        Some(target_desc) => {
+                            is_wire_type_compatible(unknown.wire_type(), &target_desc)
+                        }
+                        None => match target.get_extension(unknown.number()) {
+                            Some(target_desc) => {
+                                is_wire_type_compatible(unknown.wire_type(), &target_desc)
+                            }
+                            None => true,
+                        },
+                    })
+            }
+        })
+    }
+
+    /// Creates a copy of this message whose descriptor is taken from `pool`, for example after the pool containing
+    /// the original descriptor has been rebuilt.
+    ///
+    /// The message type is looked up in `pool` by its full name, and must be [compatible](Self::is_compatible_with)
+    /// with the contents of this message. Nested messages, extension fields and unknown fields are copied as well,
+    /// and refer to the corresponding types in `pool`. An unknown field becomes known if `pool` defines a field with
+    /// its number.
+    ///
+    /// Returns an error if the message type is not found in `pool`, or if it is not compatible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, ReflectMessage, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    ///
+    /// let new_pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let rebound = dynamic_message.rebind_to_pool(&new_pool).unwrap();
+    /// assert_eq!(rebound.descriptor().parent_pool(), &new_pool);
+    /// assert_eq!(rebound.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    ///
+    /// assert!(dynamic_message.rebind_to_pool(&DescriptorPool::global()).is_err());
+    /// ```
+    pub fn rebind_to_pool(&self, pool: &DescriptorPool) -> Result<DynamicMessage, DecodeError> {
+        let name = self.desc.full_name();
+        let desc = pool
+            .get_message_by_name(name)
+            .ok_or_else(|| DecodeError::new(format!("message '{}' not found", name)))?;
+        if !self.is_compatible_with(&desc) {
+            return Err(DecodeError::new(format!(
+                "message is not compatible with the definition of '{}' in the new pool",
+                name
+            )));
+        }
+
+        DynamicMessage::decode(desc, self.encode_to_vec().as_slice())
+    }
+
+    /// Returns a compact, single-line summary of this message, suitable for log output.
+    ///
+    /// The summary consists of the message name followed by its set fields, for example
+    /// `MyMessage{foo=150, nested={bar:66}}`. If the summary would be longer than `max_len` characters, it is
+    /// truncated and ends with `...`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// assert_eq!(dynamic_message.summary(100), "MyMessage{foo=150, nested={bar:66}}");
+    /// assert_eq!(dynamic_message.summary(16), "MyMessage{foo...");
+    /// ```
+    pub fn summary(&self, max_len: usize) -> String {
+        let mut summary = format!("{}{{", self.desc.name());
+        for (index, (field_desc, value)) in self.fields().enumerate() {
+            if index != 0 {
+                summary.push_str(", ");
+            }
+            let _ = write!(summary, "{}={}", field_desc.name(), value);
+        }
+        summary.push('}');
+
+        if summary.chars().count() > max_len {
+            const ELLIPSIS: &str = "...";
+            summary = summary
+                .chars()
+                .take(max_len.saturating_sub(ELLIPSIS.len()))
+                .chain(ELLIPSIS.chars())
+                .take(max_len)
+                .collect();
+        }
+        summary
+    }
+
+    /// Flattens this message and any message nested within it into a sorted map from field paths to values,
+    /// suitable for emitting as telemetry dimensions.
+    ///
+    /// Each key is the path of a singular scalar field, with the names of nested message fields joined by
+    /// `separator`. Elements of repeated fields are identified by their index (e.g. `field[0]`) and values of map
+    /// fields by their key (e.g. `field["key"]`), and extension fields by their full name in square brackets. Values
+    /// are written using the protobuf text format, so strings are quoted and enum values are written by name.
+    ///
+    /// Only fields which are set are included (see [`has_field`][Self::has_field]). Unknown fields, and fields set
+    /// to an empty message, produce no entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let map = dynamic_message.to_flat_map(".");
+    /// assert_eq!(map.len(), 2);
+    /// assert_eq!(map["foo"], "150");
+    /// assert_eq!(map["nested.bar"], "66");
+    /// ```
+    pub fn to_flat_map(&self, separator: &str) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        self.collect_flat_map("", separator, &mut map);
+        map
+    }
+
+    fn collect_flat_map(&self, prefix: &str, separator: &str, map: &mut BTreeMap<String, String>) {
+        let fields = self
+            .fields
+            .iter_fields(&self.desc)
+            .map(|(field_desc, value)| (field_desc.name().to_owned(), field_desc.kind(), value));
+        let extensions = self
+            .fields
+            .iter_extensions(&self.desc)
+            .map(|(extension_desc, value)| {
+                (
+                    format!("[{}]", extension_desc.full_name()),
+                    extension_desc.kind(),
+                    value,
+                )
+            });
+        for (name, kind, value) in fields.chain(extensions) {
+            let path = format!("{}{}", prefix, name);
+            match value {
+                Value::List(values) => {
+                    for (index, value) in values.iter().enumerate() {
+                        value.collect_flat_map(
+                            format!("{}[{}]", path, index),
+                            &kind,
+                            separator,
+                            map,
+                        );
+                    }
+                }
+                Value::Map(values) => {
+                    let value_kind = match &kind {
+                        Kind::Message(entry_desc) => entry_desc.map_entry_value_field().kind(),
+                        kind => kind.clone(),
+                    };
+                    for (key, value) in values {
+                        let path = format!("{}[{}]", path, Value::from(key.clone()));
+                        value.collect_flat_map(path, &value_kind, separator, map);
+                    }
+                }
+                value => value.collect_flat_map(path, &kind, separator, map),
+            }
+        }
+    }
+
+    /// Clears all fields for which `is_sensitive` returns `true`, in this message and any message nested within it.
+    ///
+    /// Fields of messages contained in singular, repeated and map fields are redacted recursively. The predicate
+    /// may use any property of the field descriptor, for example a custom option read using
+    /// [`FieldDescriptor::options()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x04\x08\x01\x10\x42".as_ref()).unwrap();
+    /// dynamic_message.redact_fields(|field| field.name() == "foo");
+    /// assert_eq!(dynamic_message.encode_to_vec(), b"\x1a\x02\x10\x42");
+    /// ```
+    pub fn redact_fields(&mut self, is_sensitive: impl Fn(&FieldDescriptor) -> bool) {
+        self.redact_fields_with(&is_sensitive)
+    }
+
+    fn redact_fields_with<F>(&mut self, is_sensitive: &F)
+    where
+        F: Fn(&FieldDescriptor) -> bool,
+    {
+        let sensitive_fields: Vec<_> = self
+            .fields()
+            .map(|(field_desc, _)| field_desc)
+            .filter(|field_desc| is_sensitive(field_desc))
+            .collect();
+        for field_desc in sensitive_fields {
+            self.clear_field(&field_desc);
+        }
+
+        for (_, value) in self.fields_mut() {
+            match value {
+                Value::Message(message) => message.redact_fields_with(is_sensitive),
+                Value::List(values) => {
+                    for value in values {
+                        if let Value::Message(message) = value {
+                            message.redact_fields_with(is_sensitive);
+                        }
+                    }
+                }
+                Value::Map(values) => {
+                    for value in values.values_mut() {
+                        if let Value::Message(message) = value {
+                            message.redact_fields_with(is_sensitive);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn find_missing_required_fields_with_prefix(&self, prefix: &str, missing: &mut Vec<String>) {
+        for field_desc in self.desc.fields() {
+            if field_desc.cardinality() == Cardinality::Required && !self.has_field(&field_desc) {
+                missing.push(format!("{}{}", prefix, field_desc.name()));
+            }
+        }
+
+        let fields = self
+            .fields
+            .iter_fields(&self.desc)
+            .map(|(field_desc, value)| (field_desc.name().to_owned(), value));
+        let extensions = self
+            .fields
+            .iter_extensions(&self.desc)
+            .map(|(extension_desc, value)| (format!("[{}]", extension_desc.full_name()), value));
+        for (name, value) in fields.chain(extensions) {
+            match value {
+                Value::Message(message) => message.find_missing_required_fields_with_prefix(
+                    &format!("{}{}.", prefix, name),
+                    missing,
+                ),
+                Value::List(values) => {
+                    for (index, value) in values.iter().enumerate() {
+                        if let Value::Message(message) = value {
+                            message.find_missing_required_fields_with_prefix(
+                                &format!("{}{}[{}].", prefix, name, index),
+                                missing,
+                            );
+                        }
+                    }
+                }
+                Value::Map(values) => {
+                    for (key, value) in values {
+                        if let Value::Message(message) = value {
+                            message.find_missing_required_fields_with_prefix(
+                                &format!("{}{}[{}].", prefix, name, Value::from(key.clone())),
+                                missing,
+                            );
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
 }
 
 impl ReflectMessage for DynamicMessage {
@@ -596,6 +1464,27 @@ impl ReflectMessage for DynamicMessage {
 }
 
 impl Value {
+    fn collect_flat_map(
+        &self,
+        path: String,
+        kind: &Kind,
+        separator: &str,
+        map: &mut BTreeMap<String, String>,
+    ) {
+        match self {
+            Value::Message(message) => {
+                message.collect_flat_map(&format!("{}{}", path, separator), separator, map)
+            }
+            value => {
+                let mut text = String::new();
+                text_format::Writer::new(text_format::FormatOptions::new(), &mut text)
+                    .fmt_value(value, Some(kind))
+                    .expect("writing to string cannot fail");
+                map.insert(path, text);
+            }
+        }
+    }
+
     /// Returns the default value for the given protobuf field.
     ///
     /// This is equivalent to [`default_value`][Value::default_value] except for the following cases:
@@ -608,7 +1497,7 @@ impl Value {
             Value::List(Vec::default())
         } else if field_desc.is_map() {
             Value::Map(HashMap::default())
-        } else if let Some(default_value) = field_desc.default_value() {
+        } else if let Some(default_value) = field_desc.explicit_default_value() {
             default_value.clone()
         } else {
             Self::default_value(&field_desc.kind())
@@ -623,13 +1512,52 @@ impl Value {
             Value::List(Vec::default())
         } else if extension_desc.is_map() {
             Value::Map(HashMap::default())
-        } else if let Some(default_value) = extension_desc.default_value() {
+        } else if let Some(default_value) = extension_desc.explicit_default_value() {
             default_value.clone()
         } else {
             Self::default_value(&extension_desc.kind())
         }
     }
 
+    /// Creates a map value for the given map field from an iterator of entries.
+    ///
+    /// Each key and value is checked against the key and value types of the field's map entry. If the field is not
+    /// a map, or any entry has the wrong type, an error is returned containing the first invalid key or value.
+    pub fn map_for_field<I>(field_desc: &FieldDescriptor, entries: I) -> Result<Self, SetFieldError>
+    where
+        I: IntoIterator<Item = (MapKey, Value)>,
+    {
+        let entry_desc = match field_desc.kind() {
+            Kind::Message(entry_desc) if field_desc.is_map() => entry_desc,
+            _ => {
+                return Err(SetFieldError::InvalidType {
+                    field: field_desc.clone(),
+                    value: Value::Map(entries.into_iter().collect()),
+                })
+            }
+        };
+        let key_kind = entry_desc.map_entry_key_field().kind();
+        let value_desc = entry_desc.map_entry_value_field();
+
+        let mut map = HashMap::new();
+        for (key, value) in entries {
+            if !key.is_valid(&key_kind) {
+                return Err(SetFieldError::InvalidType {
+                    field: field_desc.clone(),
+                    value: Value::from(key),
+                });
+            }
+            if !value.is_valid_for_field(&value_desc) {
+                return Err(SetFieldError::InvalidType {
+                    field: field_desc.clone(),
+                    value,
+                });
+            }
+            map.insert(key, value);
+        }
+        Ok(Value::Map(map))
+    }
+
     /// Returns the default value for the given protobuf type `kind`.
     ///
     /// Unlike [`default_value_for_field`](Value::default_value_for_field), this method does not
@@ -725,6 +1653,94 @@ impl Value {
         )
     }
 
+    fn remap_to_field(&self, field_desc: &FieldDescriptor) -> Option<Value> {
+        let kind = field_desc.kind();
+        match self {
+            Value::Map(values) if field_desc.is_map() => {
+                let map_entry = kind.as_message()?;
+                let key_kind = map_entry.map_entry_key_field().kind();
+                let value_kind = map_entry.map_entry_value_field().kind();
+                values
+                    .iter()
+                    .map(|(key, value)| {
+                        if key.is_valid(&key_kind) {
+                            Some((key.clone(), value.remap_to_kind(&value_kind)?))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Option<_>>()
+                    .map(Value::Map)
+            }
+            Value::List(values) if field_desc.is_list() => values
+                .iter()
+                .map(|value| value.remap_to_kind(&kind))
+                .collect::<Option<_>>()
+                .map(Value::List),
+            Value::Map(_) | Value::List(_) => None,
+            _ if field_desc.is_map() || field_desc.is_list() => None,
+            value => value.remap_to_kind(&kind),
+        }
+    }
+
+    fn remap_to_kind(&self, kind: &Kind) -> Option<Value> {
+        match (self, kind) {
+            (Value::Message(message), Kind::Message(desc)) => {
+                if &message.desc == desc {
+                    Some(self.clone())
+                } else {
+                    Some(Value::Message(message.remap_to(desc.clone())))
+                }
+            }
+            (value, kind) if value.is_valid(kind) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::mutable_key_type)]
+    fn collect_enum_values_used(
+        &self,
+        kind: &Kind,
+        used: &mut HashMap<EnumDescriptor, HashSet<i32>>,
+    ) {
+        match (self, kind) {
+            (Value::EnumNumber(number), Kind::Enum(enum_desc)) => {
+                used.entry(enum_desc.clone()).or_default().insert(*number);
+            }
+            (Value::Message(message), _) => message.collect_enum_values_used(used),
+            (Value::List(values), _) => {
+                for value in values {
+                    value.collect_enum_values_used(kind, used);
+                }
+            }
+            (Value::Map(values), Kind::Message(map_entry)) => {
+                let value_kind = map_entry.map_entry_value_field().kind();
+                for value in values.values() {
+                    value.collect_enum_values_used(&value_kind, used);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "Bool",
+            Value::I32(_) => "I32",
+            Value::I64(_) => "I64",
+            Value::U32(_) => "U32",
+            Value::U64(_) => "U64",
+            Value::F32(_) => "F32",
+            Value::F64(_) => "F64",
+            Value::String(_) => "String",
+            Value::Bytes(_) => "Bytes",
+            Value::EnumNumber(_) => "EnumNumber",
+            Value::Message(_) => "Message",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+        }
+    }
+
     /// Returns the value if it is a `Value::Bool`, or `None` if it is any other type.
     pub fn as_bool(&self) -> Option<bool> {
         match *self {
@@ -837,6 +1853,62 @@ impl Value {
         }
     }
 
+    /// Returns the value as a `u32` if it is an integer within range, or `None` otherwise.
+    ///
+    /// Unlike [`as_u32`][Value::as_u32], this accepts any of `Value::I32`, `Value::I64`, `Value::U32` or
+    /// `Value::U64`.
+    pub fn coerce_u32(&self) -> Option<u32> {
+        self.coerce_integer()
+    }
+
+    /// Returns the value as a `u64` if it is an integer within range, or `None` otherwise.
+    ///
+    /// Unlike [`as_u64`][Value::as_u64], this accepts any of `Value::I32`, `Value::I64`, `Value::U32` or
+    /// `Value::U64`.
+    pub fn coerce_u64(&self) -> Option<u64> {
+        self.coerce_integer()
+    }
+
+    /// Returns the value as an `i32` if it is an integer within range, or `None` otherwise.
+    ///
+    /// Unlike [`as_i32`][Value::as_i32], this accepts any of `Value::I32`, `Value::I64`, `Value::U32` or
+    /// `Value::U64`.
+    pub fn coerce_i32(&self) -> Option<i32> {
+        self.coerce_integer()
+    }
+
+    /// Returns the value as an `i64` if it is an integer within range, or `None` otherwise.
+    ///
+    /// Unlike [`as_i64`][Value::as_i64], this accepts any of `Value::I32`, `Value::I64`, `Value::U32` or
+    /// `Value::U64`.
+    pub fn coerce_i64(&self) -> Option<i64> {
+        self.coerce_integer()
+    }
+
+    /// Returns the value as an `f64` if it is a `Value::F32` or `Value::F64`, or `None` otherwise.
+    ///
+    /// Unlike [`as_f64`][Value::as_f64], this also accepts `Value::F32`. Integer values are not converted.
+    pub fn coerce_f64(&self) -> Option<f64> {
+        match *self {
+            Value::F32(value) => Some(value.into()),
+            Value::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn coerce_integer<T>(&self) -> Option<T>
+    where
+        T: TryFrom<i32> + TryFrom<i64> + TryFrom<u32> + TryFrom<u64>,
+    {
+        match *self {
+            Value::I32(value) => T::try_from(value).ok(),
+            Value::I64(value) => T::try_from(value).ok(),
+            Value::U32(value) => T::try_from(value).ok(),
+            Value::U64(value) => T::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
     /// Returns the value if it is a `Value::EnumNumber`, or `None` if it is any other type.
     pub fn as_enum_number(&self) -> Option<i32> {
         match *self {
@@ -917,6 +1989,68 @@ impl Value {
         }
     }
 
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::Bool`, or `None`
+    /// otherwise.
+    pub fn as_bool_list(&self) -> Option<Vec<bool>> {
+        self.as_list_of(Value::as_bool)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::I32`, or `None`
+    /// otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::Value;
+    /// let list = Value::List(vec![Value::I32(1), Value::I32(2)]);
+    /// assert_eq!(list.as_i32_list(), Some(vec![1, 2]));
+    /// assert_eq!(Value::List(vec![Value::I32(1), Value::I64(2)]).as_i32_list(), None);
+    /// assert_eq!(Value::I32(1).as_i32_list(), None);
+    /// ```
+    pub fn as_i32_list(&self) -> Option<Vec<i32>> {
+        self.as_list_of(Value::as_i32)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::I64`, or `None`
+    /// otherwise.
+    pub fn as_i64_list(&self) -> Option<Vec<i64>> {
+        self.as_list_of(Value::as_i64)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::U32`, or `None`
+    /// otherwise.
+    pub fn as_u32_list(&self) -> Option<Vec<u32>> {
+        self.as_list_of(Value::as_u32)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::U64`, or `None`
+    /// otherwise.
+    pub fn as_u64_list(&self) -> Option<Vec<u64>> {
+        self.as_list_of(Value::as_u64)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::F32`, or `None`
+    /// otherwise.
+    pub fn as_f32_list(&self) -> Option<Vec<f32>> {
+        self.as_list_of(Value::as_f32)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::F64`, or `None`
+    /// otherwise.
+    pub fn as_f64_list(&self) -> Option<Vec<f64>> {
+        self.as_list_of(Value::as_f64)
+    }
+
+    /// Returns the elements of the value if it is a `Value::List` where every element is a `Value::EnumNumber`, or `None`
+    /// otherwise.
+    pub fn as_enum_number_list(&self) -> Option<Vec<i32>> {
+        self.as_list_of(Value::as_enum_number)
+    }
+
+    fn as_list_of<T>(&self, f: impl Fn(&Value) -> Option<T>) -> Option<Vec<T>> {
+        self.as_list()?.iter().map(f).collect()
+    }
+
     /// Returns a a reference to the value if it is a `Value::Map`, or `None` if it is any other type.
     pub fn as_map(&self) -> Option<&HashMap<MapKey, Value>> {
         match self {
@@ -933,6 +2067,31 @@ impl Value {
         }
     }
 
+    /// Returns the entries of the value sorted by key if it is a `Value::Map`, or `None` if it is any other type.
+    ///
+    /// Keys are compared using the [`Ord`] implementation of [`MapKey`], which orders keys of the same variant by
+    /// value, and keys of different variants by the order of the variants, so the order is total. This is the
+    /// order used when map fields are formatted with sorted keys or [hashed][DynamicMessage::stable_hash].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use prost_reflect::{Value, MapKey};
+    /// let value = Value::Map(HashMap::from([
+    ///     (MapKey::I32(3), Value::Bool(true)),
+    ///     (MapKey::I32(-1), Value::Bool(false)),
+    /// ]));
+    /// assert_eq!(
+    ///     value.sorted_map_entries(),
+    ///     Some(vec![(&MapKey::I32(-1), &Value::Bool(false)), (&MapKey::I32(3), &Value::Bool(true))]),
+    /// );
+    /// assert_eq!(Value::I32(3).sorted_map_entries(), None);
+    /// ```
+    pub fn sorted_map_entries(&self) -> Option<Vec<(&MapKey, &Value)>> {
+        self.as_map().map(sorted_map_entries)
+    }
+
     /// Converts this value into a [`MapKey`], or `None` if it is not a valid map key type.
     ///
     /// # Examples
@@ -954,6 +2113,35 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Converts this value into a [`MapKey`], returning an error if it is not a valid map key type.
+    ///
+    /// This is equivalent to [`into_map_key`][Self::into_map_key], except that the value is returned as part of
+    /// the error if it cannot be converted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{Value, MapKey};
+    /// assert_eq!(Value::U64(5).try_into_map_key(), Ok(MapKey::U64(5)));
+    ///
+    /// let err = Value::F32(1.5).try_into_map_key().unwrap_err();
+    /// assert_eq!(err.into_value(), Value::F32(1.5));
+    /// ```
+    pub fn try_into_map_key(self) -> Result<MapKey, TryFromValueError> {
+        match self {
+            Value::Bool(value) => Ok(MapKey::Bool(value)),
+            Value::I32(value) => Ok(MapKey::I32(value)),
+            Value::I64(value) => Ok(MapKey::I64(value)),
+            Value::U32(value) => Ok(MapKey::U32(value)),
+            Value::U64(value) => Ok(MapKey::U64(value)),
+            Value::String(value) => Ok(MapKey::String(value)),
+            value => Err(TryFromValueError {
+                expected: "MapKey",
+                value,
+            }),
+        }
+    }
 }
 
 impl MapKey {
@@ -983,6 +2171,19 @@ impl MapKey {
         *self == MapKey::default_value(kind)
     }
 
+    /// Converts this map key into the equivalent [`Value`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{Value, MapKey};
+    /// assert_eq!(MapKey::I32(5).to_value(), Value::I32(5));
+    /// assert_eq!(MapKey::String("foo".to_owned()).to_value(), Value::String("foo".to_owned()));
+    /// ```
+    pub fn to_value(&self) -> Value {
+        Value::from(self.clone())
+    }
+
     /// Returns `true` if this map key can be encoded as the given [`Kind`].
     pub fn is_valid(&self, kind: &Kind) -> bool {
         matches!(
@@ -1106,10 +2307,115 @@ impl From<MapKey> for Value {
     }
 }
 
+impl TryFrom<Value> for MapKey {
+    type Error = TryFromValueError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.try_into_map_key()
+    }
+}
+
+macro_rules! impl_value_conversions {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value.into())
+                }
+            }
+
+            impl TryFrom<Value> for $ty {
+                type Error = TryFromValueError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    match value {
+                        Value::$variant(value) => Ok(value.into()),
+                        value => Err(TryFromValueError {
+                            expected: stringify!($variant),
+                            value,
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_value_conversions! {
+    bool => Bool,
+    i32 => I32,
+    i64 => I64,
+    u32 => U32,
+    u64 => U64,
+    f32 => F32,
+    f64 => F64,
+    String => String,
+    Vec<u8> => Bytes,
+    Bytes => Bytes,
+    DynamicMessage => Message,
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl FieldOrExtension {
+    /// Gets the unique number for this field or extension field.
+    pub fn number(&self) -> u32 {
+        match self {
+            FieldOrExtension::Field(field_desc) => field_desc.number(),
+            FieldOrExtension::Extension(extension_desc) => extension_desc.number(),
+        }
+    }
+
+    /// Gets the [`Kind`] of this field or extension field.
+    pub fn kind(&self) -> Kind {
+        match self {
+            FieldOrExtension::Field(field_desc) => field_desc.kind(),
+            FieldOrExtension::Extension(extension_desc) => extension_desc.kind(),
+        }
+    }
+}
+
+impl TryFromValueError {
+    /// Returns the name of the [`Value`] variant which was expected, for example `"I32"`.
+    ///
+    /// If the value was being converted to a [`MapKey`], this returns `"MapKey"`.
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+
+    /// Returns the value which could not be converted.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the value which could not be converted, consuming the error.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+}
+
+impl fmt::Display for TryFromValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a value of variant '{}', but found '{}'",
+            self.expected,
+            self.value.variant_name()
+        )
+    }
+}
+
+impl Error for TryFromValueError {}
+
 impl fmt::Display for SetFieldError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SetFieldError::NotFound => write!(f, "field not found"),
+            SetFieldError::FieldNameNotFound { name } => write!(f, "field '{}' not found", name),
             SetFieldError::InvalidType { field, value } => {
                 write!(f, "expected a value of type '")?;
                 if field.is_map() {
@@ -1117,24 +2423,149 @@ impl fmt::Display for SetFieldError {
                     let entry = entry.as_message().unwrap();
                     write!(
                         f,
-                        "map<{:?}, {:?}>",
-                        entry.map_entry_key_field().kind(),
-                        entry.map_entry_value_field().kind()
+                        "map<{}, {}>",
+                        entry.map_entry_key_field().kind().proto_name(),
+                        entry.map_entry_value_field().kind().proto_name()
                     )?;
                 } else if field.is_list() {
-                    write!(f, "repeated {:?}", field.kind())?;
+                    write!(f, "repeated {}", field.kind().proto_name())?;
                 } else {
-                    write!(f, "{:?}", field.kind())?;
+                    write!(f, "{}", field.kind().proto_name())?;
                 }
                 write!(f, "', but found '{}'", value)
             }
+            SetFieldError::InvalidEnumNumber { field, number } => write!(
+                f,
+                "enum number {} is not defined by the closed enum type '{}' of field '{}'",
+                number,
+                field.kind().proto_name(),
+                field.full_name()
+            ),
         }
     }
 }
 
 impl Error for SetFieldError {}
 
-pub(crate) fn fmt_string(f: &mut impl fmt::Write, bytes: &[u8]) -> fmt::Result {
+fn is_field_compatible(
+    field_desc: &impl FieldDescriptorLike,
+    value: &Value,
+    target: &MessageDescriptor,
+) -> bool {
+    match target.get_field(field_desc.number()) {
+        Some(target_desc) => is_value_compatible(field_desc, value, &target_desc),
+        None => match target.get_extension(field_desc.number()) {
+            Some(target_desc) => is_value_compatible(field_desc, value, &target_desc),
+            None => false,
+        },
+    }
+}
+
+fn is_value_compatible(
+    field_desc: &impl FieldDescriptorLike,
+    value: &Value,
+    target_desc: &impl FieldDescriptorLike,
+) -> bool {
+    if field_desc.is_map() || target_desc.is_map() {
+        let (entry_desc, target_entry_desc) = match (field_desc.kind(), target_desc.kind()) {
+            (Kind::Message(entry), Kind::Message(target_entry))
+                if field_desc.is_map() && target_desc.is_map() =>
+            {
+                (entry, target_entry)
+            }
+            _ => return false,
+        };
+
+        let value_desc = entry_desc.map_entry_value_field();
+        let target_value_desc = target_entry_desc.map_entry_value_field();
+        return entry_desc.map_entry_key_field().kind().wire_type()
+            == target_entry_desc.map_entry_key_field().kind().wire_type()
+            && value
+                .as_map()
+                .unwrap()
+                .values()
+                .all(|value| is_value_compatible(&value_desc, value, &target_value_desc));
+    }
+
+    if field_desc.is_list() && !target_desc.is_list() {
+        return false;
+    }
+
+    match (field_desc.kind(), target_desc.kind()) {
+        (Kind::Message(_), Kind::Message(target_message)) => {
+            let is_compatible = |value: &Value| match value {
+                Value::Message(message) => message.is_compatible_with(&target_message),
+                _ => false,
+            };
+            field_desc.is_group() == target_desc.is_group()
+                && match value {
+                    Value::List(values) => values.iter().all(is_compatible),
+                    value => is_compatible(value),
+                }
+        }
+        (Kind::Message(_), _) | (_, Kind::Message(_)) => false,
+        (kind, target_kind) => kind.wire_type() == target_kind.wire_type(),
+    }
+}
+
+fn is_wire_type_compatible(wire_type: WireType, target_desc: &impl FieldDescriptorLike) -> bool {
+    if target_desc.is_group() {
+        wire_type == WireType::StartGroup
+    } else if target_desc.is_list()
+        && target_desc.is_packable()
+        && wire_type == WireType::LengthDelimited
+    {
+        true
+    } else {
+        wire_type == target_desc.kind().wire_type()
+    }
+}
+
+/// Returns the kind of the values of a field, or the kind of the map values if the field is a map.
+fn value_kind(field_desc: &FieldDescriptor) -> Kind {
+    match field_desc.kind() {
+        Kind::Message(entry_desc) if field_desc.is_map() => {
+            entry_desc.map_entry_value_field().kind()
+        }
+        kind => kind,
+    }
+}
+
+pub(crate) fn sorted_map_entries(map: &HashMap<MapKey, Value>) -> Vec<(&MapKey, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    entries
+}
+
+/// Returns the message name of an `Any` type URL, if it starts with `type_url_prefix` or one of the standard
+/// `type.googleapis.com/` and `type.googleprod.com/` prefixes.
+pub(crate) fn strip_type_url_prefix<'a>(
+    type_url: &'a str,
+    type_url_prefix: Option<&str>,
+) -> Option<&'a str> {
+    if let Some(prefix) = type_url_prefix {
+        let message_name = type_url.strip_prefix(prefix).and_then(|rest| {
+            if prefix.ends_with('/') {
+                Some(rest)
+            } else {
+                rest.strip_prefix('/')
+            }
+        });
+        if message_name.is_some() {
+            return message_name;
+        }
+    }
+
+    type_url
+        .strip_prefix(GOOGLE_APIS_DOMAIN)
+        .or_else(|| type_url.strip_prefix(GOOGLE_PROD_DOMAIN))
+}
+
+pub(crate) fn fmt_string(
+    f: &mut impl fmt::Write,
+    bytes: &[u8],
+    escape_style: EscapeStyle,
+) -> fmt::Result {
     f.write_char('"')?;
     for &ch in bytes {
         match ch {
@@ -1145,9 +2576,10 @@ pub(crate) fn fmt_string(f: &mut impl fmt::Write, bytes: &[u8]) -> fmt::Result {
             b'\'' => f.write_str("\\'")?,
             b'"' => f.write_str("\\\"")?,
             b'\x20'..=b'\x7e' => f.write_char(ch as char)?,
-            _ => {
-                write!(f, "\\{:03o}", ch)?;
-            }
+            _ => match escape_style {
+                EscapeStyle::Octal => write!(f, "\\{:03o}", ch)?,
+                EscapeStyle::Hex => write!(f, "\\x{:02x}", ch)?,
+            },
         }
     }
     f.write_char('"')
@@ -1200,3 +2632,42 @@ fn type_sizes() {
     assert_eq!(std::mem::size_of::<DynamicMessage>(), 40);
     assert_eq!(std::mem::size_of::<Value>(), 56);
 }
+
+#[test]
+fn value_conversions() {
+    assert_eq!(Value::from(5i32), Value::I32(5));
+    assert_eq!(Value::from("foo"), Value::String("foo".to_owned()));
+    assert_eq!(
+        Value::from(b"foo".to_vec()),
+        Value::Bytes(Bytes::from_static(b"foo"))
+    );
+    assert_eq!(i32::try_from(Value::I32(5)), Ok(5));
+    assert_eq!(
+        Vec::<u8>::try_from(Value::Bytes(Bytes::from_static(b"foo"))),
+        Ok(b"foo".to_vec())
+    );
+
+    let err = u64::try_from(Value::String("foo".to_owned())).unwrap_err();
+    assert_eq!(err.expected(), "U64");
+    assert_eq!(err.value(), &Value::String("foo".to_owned()));
+    assert_eq!(
+        err.to_string(),
+        "expected a value of variant 'U64', but found 'String'"
+    );
+}
+
+#[test]
+fn value_coercions() {
+    assert_eq!(Value::I64(5).coerce_u64(), Some(5));
+    assert_eq!(Value::I32(-1).coerce_u64(), None);
+    assert_eq!(Value::U64(u64::MAX).coerce_i64(), None);
+    assert_eq!(Value::U64(7).coerce_i32(), Some(7));
+    assert_eq!(Value::I64(i64::from(u32::MAX) + 1).coerce_u32(), None);
+    assert_eq!(Value::U32(u32::MAX).coerce_i64(), Some(i64::from(u32::MAX)));
+    assert_eq!(Value::String("5".to_owned()).coerce_i32(), None);
+    assert_eq!(Value::F64(5.0).coerce_i64(), None);
+
+    assert_eq!(Value::F32(1.5).coerce_f64(), Some(1.5));
+    assert_eq!(Value::F64(2.5).coerce_f64(), Some(2.5));
+    assert_eq!(Value::I32(1).coerce_f64(), None);
+}
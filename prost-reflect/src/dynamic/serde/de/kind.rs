@@ -9,7 +9,8 @@ use crate::{
 };
 
 use super::{
-    deserialize_enum, deserialize_message, FieldDescriptorSeed, OptionalFieldDescriptorSeed,
+    super::decode_base64, deserialize_enum, deserialize_message, FieldDescriptorSeed,
+    OptionalFieldDescriptorSeed,
 };
 
 pub struct KindSeed<'a>(pub &'a Kind, pub &'a DeserializeOptions);
@@ -491,29 +492,8 @@ impl<'de> Visitor<'de> for BytesVisitor {
     where
         E: Error,
     {
-        use base64::{
-            alphabet,
-            engine::DecodePaddingMode,
-            engine::{GeneralPurpose, GeneralPurposeConfig},
-            DecodeError, Engine,
-        };
-
-        const CONFIG: GeneralPurposeConfig = GeneralPurposeConfig::new()
-            .with_decode_allow_trailing_bits(true)
-            .with_decode_padding_mode(DecodePaddingMode::Indifferent);
-        const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, CONFIG);
-        const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG);
-
-        let mut buf = Vec::new();
-        match STANDARD.decode_vec(v, &mut buf) {
-            Ok(()) => Ok(buf.into()),
-            Err(DecodeError::InvalidByte(_, b'-')) | Err(DecodeError::InvalidByte(_, b'_')) => {
-                buf.clear();
-                match URL_SAFE.decode_vec(v, &mut buf) {
-                    Ok(()) => Ok(buf.into()),
-                    Err(err) => Err(Error::custom(format!("invalid base64: {}", err))),
-                }
-            }
+        match decode_base64(v) {
+            Ok(buf) => Ok(buf.into()),
             Err(err) => Err(Error::custom(format!("invalid base64: {}", err))),
         }
     }
@@ -554,6 +534,7 @@ impl<'a, 'de> Visitor<'de> for MessageVisitorInner<'a> {
             if let Some(field) = desc
                 .get_field_by_json_name(key.as_ref())
                 .or_else(|| desc.get_field_by_name(key.as_ref()))
+                .or_else(|| self.1.get_field_by_alias(&desc, key.as_ref()))
             {
                 if let Some(value) =
                     map.next_value_seed(OptionalFieldDescriptorSeed(&field, self.1))?
@@ -611,6 +592,14 @@ impl<'a, 'de> Visitor<'de> for EnumVisitor<'a> {
     where
         E: Error,
     {
+        if self.0.is_closed() && self.0.get_value(v).is_none() {
+            return Err(Error::custom(format!(
+                "unrecognized number {} for closed enum '{}'",
+                v,
+                self.0.full_name()
+            )));
+        }
+
         Ok(v)
     }
 
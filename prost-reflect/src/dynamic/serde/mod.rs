@@ -2,12 +2,17 @@ mod case;
 mod de;
 mod ser;
 
+use std::{collections::HashMap, error::Error, fmt, sync::Arc};
+
 use serde::{
     de::{DeserializeSeed, Deserializer},
     ser::{Serialize, Serializer},
 };
 
-use crate::{DynamicMessage, MessageDescriptor};
+use super::wkt::{
+    MAX_DURATION_NANOS, MAX_DURATION_SECONDS, MAX_TIMESTAMP_SECONDS, MIN_TIMESTAMP_SECONDS,
+};
+use crate::{DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, ReflectMessage, Value};
 
 /// Options to control serialization of messages.
 ///
@@ -19,17 +24,74 @@ pub struct SerializeOptions {
     use_enum_numbers: bool,
     use_proto_field_name: bool,
     skip_default_fields: bool,
+    preserve_proto_order: bool,
+    bytes_encoding: BytesEncoding,
+    type_url_prefix: Option<String>,
+    reject_non_finite_floats: bool,
+}
+
+/// The encoding used for `bytes` values, when serializing messages.
+///
+/// Used by [`SerializeOptions::bytes_encoding()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub enum BytesEncoding {
+    /// Standard base64 with padding, as required by the JSON mapping.
+    #[default]
+    StandardBase64,
+    /// URL-safe base64 without padding.
+    UrlSafeBase64,
+    /// Lowercase hexadecimal.
+    Hex,
 }
 
 /// Options to control deserialization of messages.
 ///
 /// Used by [`DynamicMessage::deserialize_with_options()`].
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 pub struct DeserializeOptions {
     deny_unknown_fields: bool,
+    any_resolver: Option<Arc<AnyResolver>>,
+    field_aliases: Option<Arc<FieldAliases>>,
 }
 
+type AnyResolver = dyn Fn(&str) -> Option<MessageDescriptor> + Send + Sync;
+
+type FieldAliases = HashMap<MessageDescriptor, HashMap<String, FieldDescriptor>>;
+
+/// An error returned by [`DeserializeOptions::field_aliases()`] when an alias conflicts with the name of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct FieldAliasError {
+    alias: String,
+    message_name: String,
+}
+
+impl FieldAliasError {
+    /// Gets the alias which caused the conflict.
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    /// Gets the full name of the message the alias was defined for.
+    pub fn message_name(&self) -> &str {
+        &self.message_name
+    }
+}
+
+impl fmt::Display for FieldAliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "alias '{}' conflicts with an existing field name of message '{}'",
+            self.alias, self.message_name
+        )
+    }
+}
+
+impl Error for FieldAliasError {}
+
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 impl Serialize for DynamicMessage {
     /// Serialize this message into `serializer` using the [canonical JSON encoding](https://developers.google.com/protocol-buffers/docs/proto3#json).
@@ -117,8 +179,53 @@ impl DynamicMessage {
         ser::serialize_message(self, serializer, options)
     }
 
+    /// Serializes this message to a compact, single-line JSON string, using the default [`SerializeOptions`].
+    ///
+    /// Returns an error if the message cannot be represented in JSON, for example if it contains a
+    /// `google.protobuf.Timestamp` message which is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("foo", Value::I32(150));
+    /// assert_eq!(dynamic_message.to_json_string().unwrap(), r#"{"foo":150}"#);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Serializes this message to an indented, multi-line JSON string, using the default [`SerializeOptions`].
+    ///
+    /// See [`to_json_string`](DynamicMessage::to_json_string) for more details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("foo", Value::I32(150));
+    /// assert_eq!(dynamic_message.to_json_string_pretty().unwrap(), "{\n  \"foo\": 150\n}");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json_string_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
     /// Deserialize an instance of the message type described by `desc` from `deserializer`.
     ///
+    /// The message is built directly from the events produced by `deserializer`, without first collecting the input
+    /// into an intermediate value such as a `serde_json::Value`, so streaming deserializers like
+    /// [`serde_json::Deserializer::from_reader`] can be used for large inputs. The only exception is
+    /// `google.protobuf.Any`, where any fields which appear before the `@type` field must be buffered until the type is
+    /// known.
+    ///
     /// # Examples
     ///
     /// ```
@@ -172,6 +279,120 @@ impl DynamicMessage {
     {
         de::deserialize_message(&desc, deserializer, options)
     }
+
+    /// Decodes an instance of the message type specified by the [`MessageDescriptor`] from a base64-encoded string.
+    ///
+    /// Both the standard and URL-safe base64 alphabets are accepted, with or without padding. Leading and trailing
+    /// whitespace is ignored. This method is only available with the `serde` feature, which provides the base64
+    /// decoder; see [`decode_hex()`][DynamicMessage::decode_hex] for an equivalent which is always available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode_base64(message_descriptor.clone(), "CJYB").unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    /// assert!(DynamicMessage::decode_base64(message_descriptor, "CJYB!").is_err());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn decode_base64(desc: MessageDescriptor, s: &str) -> Result<Self, prost::DecodeError> {
+        let bytes = decode_base64(s.trim())
+            .map_err(|err| prost::DecodeError::new(format!("invalid base64: {}", err)))?;
+        DynamicMessage::decode(desc, bytes.as_slice())
+    }
+
+    /// Converts a `google.protobuf.Struct`, `google.protobuf.Value` or `google.protobuf.ListValue` message to the
+    /// equivalent [`serde_json::Value`].
+    ///
+    /// Returns `None` if this message is not one of these three types, or if it contains a number which cannot be
+    /// represented in JSON, such as `NaN`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::DynamicMessage;
+    /// let json = serde_json::json!({ "name": "foo", "tags": [1.0, true, null] });
+    /// let message = DynamicMessage::struct_from_json(json.clone()).unwrap();
+    /// assert_eq!(message.to_json_for_struct(), Some(json));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json_for_struct(&self) -> Option<serde_json::Value> {
+        match self.desc.full_name() {
+            "google.protobuf.Struct" | "google.protobuf.Value" | "google.protobuf.ListValue" => {
+                serde_json::to_value(self).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Creates a new `google.protobuf.Struct` message from a JSON object.
+    ///
+    /// Returns an error if `json` is not an object.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn struct_from_json(json: serde_json::Value) -> Result<Self, serde_json::Error> {
+        DynamicMessage::deserialize(prost_types::Struct::default().descriptor(), json)
+    }
+
+    /// Creates a new `google.protobuf.Value` message from any JSON value.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn value_from_json(json: serde_json::Value) -> Result<Self, serde_json::Error> {
+        DynamicMessage::deserialize(prost_types::Value::default().descriptor(), json)
+    }
+
+    /// Creates a new `google.protobuf.ListValue` message from a JSON array.
+    ///
+    /// Returns an error if `json` is not an array.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn list_value_from_json(json: serde_json::Value) -> Result<Self, serde_json::Error> {
+        DynamicMessage::deserialize(prost_types::ListValue::default().descriptor(), json)
+    }
+}
+
+impl Value {
+    /// Converts this value to JSON, using the JSON mapping for values of the given [`Kind`].
+    ///
+    /// This gives the same result as serializing a field of type `kind` holding this value. In particular,
+    /// well-known types use their special JSON representation, so a `google.protobuf.StringValue` message
+    /// becomes a JSON string, and `google.protobuf.Empty` becomes an empty object.
+    ///
+    /// Returns an error if the value cannot be represented in JSON, for example if it is a
+    /// `google.protobuf.Timestamp` message which is out of range.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if the value is not [valid](Value::is_valid) for `kind`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Kind, Value};
+    /// let message_descriptor = DescriptorPool::global().get_message_by_name("google.protobuf.StringValue").unwrap();
+    /// let mut message = DynamicMessage::new(message_descriptor.clone());
+    /// message.set_field_by_name("value", Value::String("hello".to_owned()));
+    ///
+    /// let json = Value::Message(message).to_json(&Kind::Message(message_descriptor)).unwrap();
+    /// assert_eq!(json, serde_json::json!("hello"));
+    /// assert_eq!(Value::I64(5).to_json(&Kind::Int64).unwrap(), serde_json::json!("5"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json(&self, kind: &Kind) -> Result<serde_json::Value, serde_json::Error> {
+        self.to_json_with_options(kind, &SerializeOptions::new())
+    }
+
+    /// Converts this value to JSON, using the JSON mapping for values of the given [`Kind`] and the encoding
+    /// specified by `options`.
+    ///
+    /// See [`to_json`](Value::to_json) for more details.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json_with_options(
+        &self,
+        kind: &Kind,
+        options: &SerializeOptions,
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        ser::serialize_value(self, kind, serde_json::value::Serializer, options)
+    }
 }
 
 impl DeserializeOptions {
@@ -180,6 +401,8 @@ impl DeserializeOptions {
     pub const fn new() -> Self {
         DeserializeOptions {
             deny_unknown_fields: true,
+            any_resolver: None,
+            field_aliases: None,
         }
     }
 
@@ -190,6 +413,121 @@ impl DeserializeOptions {
         self.deny_unknown_fields = yes;
         self
     }
+
+    /// Sets the function used to find the message type of `google.protobuf.Any` values from their `@type` URL.
+    ///
+    /// The function is passed the full type URL, and should return the descriptor for the message type it refers
+    /// to, or `None` if it is unknown. This allows deserializing `Any` values whose types are defined in a
+    /// different [`DescriptorPool`][crate::DescriptorPool] than the message being deserialized, or which use a
+    /// custom type URL prefix.
+    ///
+    /// By default, the type is looked up in the [parent pool][MessageDescriptor::parent_pool] of the `Any` message,
+    /// and only the `type.googleapis.com/` and `type.googleprod.com/` prefixes are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DeserializeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("google.protobuf.Any").unwrap();
+    /// let other_pool = pool.clone();
+    /// let options = DeserializeOptions::new().any_resolver(move |type_url| {
+    ///     let name = type_url.strip_prefix("example.com/")?;
+    ///     other_pool.get_message_by_name(name)
+    /// });
+    ///
+    /// let json = r#"{ "@type": "example.com/package.MyMessage", "foo": 150 }"#;
+    /// let mut deserializer = serde_json::de::Deserializer::from_str(json);
+    /// let any = DynamicMessage::deserialize_with_options(message_descriptor, &mut deserializer, &options).unwrap();
+    /// deserializer.end().unwrap();
+    ///
+    /// assert_eq!(any.get_field_by_name("type_url").unwrap().as_str(), Some("example.com/package.MyMessage"));
+    /// ```
+    pub fn any_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<MessageDescriptor> + Send + Sync + 'static,
+    {
+        self.any_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Adds alternative names which may be used for fields in JSON input.
+    ///
+    /// Each item of `aliases` is a pair of an alias and the field it refers to. When deserializing the message
+    /// containing the field, a JSON key matching the alias is treated as if it were the name of the field. This is
+    /// useful for accepting input that uses the old name of a field which has since been renamed.
+    ///
+    /// Aliases of different messages are independent, but the aliases of a message must not match the
+    /// [name][FieldDescriptor::name] or [JSON name][FieldDescriptor::json_name] of any of its fields, or an alias
+    /// of a different field. In that case, an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DeserializeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let foo = message_descriptor.get_field_by_name("foo").unwrap();
+    /// let options = DeserializeOptions::new().field_aliases([("oldFoo", foo.clone())]).unwrap();
+    ///
+    /// let mut deserializer = serde_json::de::Deserializer::from_str(r#"{ "oldFoo": 150 }"#);
+    /// let message = DynamicMessage::deserialize_with_options(message_descriptor, &mut deserializer, &options).unwrap();
+    /// deserializer.end().unwrap();
+    /// assert_eq!(message.get_field(&foo).as_ref(), &Value::I32(150));
+    ///
+    /// let err = DeserializeOptions::new().field_aliases([("bar", foo)]).unwrap_err();
+    /// assert_eq!(err.to_string(), "alias 'bar' conflicts with an existing field name of message 'package.MyMessage'");
+    /// ```
+    #[allow(clippy::mutable_key_type)]
+    pub fn field_aliases<I, S>(mut self, aliases: I) -> Result<Self, FieldAliasError>
+    where
+        I: IntoIterator<Item = (S, FieldDescriptor)>,
+        S: Into<String>,
+    {
+        let field_aliases = Arc::make_mut(self.field_aliases.get_or_insert_with(Default::default));
+        for (alias, field) in aliases {
+            let alias = alias.into();
+            let message = field.parent_message();
+            let message_aliases = field_aliases.entry(message.clone()).or_default();
+
+            let conflicts = message.get_field_by_name(&alias).is_some()
+                || message.get_field_by_json_name(&alias).is_some()
+                || message_aliases
+                    .get(&alias)
+                    .is_some_and(|existing| *existing != field);
+            if conflicts {
+                return Err(FieldAliasError {
+                    alias,
+                    message_name: message.full_name().to_owned(),
+                });
+            }
+
+            message_aliases.insert(alias, field);
+        }
+        Ok(self)
+    }
+
+    fn get_field_by_alias(
+        &self,
+        message: &MessageDescriptor,
+        alias: &str,
+    ) -> Option<FieldDescriptor> {
+        self.field_aliases
+            .as_ref()?
+            .get(message)?
+            .get(alias)
+            .cloned()
+    }
+}
+
+impl fmt::Debug for DeserializeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeserializeOptions")
+            .field("deny_unknown_fields", &self.deny_unknown_fields)
+            .field("any_resolver", &self.any_resolver.is_some())
+            .field("field_aliases", &self.field_aliases)
+            .finish()
+    }
 }
 
 impl Default for DeserializeOptions {
@@ -207,14 +545,20 @@ impl SerializeOptions {
             use_enum_numbers: false,
             use_proto_field_name: false,
             skip_default_fields: true,
+            preserve_proto_order: false,
+            bytes_encoding: BytesEncoding::StandardBase64,
+            type_url_prefix: None,
+            reject_non_finite_floats: false,
         }
     }
 
     /// Whether to encode 64-bit integral types as strings.
     ///
     /// The spec requires encoding 64-bit integers as strings, to prevent loss of precision in JSON
-    /// when the value cannot be represented exactly by a double. If this option is disabled, all
-    /// numbers will be serialized as their corresponding serde types instead.
+    /// when the value cannot be represented exactly by a double. If this option is disabled,
+    /// 64-bit integers will be serialized as numbers when their magnitude is at most 2^53,
+    /// and as strings otherwise, so that no precision is lost by consumers which parse JSON numbers
+    /// as doubles.
     ///
     /// The default value is `true`.
     pub const fn stringify_64_bit_integers(mut self, yes: bool) -> Self {
@@ -251,6 +595,107 @@ impl SerializeOptions {
         self.skip_default_fields = yes;
         self
     }
+
+    /// Whether to serialize fields in the order they are declared in the `.proto` file.
+    ///
+    /// If `false`, fields are serialized in an unspecified order, which is usually ascending order of field number.
+    /// If `true`, fields are sorted by their [index][crate::FieldDescriptor::index] within the message, and any
+    /// extension fields are serialized afterwards in order of field number.
+    ///
+    /// The default value is `false`.
+    pub const fn preserve_proto_order(mut self, yes: bool) -> Self {
+        self.preserve_proto_order = yes;
+        self
+    }
+
+    /// Whether to return an error when serializing a non-finite `float` or `double` value.
+    ///
+    /// The spec requires encoding infinite and `NaN` values as the strings `"Infinity"`, `"-Infinity"` and
+    /// `"NaN"`. If this option is enabled, serialization fails instead, including for the values of
+    /// `google.protobuf.FloatValue` and `google.protobuf.DoubleValue`.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, SerializeOptions, Value};
+    /// let message_descriptor = DescriptorPool::global().get_message_by_name("google.protobuf.DoubleValue").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("value", Value::F64(f64::NAN));
+    ///
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// dynamic_message.serialize_with_options(&mut serializer, &SerializeOptions::new()).unwrap();
+    /// assert_eq!(serializer.into_inner(), br#""NaN""#);
+    ///
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// let options = SerializeOptions::new().reject_non_finite_floats(true);
+    /// assert!(dynamic_message.serialize_with_options(&mut serializer, &options).is_err());
+    /// ```
+    pub const fn reject_non_finite_floats(mut self, yes: bool) -> Self {
+        self.reject_non_finite_floats = yes;
+        self
+    }
+
+    /// How to encode `bytes` values, including the value of `google.protobuf.BytesValue`.
+    ///
+    /// The spec requires standard base64 with padding. Deserialization always accepts both standard and URL-safe
+    /// base64, with or without padding, but does not accept hex-encoded values.
+    ///
+    /// The default value is [`BytesEncoding::StandardBase64`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, SerializeOptions, BytesEncoding, Value};
+    /// let message_descriptor = DescriptorPool::global().get_message_by_name("google.protobuf.BytesValue").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("value", Value::Bytes(b"\xfb\xff".as_ref().into()));
+    ///
+    /// let to_json = |options: &SerializeOptions| {
+    ///     let mut serializer = serde_json::Serializer::new(vec![]);
+    ///     dynamic_message.serialize_with_options(&mut serializer, options).unwrap();
+    ///     String::from_utf8(serializer.into_inner()).unwrap()
+    /// };
+    ///
+    /// assert_eq!(to_json(&SerializeOptions::new()), r#""+/8=""#);
+    /// assert_eq!(to_json(&SerializeOptions::new().bytes_encoding(BytesEncoding::UrlSafeBase64)), r#""-_8""#);
+    /// assert_eq!(to_json(&SerializeOptions::new().bytes_encoding(BytesEncoding::Hex)), r#""fbff""#);
+    /// ```
+    pub const fn bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = encoding;
+        self
+    }
+
+    /// Sets an additional type URL prefix which is recognized when serializing `google.protobuf.Any` messages.
+    ///
+    /// An `Any` message is serialized by looking up the type named by its type URL, so serialization fails if the
+    /// type URL does not have a recognized prefix. By default, only the `type.googleapis.com/` and
+    /// `type.googleprod.com/` prefixes are recognized. A trailing `/` may be omitted from `prefix`, as in
+    /// [`DynamicMessage::pack_into_any()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, SerializeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("foo", Value::I32(150));
+    /// let any = dynamic_message.pack_into_any(Some("example.com"));
+    ///
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// assert!(any.serialize_with_options(&mut serializer, &SerializeOptions::new()).is_err());
+    ///
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// let options = SerializeOptions::new().type_url_prefix("example.com");
+    /// any.serialize_with_options(&mut serializer, &options).unwrap();
+    /// assert_eq!(serializer.into_inner(), br#"{"@type":"example.com/package.MyMessage","foo":150}"#);
+    /// ```
+    pub fn type_url_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.type_url_prefix = Some(prefix.into());
+        self
+    }
 }
 
 impl Default for SerializeOptions {
@@ -259,12 +704,6 @@ impl Default for SerializeOptions {
     }
 }
 
-const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
-const MAX_DURATION_NANOS: u32 = 999_999_999;
-
-const MIN_TIMESTAMP_SECONDS: i64 = -62135596800;
-const MAX_TIMESTAMP_SECONDS: i64 = 253402300799;
-
 fn is_well_known_type(full_name: &str) -> bool {
     matches!(
         full_name,
@@ -288,6 +727,29 @@ fn is_well_known_type(full_name: &str) -> bool {
     )
 }
 
+/// Decodes standard or URL-safe base64, with or without padding.
+fn decode_base64(s: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    use base64::{
+        alphabet,
+        engine::DecodePaddingMode,
+        engine::{GeneralPurpose, GeneralPurposeConfig},
+        DecodeError, Engine,
+    };
+
+    const CONFIG: GeneralPurposeConfig = GeneralPurposeConfig::new()
+        .with_decode_allow_trailing_bits(true)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, CONFIG);
+    const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG);
+
+    match STANDARD.decode(s) {
+        Err(DecodeError::InvalidByte(_, b'-')) | Err(DecodeError::InvalidByte(_, b'_')) => {
+            URL_SAFE.decode(s)
+        }
+        result => result,
+    }
+}
+
 fn check_duration(duration: &prost_types::Duration) -> Result<(), &'static str> {
     if duration.seconds.unsigned_abs() > MAX_DURATION_SECONDS
         || duration.nanos.unsigned_abs() > MAX_DURATION_NANOS
@@ -1,12 +1,21 @@
 mod wkt;
 
-use base64::{display::Base64Display, prelude::BASE64_STANDARD};
+use std::fmt;
 
-use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use base64::{
+    display::Base64Display,
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+};
+
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use crate::{
     descriptor::Kind,
-    dynamic::{fields::ValueAndDescriptor, serde::SerializeOptions, DynamicMessage, MapKey, Value},
+    dynamic::{
+        fields::ValueAndDescriptor,
+        serde::{BytesEncoding, SerializeOptions},
+        DynamicMessage, MapKey, Value,
+    },
     ReflectMessage,
 };
 
@@ -30,6 +39,22 @@ where
     .serialize(serializer)
 }
 
+pub(super) fn serialize_value<S>(
+    value: &Value,
+    kind: &Kind,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SerializeWrapper {
+        value: &ValueAndKind { value, kind },
+        options,
+    }
+    .serialize(serializer)
+}
+
 impl<'a> Serialize for SerializeWrapper<'a, DynamicMessage> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -55,66 +80,76 @@ where
     S: SerializeMap,
 {
     if options.skip_default_fields {
-        for field in value.fields.iter(&value.desc) {
-            let (name, value, ref kind) = match field {
-                ValueAndDescriptor::Field(value, ref field_desc) => {
-                    let name = if options.use_proto_field_name {
-                        field_desc.name()
-                    } else {
-                        field_desc.json_name()
-                    };
-                    (name, value, field_desc.kind())
-                }
-                ValueAndDescriptor::Extension(value, ref extension_desc) => {
-                    (extension_desc.json_name(), value, extension_desc.kind())
-                }
-                ValueAndDescriptor::Unknown(_) => continue,
-            };
+        serialize_fields(map, value.fields.iter(&value.desc), options)
+    } else {
+        serialize_fields(map, value.fields.iter_include_default(&value.desc), options)
+    }
+}
 
-            map.serialize_entry(
-                name,
-                &SerializeWrapper {
-                    value: &ValueAndKind {
-                        value: value.as_ref(),
-                        kind,
-                    },
-                    options,
-                },
-            )?;
+fn serialize_fields<'a, S>(
+    map: &mut S,
+    fields: impl Iterator<Item = ValueAndDescriptor<'a>>,
+    options: &SerializeOptions,
+) -> Result<(), S::Error>
+where
+    S: SerializeMap,
+{
+    if options.preserve_proto_order {
+        let mut fields: Vec<_> = fields.collect();
+        fields.sort_by_key(|field| match field {
+            ValueAndDescriptor::Field(_, field_desc) => (0, field_desc.index()),
+            ValueAndDescriptor::Extension(_, extension_desc) => {
+                (1, extension_desc.number() as usize)
+            }
+            ValueAndDescriptor::Unknown(_) => (2, 0),
+        });
+        for field in fields {
+            serialize_field(map, field, options)?;
         }
     } else {
-        for field in value.fields.iter_include_default(&value.desc) {
-            let (name, value, ref kind) = match field {
-                ValueAndDescriptor::Field(value, ref field_desc) => {
-                    let name = if options.use_proto_field_name {
-                        field_desc.name()
-                    } else {
-                        field_desc.json_name()
-                    };
-                    (name, value, field_desc.kind())
-                }
-                ValueAndDescriptor::Extension(value, ref extension_desc) => {
-                    (extension_desc.json_name(), value, extension_desc.kind())
-                }
-                ValueAndDescriptor::Unknown(_) => continue,
-            };
-
-            map.serialize_entry(
-                name,
-                &SerializeWrapper {
-                    value: &ValueAndKind {
-                        value: value.as_ref(),
-                        kind,
-                    },
-                    options,
-                },
-            )?;
+        for field in fields {
+            serialize_field(map, field, options)?;
         }
     }
 
     Ok(())
 }
 
+fn serialize_field<S>(
+    map: &mut S,
+    field: ValueAndDescriptor,
+    options: &SerializeOptions,
+) -> Result<(), S::Error>
+where
+    S: SerializeMap,
+{
+    let (name, value, ref kind) = match field {
+        ValueAndDescriptor::Field(value, ref field_desc) => {
+            let name = if options.use_proto_field_name {
+                field_desc.name()
+            } else {
+                field_desc.json_name()
+            };
+            (name, value, field_desc.kind())
+        }
+        ValueAndDescriptor::Extension(value, ref extension_desc) => {
+            (extension_desc.json_name(), value, extension_desc.kind())
+        }
+        ValueAndDescriptor::Unknown(_) => return Ok(()),
+    };
+
+    map.serialize_entry(
+        name,
+        &SerializeWrapper {
+            value: &ValueAndKind {
+                value: value.as_ref(),
+                kind,
+            },
+            options,
+        },
+    )
+}
+
 struct ValueAndKind<'a> {
     value: &'a Value,
     kind: &'a Kind,
@@ -128,49 +163,13 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
         match self.value.value {
             Value::Bool(value) => serializer.serialize_bool(*value),
             Value::I32(value) => serializer.serialize_i32(*value),
-            Value::I64(value) => {
-                if self.options.stringify_64_bit_integers {
-                    serializer.collect_str(value)
-                } else {
-                    serializer.serialize_i64(*value)
-                }
-            }
+            Value::I64(value) => serialize_i64(*value, serializer, self.options),
             Value::U32(value) => serializer.serialize_u32(*value),
-            Value::U64(value) => {
-                if self.options.stringify_64_bit_integers {
-                    serializer.collect_str(value)
-                } else {
-                    serializer.serialize_u64(*value)
-                }
-            }
-            Value::F32(value) => {
-                if value.is_finite() {
-                    serializer.serialize_f32(*value)
-                } else if *value == f32::INFINITY {
-                    serializer.serialize_str("Infinity")
-                } else if *value == f32::NEG_INFINITY {
-                    serializer.serialize_str("-Infinity")
-                } else {
-                    debug_assert!(value.is_nan());
-                    serializer.serialize_str("NaN")
-                }
-            }
-            Value::F64(value) => {
-                if value.is_finite() {
-                    serializer.serialize_f64(*value)
-                } else if *value == f64::INFINITY {
-                    serializer.serialize_str("Infinity")
-                } else if *value == f64::NEG_INFINITY {
-                    serializer.serialize_str("-Infinity")
-                } else {
-                    debug_assert!(value.is_nan());
-                    serializer.serialize_str("NaN")
-                }
-            }
+            Value::U64(value) => serialize_u64(*value, serializer, self.options),
+            Value::F32(value) => serialize_f32(*value, serializer, self.options),
+            Value::F64(value) => serialize_f64(*value, serializer, self.options),
             Value::String(value) => serializer.serialize_str(value),
-            Value::Bytes(value) => {
-                serializer.collect_str(&Base64Display::new(value, &BASE64_STANDARD))
-            }
+            Value::Bytes(value) => serialize_bytes_value(value, serializer, self.options),
             Value::EnumNumber(number) => {
                 let enum_ty = match self.value.kind {
                     Kind::Enum(enum_ty) => enum_ty,
@@ -252,3 +251,123 @@ impl<'a> Serialize for SerializeWrapper<'a, MapKey> {
         }
     }
 }
+
+fn serialize_f32<S>(
+    value: f32,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.is_finite() {
+        serializer.serialize_f32(value)
+    } else {
+        serialize_non_finite(value.into(), serializer, options)
+    }
+}
+
+fn serialize_f64<S>(
+    value: f64,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.is_finite() {
+        serializer.serialize_f64(value)
+    } else {
+        serialize_non_finite(value, serializer, options)
+    }
+}
+
+fn serialize_non_finite<S>(
+    value: f64,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let name = if value == f64::INFINITY {
+        "Infinity"
+    } else if value == f64::NEG_INFINITY {
+        "-Infinity"
+    } else {
+        debug_assert!(value.is_nan());
+        "NaN"
+    };
+
+    if options.reject_non_finite_floats {
+        Err(Error::custom(format!(
+            "cannot serialize non-finite floating-point value '{}'",
+            name
+        )))
+    } else {
+        serializer.serialize_str(name)
+    }
+}
+
+/// The largest magnitude of an integer which can be represented exactly by a double.
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+fn serialize_i64<S>(
+    value: i64,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if options.stringify_64_bit_integers || value.unsigned_abs() > MAX_EXACT_INTEGER {
+        serializer.collect_str(&value)
+    } else {
+        serializer.serialize_i64(value)
+    }
+}
+
+fn serialize_u64<S>(
+    value: u64,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if options.stringify_64_bit_integers || value > MAX_EXACT_INTEGER {
+        serializer.collect_str(&value)
+    } else {
+        serializer.serialize_u64(value)
+    }
+}
+
+fn serialize_bytes_value<S>(
+    value: &[u8],
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match options.bytes_encoding {
+        BytesEncoding::StandardBase64 => {
+            serializer.collect_str(&Base64Display::new(value, &BASE64_STANDARD))
+        }
+        BytesEncoding::UrlSafeBase64 => {
+            serializer.collect_str(&Base64Display::new(value, &BASE64_URL_SAFE_NO_PAD))
+        }
+        BytesEncoding::Hex => serializer.collect_str(&HexDisplay(value)),
+    }
+}
+
+struct HexDisplay<'a>(&'a [u8]);
+
+impl fmt::Display for HexDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,3 @@
-use base64::{display::Base64Display, prelude::BASE64_STANDARD};
 use prost::{DecodeError, Message};
 use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
 
@@ -8,12 +7,15 @@ use crate::{
             case::snake_case_to_camel_case, check_duration, check_timestamp, is_well_known_type,
             SerializeOptions,
         },
-        DynamicMessage,
+        strip_type_url_prefix, DynamicMessage,
     },
     ReflectMessage,
 };
 
-use super::{serialize_dynamic_message_fields, SerializeWrapper};
+use super::{
+    serialize_bytes_value, serialize_dynamic_message_fields, serialize_f32, serialize_f64,
+    serialize_i64, serialize_u64, SerializeWrapper,
+};
 
 #[allow(type_alias_bounds)]
 type WellKnownTypeSerializer<S: Serializer> =
@@ -58,7 +60,9 @@ where
 {
     let raw: prost_types::Any = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if let Some(message_name) = raw.type_url.strip_prefix("type.googleapis.com/") {
+    if let Some(message_name) =
+        strip_type_url_prefix(&raw.type_url, options.type_url_prefix.as_deref())
+    {
         let message_desc = msg
             .descriptor()
             .parent_pool()
@@ -128,27 +132,27 @@ where
 fn serialize_float<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: f32 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.serialize_f32(raw)
+    serialize_f32(raw, serializer, options)
 }
 
 fn serialize_double<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: f64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.serialize_f64(raw)
+    serialize_f64(raw, serializer, options)
 }
 
 fn serialize_int32<S>(
@@ -174,11 +178,7 @@ where
 {
     let raw: i64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if options.stringify_64_bit_integers {
-        serializer.collect_str(&raw)
-    } else {
-        serializer.serialize_i64(raw)
-    }
+    serialize_i64(raw, serializer, options)
 }
 
 fn serialize_uint32<S>(
@@ -204,11 +204,7 @@ where
 {
     let raw: u64 = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if options.stringify_64_bit_integers {
-        serializer.collect_str(&raw)
-    } else {
-        serializer.serialize_u64(raw)
-    }
+    serialize_u64(raw, serializer, options)
 }
 
 fn serialize_bool<S>(
@@ -240,14 +236,14 @@ where
 fn serialize_bytes<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: Vec<u8> = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.collect_str(&Base64Display::new(&raw, &BASE64_STANDARD))
+    serialize_bytes_value(&raw, serializer, options)
 }
 
 fn serialize_field_mask<S>(
@@ -1,12 +1,14 @@
-use std::fmt::{self, Write};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+};
 
 use prost::Message;
 
 use crate::{
-    descriptor::{GOOGLE_APIS_DOMAIN, GOOGLE_PROD_DOMAIN},
     dynamic::{
         fields::ValueAndDescriptor,
-        fmt_string,
+        fmt_string, sorted_map_entries, strip_type_url_prefix,
         text_format::FormatOptions,
         unknown::{UnknownField, UnknownFieldSet, UnknownFieldValue},
     },
@@ -31,15 +33,64 @@ where
         }
     }
 
+    #[cfg(feature = "text-format")]
+    pub fn fmt_header_directives(&mut self, message: &DynamicMessage) -> fmt::Result {
+        if self.options.header_directives {
+            writeln!(
+                self.f,
+                "# proto-file: {}",
+                message.desc.parent_file().name()
+            )?;
+            writeln!(self.f, "# proto-message: {}", message.desc.full_name())?;
+        }
+        Ok(())
+    }
+
     pub fn fmt_message(&mut self, message: &DynamicMessage) -> fmt::Result {
         if self.options.expand_any {
-            if let Some((type_url, body)) = as_any(message) {
+            if let Some((type_url, body)) = as_any(message, self.options.type_url_prefix.as_deref())
+            {
                 self.f.write_char('[')?;
                 self.f.write_str(&type_url)?;
                 self.f.write_str("]")?;
                 self.fmt_field_value(&Value::Message(body), None)?;
                 return Ok(());
             }
+
+            if self.options.mark_unresolved_any {
+                if let Some(type_url) = unresolved_any_type_url(message) {
+                    write!(self.f, "[UNRESOLVED {}]", type_url)?;
+                    self.fmt_padding()?;
+                    self.f.write_str("{}")?;
+                    return Ok(());
+                }
+            }
+        }
+
+        if self.options.buf_compatible {
+            // Like protobuf-go, fields are printed in declaration order, followed by extensions.
+            let fields = message
+                .desc
+                .fields()
+                .filter(|field_desc| message.fields.has(field_desc))
+                .map(|field_desc| {
+                    ValueAndDescriptor::Field(message.fields.get(&field_desc), field_desc)
+                });
+            let extensions =
+                message
+                    .fields
+                    .iter_extensions(&message.desc)
+                    .map(|(extension_desc, value)| {
+                        ValueAndDescriptor::Extension(Cow::Borrowed(value), extension_desc)
+                    });
+            let skip_unknown_fields = self.options.skip_unknown_fields;
+            let unknown = message.fields.iter(&message.desc).filter(move |f| {
+                !skip_unknown_fields && matches!(f, ValueAndDescriptor::Unknown(..))
+            });
+            return self.fmt_delimited(
+                fields.chain(extensions).chain(unknown),
+                Writer::fmt_message_field,
+            );
         }
 
         let fields = message.fields.iter(&message.desc);
@@ -60,20 +111,8 @@ where
             Value::I64(value) => write!(self.f, "{}", value),
             Value::U32(value) => write!(self.f, "{}", value),
             Value::U64(value) => write!(self.f, "{}", value),
-            Value::F32(value) => {
-                if value.fract() == 0.0 {
-                    write!(self.f, "{:.1}", value)
-                } else {
-                    write!(self.f, "{}", value)
-                }
-            }
-            Value::F64(value) => {
-                if value.fract() == 0.0 {
-                    write!(self.f, "{:.1}", value)
-                } else {
-                    write!(self.f, "{}", value)
-                }
-            }
+            Value::F32(value) => self.fmt_float(*value, value.is_nan(), value.is_infinite()),
+            Value::F64(value) => self.fmt_float(*value, value.is_nan(), value.is_infinite()),
             Value::String(s) => self.fmt_string(s.as_bytes()),
             Value::Bytes(s) => self.fmt_string(s.as_ref()),
             Value::EnumNumber(value) => {
@@ -110,27 +149,81 @@ where
                 let value_kind = kind
                     .and_then(|k| k.as_message())
                     .map(|m| m.map_entry_value_field().kind());
-                self.fmt_list(map.iter(), |this, (key, value)| {
-                    if this.options.pretty {
-                        this.f.write_str("{")?;
-                        this.indent_level += 2;
-                        this.fmt_newline()?;
-                        this.f.write_str("key: ")?;
-                        this.fmt_map_key(key)?;
-                        this.fmt_newline()?;
-                        this.f.write_str("value")?;
-                        this.fmt_field_value(value, value_kind.as_ref())?;
-                        this.indent_level -= 2;
-                        this.fmt_newline()?;
-                        this.f.write_char('}')
-                    } else {
-                        this.f.write_str("{key:")?;
-                        this.fmt_map_key(key)?;
-                        this.f.write_str(",value")?;
-                        this.fmt_field_value(value, value_kind.as_ref())?;
-                        this.f.write_char('}')
-                    }
-                })
+                let fmt_entry = |this: &mut Self, (key, value): (&MapKey, &Value)| {
+                    this.fmt_map_entry(key, value, value_kind.as_ref())
+                };
+
+                if self.options.sort_map_keys {
+                    self.fmt_list(sorted_map_entries(map).into_iter(), fmt_entry)
+                } else {
+                    self.fmt_list(map.iter(), fmt_entry)
+                }
+            }
+        }
+    }
+
+    fn fmt_map_entry(
+        &mut self,
+        key: &MapKey,
+        value: &Value,
+        value_kind: Option<&Kind>,
+    ) -> fmt::Result {
+        if self.options.pretty {
+            self.f.write_str("{")?;
+            self.indent_level += 2;
+            self.fmt_newline()?;
+            self.f.write_str("key: ")?;
+            self.fmt_map_key(key)?;
+            self.fmt_newline()?;
+            self.f.write_str("value")?;
+            self.fmt_field_value(value, value_kind)?;
+            self.indent_level -= 2;
+            self.fmt_newline()?;
+            self.f.write_char('}')
+        } else {
+            self.f.write_str("{key:")?;
+            self.fmt_map_key(key)?;
+            self.f.write_str(",value")?;
+            self.fmt_field_value(value, value_kind)?;
+            self.f.write_char('}')
+        }
+    }
+
+    /// Writes the shortest representation of a float which parses back to the same value. Very large or small
+    /// numbers use an exponent.
+    fn fmt_float<T>(&mut self, value: T, is_nan: bool, is_infinite: bool) -> fmt::Result
+    where
+        T: fmt::Display + fmt::LowerExp + PartialOrd + Default,
+    {
+        if is_nan {
+            return self.f.write_str("nan");
+        } else if is_infinite {
+            return self
+                .f
+                .write_str(if value > T::default() { "inf" } else { "-inf" });
+        }
+
+        let scientific = format!("{:e}", value);
+        let (mantissa, exponent): (&str, i32) = match scientific.rsplit_once('e') {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse().unwrap_or(0)),
+            None => (scientific.as_str(), 0),
+        };
+        if self.options.buf_compatible {
+            // Match the output of Go's `strconv.FormatFloat(value, 'g', -1, bits)`.
+            if value != T::default() && !(-4..6).contains(&exponent) {
+                let sign = if exponent < 0 { '-' } else { '+' };
+                write!(self.f, "{}e{}{:02}", mantissa, sign, exponent.abs())
+            } else {
+                write!(self.f, "{}", value)
+            }
+        } else if value != T::default() && !(-4..16).contains(&exponent) {
+            self.f.write_str(&scientific)
+        } else {
+            let decimal = value.to_string();
+            if decimal.contains('.') {
+                self.f.write_str(&decimal)
+            } else {
+                write!(self.f, "{}.0", decimal)
             }
         }
     }
@@ -149,16 +242,28 @@ where
     fn fmt_message_field(&mut self, field: ValueAndDescriptor) -> fmt::Result {
         match field {
             ValueAndDescriptor::Field(value, desc) => {
-                if desc.is_group() {
-                    write!(self.f, "{}", desc.kind().as_message().unwrap().name())?;
+                let name = if self.options.use_field_numbers {
+                    desc.number().to_string()
+                } else if desc.is_group() {
+                    desc.kind().as_message().unwrap().name().to_owned()
                 } else {
-                    write!(self.f, "{}", desc.name())?;
+                    desc.name().to_owned()
+                };
+                if self.options.redact_debug_fields && desc.is_debug_redacted() {
+                    return self.fmt_redacted_field(&name, desc.number());
                 }
-                self.fmt_field_value(&value, Some(&desc.kind()))
+                self.fmt_named_field_value(&name, desc.number(), &value, &desc.kind())
             }
             ValueAndDescriptor::Extension(value, desc) => {
-                write!(self.f, "[{}]", desc.full_name())?;
-                self.fmt_field_value(&value, Some(&desc.kind()))
+                let name = if self.options.use_field_numbers {
+                    desc.number().to_string()
+                } else {
+                    format!("[{}]", desc.full_name())
+                };
+                if self.options.redact_debug_fields && desc.is_debug_redacted() {
+                    return self.fmt_redacted_field(&name, desc.number());
+                }
+                self.fmt_named_field_value(&name, desc.number(), &value, &desc.kind())
             }
             ValueAndDescriptor::Unknown(values) => {
                 self.fmt_delimited(values.iter(), Writer::fmt_unknown_field)
@@ -166,8 +271,65 @@ where
         }
     }
 
+    fn fmt_named_field_value(
+        &mut self,
+        name: &str,
+        number: u32,
+        value: &Value,
+        kind: &Kind,
+    ) -> fmt::Result {
+        if self.options.buf_compatible || self.options.expand_repeated_fields {
+            // Repeated and map fields are written as one field per element, in the same way as protobuf-go and the
+            // C++ implementation.
+            match value {
+                Value::List(values) => {
+                    return self.fmt_delimited(values.iter(), |this, value| {
+                        this.f.write_str(name)?;
+                        this.fmt_field_value(value, Some(kind))?;
+                        this.fmt_field_number_comment(number)
+                    })
+                }
+                Value::Map(map) => {
+                    let value_kind = kind.as_message().map(|m| m.map_entry_value_field().kind());
+                    let entries = if self.options.buf_compatible || self.options.sort_map_keys {
+                        sorted_map_entries(map)
+                    } else {
+                        map.iter().collect()
+                    };
+                    return self.fmt_delimited(entries.into_iter(), |this, (key, value)| {
+                        this.f.write_str(name)?;
+                        this.f.write_char(':')?;
+                        this.fmt_padding()?;
+                        this.fmt_map_entry(key, value, value_kind.as_ref())?;
+                        this.fmt_field_number_comment(number)
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        self.f.write_str(name)?;
+        self.fmt_field_value(value, Some(kind))?;
+        self.fmt_field_number_comment(number)
+    }
+
+    fn fmt_redacted_field(&mut self, name: &str, number: u32) -> fmt::Result {
+        self.f.write_str(name)?;
+        self.f.write_char(':')?;
+        self.fmt_padding()?;
+        self.f.write_str("[REDACTED]")?;
+        self.fmt_field_number_comment(number)
+    }
+
+    fn fmt_field_number_comment(&mut self, number: u32) -> fmt::Result {
+        if self.options.pretty && self.options.field_number_comments {
+            write!(self.f, "  # {}", number)?;
+        }
+        Ok(())
+    }
+
     fn fmt_field_value(&mut self, value: &Value, kind: Option<&Kind>) -> fmt::Result {
-        if !matches!(value, Value::Message(_)) {
+        if self.options.buf_compatible || !matches!(value, Value::Message(_)) {
             self.f.write_char(':')?;
         }
         self.fmt_padding()?;
@@ -230,7 +392,24 @@ where
     }
 
     fn fmt_string(&mut self, bytes: &[u8]) -> fmt::Result {
-        fmt_string(&mut self.f, bytes)
+        let len = match self.options.max_string_length {
+            Some(max) if bytes.len() > max && self.options.buf_compatible => {
+                utf8_prefix_len(bytes, max)
+            }
+            Some(max) if bytes.len() > max => max,
+            _ => bytes.len(),
+        };
+
+        if self.options.buf_compatible {
+            fmt_string_go(&mut self.f, &bytes[..len])?;
+        } else {
+            fmt_string(&mut self.f, &bytes[..len], self.options.escape_style)?;
+        }
+
+        if len < bytes.len() {
+            write!(self.f, "...({} bytes)", bytes.len())?;
+        }
+        Ok(())
     }
 
     fn fmt_delimited<T>(
@@ -244,6 +423,8 @@ where
         for item in iter {
             if self.options.pretty {
                 self.fmt_newline()?;
+            } else if self.options.space_separated_fields {
+                self.f.write_char(' ')?;
             } else {
                 self.f.write_char(',')?;
             }
@@ -286,16 +467,60 @@ where
     }
 }
 
-fn as_any(message: &DynamicMessage) -> Option<(String, DynamicMessage)> {
+/// Returns the length of the longest prefix of `bytes` which is at most `max` bytes long, and does not end partway
+/// through a UTF-8 character.
+fn utf8_prefix_len(bytes: &[u8], max: usize) -> usize {
+    // A UTF-8 character has at most three continuation bytes.
+    (max.saturating_sub(3)..=max)
+        .rev()
+        .find(|&len| bytes.get(len).map_or(true, |&byte| byte & 0xc0 != 0x80))
+        .unwrap_or(max)
+}
+
+/// Writes a string literal using the same escaping rules as protobuf-go: valid UTF-8 is written as-is, except for
+/// control characters, and invalid bytes are written as hex escapes.
+fn fmt_string_go(f: &mut impl Write, mut bytes: &[u8]) -> fmt::Result {
+    f.write_char('"')?;
+    while !bytes.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(bytes) {
+            Ok(valid) => (valid, &[][..]),
+            Err(err) => {
+                let (valid, rest) = bytes.split_at(err.valid_up_to());
+                let invalid_len = err.error_len().unwrap_or(rest.len());
+                (std::str::from_utf8(valid).unwrap(), &rest[..invalid_len])
+            }
+        };
+
+        for ch in valid.chars() {
+            match ch {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                '\0'..='\x1f' | '\x7f' => write!(f, "\\x{:02x}", ch as u32)?,
+                '\u{80}'..='\u{9f}' => write!(f, "\\u{:04x}", ch as u32)?,
+                _ => f.write_char(ch)?,
+            }
+        }
+        for byte in invalid {
+            write!(f, "\\x{:02x}", byte)?;
+        }
+        bytes = &bytes[valid.len() + invalid.len()..];
+    }
+    f.write_char('"')
+}
+
+fn as_any(
+    message: &DynamicMessage,
+    type_url_prefix: Option<&str>,
+) -> Option<(String, DynamicMessage)> {
     if message.desc.full_name() != "google.protobuf.Any" {
         return None;
     }
 
     let any = message.transcode_to::<prost_types::Any>().ok()?;
-    let message_name = any
-        .type_url
-        .strip_prefix(GOOGLE_APIS_DOMAIN)
-        .or_else(|| any.type_url.strip_prefix(GOOGLE_PROD_DOMAIN))?;
+    let message_name = strip_type_url_prefix(&any.type_url, type_url_prefix)?;
 
     let desc = message
         .desc
@@ -305,6 +530,21 @@ fn as_any(message: &DynamicMessage) -> Option<(String, DynamicMessage)> {
     Some((any.type_url, body))
 }
 
+/// Returns the type URL of an `Any` message which could not be expanded by [`as_any`]. Returns `None` for an empty
+/// `Any`, which is formatted as a normal message.
+fn unresolved_any_type_url(message: &DynamicMessage) -> Option<String> {
+    if message.desc.full_name() != "google.protobuf.Any" {
+        return None;
+    }
+
+    let type_url = message.transcode_to::<prost_types::Any>().ok()?.type_url;
+    if type_url.is_empty() {
+        None
+    } else {
+        Some(type_url)
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "text-format")]
 mod tests {
@@ -4,6 +4,9 @@ mod parse;
 
 #[cfg(feature = "text-format")]
 pub use self::parse::ParseError;
+#[cfg(feature = "text-format")]
+use std::{fmt, io};
+
 #[cfg(feature = "text-format")]
 use crate::{DynamicMessage, MessageDescriptor};
 
@@ -18,6 +21,32 @@ pub struct FormatOptions {
     pretty: bool,
     skip_unknown_fields: bool,
     expand_any: bool,
+    mark_unresolved_any: bool,
+    #[cfg_attr(not(feature = "text-format"), allow(dead_code))]
+    header_directives: bool,
+    sort_map_keys: bool,
+    use_field_numbers: bool,
+    buf_compatible: bool,
+    escape_style: EscapeStyle,
+    max_string_length: Option<usize>,
+    space_separated_fields: bool,
+    type_url_prefix: Option<String>,
+    field_number_comments: bool,
+    redact_debug_fields: bool,
+    expand_repeated_fields: bool,
+}
+
+/// The escape sequence used for non-printable bytes in strings, when printing the protobuf text format.
+///
+/// Used by [`FormatOptions::escape_style()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+pub enum EscapeStyle {
+    /// Three-digit octal escapes, such as `\001`. This matches the output of the C++ protobuf implementation.
+    #[default]
+    Octal,
+    /// Two-digit hexadecimal escapes, such as `\x01`.
+    Hex,
 }
 
 #[cfg(feature = "text-format")]
@@ -37,12 +66,18 @@ impl DynamicMessage {
     #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
     pub fn parse_text_format(desc: MessageDescriptor, input: &str) -> Result<Self, ParseError> {
         let mut message = DynamicMessage::new(desc);
-        message.merge_text_format(input)?;
+        parse::Parser::new(input)
+            .parse_message(&mut message)
+            .map_err(|kind| ParseError::new(kind, input))?;
         Ok(message)
     }
 
     /// Merges the given message encoded using the [text format](https://developers.google.com/protocol-buffers/docs/text-format-spec) into this message.
     ///
+    /// Values for repeated and map fields are added to any existing values. Singular fields are overwritten, and unlike
+    /// [`parse_text_format()`][DynamicMessage::parse_text_format], a singular field may be set more than once, with the
+    /// last value taking precedence.
+    ///
     /// # Examples
     ///
     /// ```
@@ -57,10 +92,42 @@ impl DynamicMessage {
     #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
     pub fn merge_text_format(&mut self, input: &str) -> Result<(), ParseError> {
         parse::Parser::new(input)
+            .allow_overwrite(true)
             .parse_message(self)
             .map_err(|kind| ParseError::new(kind, input))
     }
 
+    /// Sets the value of the field named `name` by parsing `literal` as a value in the
+    /// [text format](https://developers.google.com/protocol-buffers/docs/text-format-spec).
+    ///
+    /// The literal is parsed according to the type of the field, so it may be a number, `true` or `false`, a quoted
+    /// string, an enum value name or number, or a message value such as `{ foo: 1 }`. For repeated fields, a list
+    /// such as `[1, 2]` or a single value may be given, which replaces any existing values.
+    ///
+    /// If `name` does not match a field of this message, or the literal is not a valid value for the field, an error
+    /// is returned and the message is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_from_str("foo", "150").unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    ///
+    /// assert!(dynamic_message.set_field_from_str("foo", "\"150\"").is_err());
+    /// assert!(dynamic_message.set_field_from_str("unknown", "150").is_err());
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn set_field_from_str(&mut self, name: &str, literal: &str) -> Result<(), ParseError> {
+        parse::Parser::new(literal)
+            .parse_field_literal(self, name)
+            .map_err(|kind| ParseError::new(kind, literal))
+    }
+
     /// Formats this dynamic message using the protobuf text format, with default options.
     ///
     /// # Examples
@@ -96,11 +163,101 @@ impl DynamicMessage {
     #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
     pub fn to_text_format_with_options(&self, options: &FormatOptions) -> String {
         let mut result = String::new();
-        format::Writer::new(options.clone(), &mut result)
-            .fmt_message(self)
+        let mut writer = format::Writer::new(options.clone(), &mut result);
+        writer
+            .fmt_header_directives(self)
+            .and_then(|()| writer.fmt_message(self))
             .expect("writing to string cannot fail");
+        if options.buf_compatible && !result.is_empty() {
+            result.push('\n');
+        }
         result
     }
+
+    /// Writes this dynamic message to `writer` using the protobuf text format, with default options.
+    ///
+    /// The output is the same as [`to_text_format()`][DynamicMessage::to_text_format], but is written directly to
+    /// `writer` without first being collected into a [`String`]. The writer receives many small writes, so it
+    /// should usually be buffered, for example using [`BufWriter`][io::BufWriter].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let mut buf = Vec::new();
+    /// dynamic_message.write_text_format(&mut buf).unwrap();
+    /// assert_eq!(buf, b"foo:150,nested{bar:66}");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn write_text_format<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        self.write_text_format_with_options(writer, &FormatOptions::new())
+    }
+
+    /// Writes this dynamic message to `writer` using the protobuf text format, with custom options.
+    ///
+    /// See [`write_text_format()`][DynamicMessage::write_text_format] for more details.
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn write_text_format_with_options<W>(
+        &self,
+        writer: W,
+        options: &FormatOptions,
+    ) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let mut adapter = IoWriter {
+            inner: writer,
+            error: None,
+            written: false,
+        };
+        let mut writer = format::Writer::new(options.clone(), &mut adapter);
+        let result = writer
+            .fmt_header_directives(self)
+            .and_then(|()| writer.fmt_message(self));
+        if result.is_err() {
+            return Err(adapter.error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::Other, "failed to format message")
+            }));
+        }
+
+        if options.buf_compatible && adapter.written {
+            adapter.inner.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Adapts an [`io::Write`] to [`fmt::Write`], saving the underlying error if a write fails.
+#[cfg(feature = "text-format")]
+struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+    written: bool,
+}
+
+#[cfg(feature = "text-format")]
+impl<W> fmt::Write for IoWriter<W>
+where
+    W: io::Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            return Ok(());
+        }
+
+        self.written = true;
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
 }
 
 impl FormatOptions {
@@ -109,6 +266,46 @@ impl FormatOptions {
         FormatOptions::default()
     }
 
+    /// Creates new instance of [`FormatOptions`] which produce the same output as the [`buf`](https://buf.build) CLI.
+    ///
+    /// `buf` formats messages using [protobuf-go](https://pkg.go.dev/google.golang.org/protobuf/encoding/prototext),
+    /// so this preset follows its formatting choices:
+    ///
+    /// * Each field is printed on a new line, nested messages are indented by two spaces and the output ends with a
+    ///   newline.
+    /// * Message fields are separated from their name by a colon, e.g. `nested: {`.
+    /// * Fields are printed in the order they are declared in, followed by extension fields.
+    /// * Each element of a repeated field is printed as a separate field, and map entries are sorted by key.
+    /// * Floats are printed in Go's shortest representation, for example `1e+06`, and strings are not escaped
+    ///   unless they contain control characters or invalid UTF-8.
+    /// * Unknown fields are skipped, and `google.protobuf.Any` messages are expanded.
+    ///
+    /// Note that protobuf-go deliberately varies its whitespace slightly between builds, so the output of `buf` may
+    /// occasionally contain additional spaces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let options = FormatOptions::buf_compatible();
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "foo: 150\nnested: {\n  bar: 66\n}\n");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn buf_compatible() -> Self {
+        FormatOptions {
+            pretty: true,
+            skip_unknown_fields: true,
+            expand_any: true,
+            sort_map_keys: true,
+            buf_compatible: true,
+            ..FormatOptions::default()
+        }
+    }
+
     /// Whether to prettify the format output.
     ///
     /// If set to `true`, each field will be printed on a new line, and nested messages will be indented.
@@ -185,6 +382,280 @@ impl FormatOptions {
         self.expand_any = yes;
         self
     }
+
+    /// Whether to print a marker for `google.protobuf.Any` messages which could not be expanded.
+    ///
+    /// If set to `true`, and [`expand_any`][Self::expand_any] is enabled, an `Any` message whose type URL cannot be
+    /// resolved in the message's descriptor pool, or whose value cannot be decoded, is printed as
+    /// `[UNRESOLVED type_url] {}` instead of with its raw `type_url` and `value` fields. Note that the output cannot
+    /// be parsed using [`DynamicMessage::parse_text_format()`].
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions, bytes::Bytes};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("google.protobuf.Any").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("type_url", Value::String("type.googleapis.com/package.Missing".to_owned()));
+    ///
+    /// let options = FormatOptions::new().mark_unresolved_any(true);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "[UNRESOLVED type.googleapis.com/package.Missing]{}");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn mark_unresolved_any(mut self, yes: bool) -> Self {
+        self.mark_unresolved_any = yes;
+        self
+    }
+
+    /// Whether to begin the output with the `# proto-file:` and `# proto-message:` header directives.
+    ///
+    /// If set to `true`, the output will start with comments naming the file the message type was defined in and the
+    /// full name of the message type. These are recognized by editors with support for the text format:
+    ///
+    /// ```textproto
+    /// # proto-file: doctest.proto
+    /// # proto-message: package.MyMessage
+    /// foo: 150
+    /// ```
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let options = FormatOptions::new().header_directives(true);
+    /// assert_eq!(
+    ///     dynamic_message.to_text_format_with_options(&options),
+    ///     "# proto-file: doctest.proto\n# proto-message: package.MyMessage\nfoo:150",
+    /// );
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn header_directives(mut self, yes: bool) -> Self {
+        self.header_directives = yes;
+        self
+    }
+
+    /// Whether to sort the entries of map fields by their key.
+    ///
+    /// If set to `true`, map entries are printed in ascending order of their keys, giving deterministic output.
+    /// String keys are compared byte-wise, and integer keys are compared numerically. Otherwise, entries are printed
+    /// in an unspecified order.
+    ///
+    /// The default value is `false`.
+    #[cfg(feature = "text-format")]
+    pub fn sort_map_keys(mut self, yes: bool) -> Self {
+        self.sort_map_keys = yes;
+        self
+    }
+
+    /// Whether to identify fields by their number instead of their name.
+    ///
+    /// If set to `true`, fields, extension fields and groups are printed using their field number, in the same way
+    /// as unknown fields. Note that the output cannot be parsed using [`DynamicMessage::parse_text_format()`].
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let options = FormatOptions::new().use_field_numbers(true);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "1:150,3{2:66}");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn use_field_numbers(mut self, yes: bool) -> Self {
+        self.use_field_numbers = yes;
+        self
+    }
+
+    /// How to escape non-printable bytes in string and bytes fields.
+    ///
+    /// Printable ASCII characters, as well as `\t`, `\r`, `\n`, `\\`, `\'` and `\"`, are printed the same way
+    /// regardless of this option. This option has no effect if [`buf_compatible()`][Self::buf_compatible] is used.
+    ///
+    /// The default value is [`EscapeStyle::Octal`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::{EscapeStyle, FormatOptions}};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("google.protobuf.Any").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("value", Value::Bytes(b"a\x00\xff".as_ref().into()));
+    ///
+    /// assert_eq!(dynamic_message.to_text_format(), r#"value:"a\000\377""#);
+    /// let options = FormatOptions::new().escape_style(EscapeStyle::Hex);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), r#"value:"a\x00\xff""#);
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn escape_style(mut self, style: EscapeStyle) -> Self {
+        self.escape_style = style;
+        self
+    }
+
+    /// The maximum number of bytes of a string or bytes value to print.
+    ///
+    /// Longer values are truncated to this length, and followed by a marker containing the full length of the value,
+    /// such as `...(123 bytes)`. This is useful for keeping log output bounded when messages contain large values.
+    /// If [`buf_compatible()`][Self::buf_compatible] is used, values are never truncated partway through a UTF-8
+    /// character, so slightly fewer bytes may be printed. Note that the output cannot be parsed using
+    /// [`DynamicMessage::parse_text_format()`] if any values are truncated.
+    ///
+    /// By default there is no limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("google.protobuf.Any").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("value", Value::Bytes(b"hello world".as_ref().into()));
+    ///
+    /// let options = FormatOptions::new().max_string_length(5);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), r#"value:"hello"...(11 bytes)"#);
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn max_string_length(mut self, len: usize) -> Self {
+        self.max_string_length = Some(len);
+        self
+    }
+
+    /// Whether to separate fields with a space instead of a comma, when not using [`pretty()`][Self::pretty] output.
+    ///
+    /// This matches the single-line output of the C++ protobuf implementation, such as that of
+    /// `ShortDebugString()`. Elements of repeated fields are always separated by commas. This option has no effect if
+    /// [`pretty()`][Self::pretty] is set.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// assert_eq!(dynamic_message.to_text_format(), "foo:150,nested{bar:66}");
+    /// let options = FormatOptions::new().space_separated_fields(true);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "foo:150 nested{bar:66}");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn space_separated_fields(mut self, yes: bool) -> Self {
+        self.space_separated_fields = yes;
+        self
+    }
+
+    /// Sets an additional type URL prefix which is recognized when [expanding](Self::expand_any)
+    /// `google.protobuf.Any` messages.
+    ///
+    /// By default, only `Any` messages whose type URL starts with `type.googleapis.com/` or `type.googleprod.com/`
+    /// are expanded. A trailing `/` may be omitted from `prefix`, as in [`DynamicMessage::pack_into_any()`]. Note
+    /// that [`DynamicMessage::parse_text_format()`] only accepts the standard prefixes, so messages expanded using a
+    /// custom prefix cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let any = dynamic_message.pack_into_any(Some("example.com/"));
+    ///
+    /// let options = FormatOptions::new().type_url_prefix("example.com/");
+    /// assert_eq!(any.to_text_format_with_options(&options), "[example.com/package.MyMessage]{foo:150}");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn type_url_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.type_url_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Whether to write a comment containing the field number at the end of each field, when using
+    /// [`pretty()`][Self::pretty] output.
+    ///
+    /// For message fields, the comment follows the closing brace. The output can still be parsed using
+    /// [`DynamicMessage::parse_text_format()`]. This option has no effect unless [`pretty()`][Self::pretty] is set.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let options = FormatOptions::new().pretty(true).field_number_comments(true);
+    /// assert_eq!(
+    ///     dynamic_message.to_text_format_with_options(&options),
+    ///     "foo: 150  # 1\nnested {\n  bar: 66  # 2\n}  # 3",
+    /// );
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn field_number_comments(mut self, yes: bool) -> Self {
+        self.field_number_comments = yes;
+        self
+    }
+
+    /// Whether to replace the values of fields marked with the `debug_redact` field option with `[REDACTED]`.
+    ///
+    /// This matches the behaviour of `DebugString()` in the C++ protobuf implementation, and helps to prevent
+    /// sensitive values from being written to logs. Output containing redacted fields cannot be parsed using
+    /// [`DynamicMessage::parse_text_format()`]. See also [`FieldDescriptor::is_debug_redacted()`][crate::FieldDescriptor::is_debug_redacted].
+    ///
+    /// The default value is `true`.
+    #[cfg(feature = "text-format")]
+    pub fn redact_debug_fields(mut self, yes: bool) -> Self {
+        self.redact_debug_fields = yes;
+        self
+    }
+
+    /// Whether to write each element of a repeated or map field as a separate field, instead of using list syntax.
+    ///
+    /// By default, the elements are written as a list, such as `paths:["a","b"]`. If this option is set, the field
+    /// name is repeated for each element instead, such as `paths:"a",paths:"b"`, matching the output of the C++
+    /// protobuf implementation. Both forms can be parsed using [`DynamicMessage::parse_text_format()`].
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// let message_descriptor = DescriptorPool::global().get_message_by_name("google.protobuf.FieldMask").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("paths", Value::List(vec![Value::String("a".to_owned()), Value::String("b".to_owned())]));
+    /// assert_eq!(dynamic_message.to_text_format(), r#"paths:["a","b"]"#);
+    ///
+    /// let options = FormatOptions::new().pretty(true).expand_repeated_fields(true);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "paths: \"a\"\npaths: \"b\"");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn expand_repeated_fields(mut self, yes: bool) -> Self {
+        self.expand_repeated_fields = yes;
+        self
+    }
 }
 
 impl Default for FormatOptions {
@@ -193,6 +664,18 @@ impl Default for FormatOptions {
             pretty: false,
             skip_unknown_fields: true,
             expand_any: true,
+            mark_unresolved_any: false,
+            header_directives: false,
+            sort_map_keys: false,
+            use_field_numbers: false,
+            buf_compatible: false,
+            escape_style: EscapeStyle::Octal,
+            max_string_length: None,
+            space_separated_fields: false,
+            type_url_prefix: None,
+            field_number_comments: false,
+            redact_debug_fields: true,
+            expand_repeated_fields: false,
         }
     }
 }
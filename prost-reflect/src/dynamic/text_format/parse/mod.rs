@@ -23,6 +23,7 @@ use crate::{
 pub(in crate::dynamic::text_format) struct Parser<'a> {
     lexer: Lexer<'a, Token<'a>>,
     peek: Option<Result<(Token<'a>, Span), ParseErrorKind>>,
+    allow_overwrite: bool,
 }
 
 enum FieldName {
@@ -36,9 +37,16 @@ impl<'a> Parser<'a> {
         Parser {
             lexer: Token::lexer(input),
             peek: None,
+            allow_overwrite: false,
         }
     }
 
+    /// Allows singular fields to be set more than once, with the last value taking precedence.
+    pub fn allow_overwrite(mut self, yes: bool) -> Self {
+        self.allow_overwrite = yes;
+        self
+    }
+
     pub fn parse_message(&mut self, message: &mut DynamicMessage) -> Result<(), ParseErrorKind> {
         while self.peek()?.is_some() {
             self.parse_field(message)?;
@@ -46,6 +54,34 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    /// Parses the entire input as a value of the field named `field_name`, and sets it on `message`.
+    ///
+    /// The message is left unchanged if an error occurs.
+    pub fn parse_field_literal(
+        &mut self,
+        message: &mut DynamicMessage,
+        field_name: &str,
+    ) -> Result<(), ParseErrorKind> {
+        let field =
+            find_field(&message.desc, field_name).ok_or_else(|| ParseErrorKind::FieldNotFound {
+                field_name: field_name.to_owned(),
+                message_name: message.desc.full_name().to_owned(),
+                span: 0..0,
+            })?;
+
+        let mut parsed = DynamicMessage::new(message.desc.clone());
+        self.parse_field_value(&mut parsed, &field)?;
+        if self.peek()?.is_some() {
+            return self.unexpected_token("end of input");
+        }
+
+        let value = parsed
+            .take_field(&field)
+            .unwrap_or_else(|| Value::default_value_for_field(&field));
+        message.set_field(&field, value);
+        Ok(())
+    }
+
     fn parse_message_value(
         &mut self,
         message: &mut DynamicMessage,
@@ -225,18 +261,20 @@ impl<'a> Parser<'a> {
             let kind = field.kind();
             let (value, span) = self.parse_value(&kind)?;
 
-            if message.fields.has(field) {
-                return Err(ParseErrorKind::FieldAlreadySet {
-                    field_name: field.text_name().to_owned(),
-                    span,
-                });
-            } else if let Some(oneof) = field.containing_oneof() {
-                for oneof_field in oneof.fields() {
-                    if message.has_field(&oneof_field) {
-                        return Err(ParseErrorKind::OneofAlreadySet {
-                            oneof_name: oneof.name().to_owned(),
-                            span,
-                        });
+            if !self.allow_overwrite {
+                if message.fields.has(field) {
+                    return Err(ParseErrorKind::FieldAlreadySet {
+                        field_name: field.text_name().to_owned(),
+                        span,
+                    });
+                } else if let Some(oneof) = field.containing_oneof() {
+                    for oneof_field in oneof.fields() {
+                        if message.has_field(&oneof_field) {
+                            return Err(ParseErrorKind::OneofAlreadySet {
+                                oneof_name: oneof.name().to_owned(),
+                                span,
+                            });
+                        }
                     }
                 }
             }
@@ -291,9 +291,11 @@ mod tests {
     use prost::{
         bytes::Bytes,
         encoding::{DecodeContext, WireType},
+        Message,
     };
 
     use super::{UnknownField, UnknownFieldSet, UnknownFieldValue};
+    use crate::{DescriptorPool, DynamicMessage, Value};
 
     fn assert_roundtrip(expected: &[u8], value: &UnknownField) {
         assert_eq!(expected.len(), value.encoded_len());
@@ -404,4 +406,28 @@ mod tests {
 
         assert_roundtrip(bytes.strip_suffix(buf).unwrap(), &value);
     }
+
+    #[test]
+    fn reparse_unknown_fields() {
+        let desc = DescriptorPool::global()
+            .get_message_by_name("google.protobuf.Int32Value")
+            .unwrap();
+
+        let mut message = DynamicMessage::new(desc);
+        for bytes in [b"\x08\x05".as_ref(), b"\x08\x96\x01", b"\x10\x01"] {
+            let mut buf = bytes;
+            let unknown = UnknownField::decode(&mut buf, DecodeContext::default()).unwrap();
+            message.fields.add_unknown(unknown.number(), unknown);
+        }
+        assert_eq!(message.unknown_fields().count(), 3);
+
+        message.reparse_unknown_fields();
+        assert_eq!(
+            message.get_field_by_name("value").unwrap().as_ref(),
+            &Value::I32(150)
+        );
+        let unknowns: Vec<_> = message.unknown_fields().map(|f| f.number()).collect();
+        assert_eq!(unknowns, [2]);
+        assert_eq!(message.encode_to_vec(), b"\x08\x96\x01\x10\x01");
+    }
 }
@@ -0,0 +1,164 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+
+use once_cell::sync::OnceCell;
+use prost::{
+    bytes::{Buf, Bytes},
+    encoding::{self, DecodeContext},
+    DecodeError,
+};
+
+use crate::{DynamicMessage, FieldDescriptor, MessageDescriptor, Value};
+
+/// A read-only view of an encoded protobuf message, which decodes the value of each field the first time it is
+/// accessed.
+///
+/// Creating a view only scans the encoded bytes to find where each field is located, without decoding any values.
+/// This makes it cheaper than [`DynamicMessage::decode()`] when only a few fields of a large message are needed.
+/// Decoded values are cached, so accessing the same field again does not decode it a second time.
+///
+/// Extension fields and unknown fields are not accessible through a view. Fields are decoded independently, so if
+/// several fields of the same oneof are encoded, all of them are visible through the view. Use
+/// [`to_message()`][Self::to_message] to decode the full message, including its extension and unknown fields.
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_reflect::{DynamicMessageView, DescriptorPool, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let bytes = b"\x08\x96\x01\x1a\x02\x10\x42".as_ref();
+/// let view = DynamicMessageView::new(message_descriptor, bytes.into()).unwrap();
+/// assert_eq!(view.get_field_by_name("foo").unwrap().unwrap().as_ref(), &Value::I32(150));
+/// ```
+pub struct DynamicMessageView {
+    desc: MessageDescriptor,
+    bytes: Bytes,
+    fields: BTreeMap<u32, LazyField>,
+}
+
+struct LazyField {
+    records: Vec<Bytes>,
+    value: OnceCell<Value>,
+}
+
+impl DynamicMessageView {
+    /// Creates a new view of the message type specified by the [`MessageDescriptor`] over the encoded bytes.
+    ///
+    /// Returns an error if the bytes are not a well-formed sequence of protobuf fields. Errors in the values of
+    /// individual fields are not reported until the field is accessed.
+    pub fn new(desc: MessageDescriptor, bytes: Bytes) -> Result<Self, DecodeError> {
+        let mut fields = BTreeMap::<u32, LazyField>::new();
+        let mut buf = bytes.clone();
+        while buf.has_remaining() {
+            let start = bytes.len() - buf.remaining();
+            let (number, wire_type) = encoding::decode_key(&mut buf)?;
+            encoding::skip_field(wire_type, number, &mut buf, DecodeContext::default())?;
+            let end = bytes.len() - buf.remaining();
+
+            if desc.get_field(number).is_some() {
+                fields
+                    .entry(number)
+                    .or_insert_with(|| LazyField {
+                        records: Vec::new(),
+                        value: OnceCell::new(),
+                    })
+                    .records
+                    .push(bytes.slice(start..end));
+            }
+        }
+
+        Ok(DynamicMessageView {
+            desc,
+            bytes,
+            fields,
+        })
+    }
+
+    /// Gets a reference to the message type descriptor for this view.
+    pub fn descriptor(&self) -> MessageDescriptor {
+        self.desc.clone()
+    }
+
+    /// Returns `true` if the given field is present in the encoded bytes.
+    ///
+    /// Unlike [`DynamicMessage::has_field()`], this does not decode the field, so it returns `true` for fields which
+    /// are explicitly encoded with their default value.
+    pub fn has_field(&self, field_desc: &FieldDescriptor) -> bool {
+        self.fields.contains_key(&field_desc.number())
+    }
+
+    /// Returns `true` if the value of the given field has been decoded and cached.
+    pub fn is_field_decoded(&self, field_desc: &FieldDescriptor) -> bool {
+        self.fields
+            .get(&field_desc.number())
+            .is_some_and(|field| field.value.get().is_some())
+    }
+
+    /// Gets the value of the given field, decoding it if this is the first time it has been accessed.
+    ///
+    /// If the field is not present in the encoded bytes, its default value is returned.
+    pub fn get_field(&self, field_desc: &FieldDescriptor) -> Result<Cow<'_, Value>, DecodeError> {
+        match self.fields.get(&field_desc.number()) {
+            Some(field) => Ok(Cow::Borrowed(
+                field.value.get_or_try_init(|| field.decode(field_desc))?,
+            )),
+            None => Ok(Cow::Owned(field_desc.default_value())),
+        }
+    }
+
+    /// Gets the value of the field with the given name, decoding it if this is the first time it has been accessed.
+    ///
+    /// See [`get_field`][Self::get_field] for more details. Returns `None` if the message type has no field with
+    /// the given name.
+    pub fn get_field_by_name(&self, name: &str) -> Option<Result<Cow<'_, Value>, DecodeError>> {
+        self.desc
+            .get_field_by_name(name)
+            .map(|field_desc| self.get_field(&field_desc))
+    }
+
+    /// Gets the value of the field with the given number, decoding it if this is the first time it has been
+    /// accessed.
+    ///
+    /// See [`get_field`][Self::get_field] for more details. Returns `None` if the message type has no field with
+    /// the given number.
+    pub fn get_field_by_number(&self, number: u32) -> Option<Result<Cow<'_, Value>, DecodeError>> {
+        self.desc
+            .get_field(number)
+            .map(|field_desc| self.get_field(&field_desc))
+    }
+
+    /// Decodes the encoded bytes of the view into a new [`DynamicMessage`].
+    ///
+    /// This is equivalent to [`DynamicMessage::decode()`], and does not reuse any values already decoded by the view.
+    pub fn to_message(&self) -> Result<DynamicMessage, DecodeError> {
+        DynamicMessage::decode(self.desc.clone(), self.bytes.clone())
+    }
+}
+
+impl LazyField {
+    fn decode(&self, field_desc: &FieldDescriptor) -> Result<Value, DecodeError> {
+        let mut value = field_desc.default_value();
+        for record in &self.records {
+            let mut buf = record.clone();
+            let (_, wire_type) = encoding::decode_key(&mut buf)?;
+            value.merge_field(
+                field_desc,
+                wire_type,
+                &mut buf,
+                DecodeContext::default(),
+                false,
+            )?;
+        }
+        Ok(value)
+    }
+}
+
+impl fmt::Debug for DynamicMessageView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicMessageView")
+            .field("desc", &self.desc)
+            .field("fields", &self.fields.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
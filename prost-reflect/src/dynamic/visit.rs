@@ -0,0 +1,157 @@
+use crate::{DynamicMessage, FieldDescriptor, MapKey, Value};
+
+/// A visitor over the fields of a [`DynamicMessage`] and all messages nested within it.
+///
+/// Used by [`DynamicMessage::accept()`]. All methods have a default implementation which does nothing, so
+/// implementations only need to override the callbacks they are interested in.
+///
+/// Messages are walked depth-first: after a callback is invoked for a message value, the fields of that message are
+/// visited before moving on to the next field. Only fields which are set (see [`DynamicMessage::has_field`]) are
+/// visited, and extension fields are not visited.
+pub trait Visitor {
+    /// Called for a singular field whose value is not a message.
+    fn visit_scalar(&mut self, field: &FieldDescriptor, value: &Value) {
+        let _ = (field, value);
+    }
+
+    /// Called for a singular message field, before its fields are visited.
+    fn visit_message(&mut self, field: &FieldDescriptor, message: &DynamicMessage) {
+        let _ = (field, message);
+    }
+
+    /// Called for each element of a repeated field. If the element is a message, its fields are visited next.
+    fn visit_list_element(&mut self, field: &FieldDescriptor, index: usize, value: &Value) {
+        let _ = (field, index, value);
+    }
+
+    /// Called for each entry of a map field. If the value is a message, its fields are visited next.
+    fn visit_map_entry(&mut self, field: &FieldDescriptor, key: &MapKey, value: &Value) {
+        let _ = (field, key, value);
+    }
+}
+
+/// A visitor over the fields of a [`DynamicMessage`] and all messages nested within it, which may modify values.
+///
+/// Used by [`DynamicMessage::accept_mut()`]. This has the same callbacks as [`Visitor`], except that values are
+/// passed by mutable reference. If a callback modifies a message value, the fields visited next are those of the
+/// modified message.
+pub trait VisitorMut {
+    /// Called for a singular field whose value is not a message.
+    fn visit_scalar(&mut self, field: &FieldDescriptor, value: &mut Value) {
+        let _ = (field, value);
+    }
+
+    /// Called for a singular message field, before its fields are visited.
+    fn visit_message(&mut self, field: &FieldDescriptor, message: &mut DynamicMessage) {
+        let _ = (field, message);
+    }
+
+    /// Called for each element of a repeated field. If the element is a message, its fields are visited next.
+    fn visit_list_element(&mut self, field: &FieldDescriptor, index: usize, value: &mut Value) {
+        let _ = (field, index, value);
+    }
+
+    /// Called for each entry of a map field. If the value is a message, its fields are visited next.
+    fn visit_map_entry(&mut self, field: &FieldDescriptor, key: &MapKey, value: &mut Value) {
+        let _ = (field, key, value);
+    }
+}
+
+impl DynamicMessage {
+    /// Walks all fields of this message and any messages nested within it, calling the corresponding method of the
+    /// visitor for each value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, FieldDescriptor, Value, Visitor};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// struct FieldNames(Vec<String>);
+    ///
+    /// impl Visitor for FieldNames {
+    ///     fn visit_scalar(&mut self, field: &FieldDescriptor, _: &Value) {
+    ///         self.0.push(field.full_name().to_owned());
+    ///     }
+    /// }
+    ///
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let mut visitor = FieldNames(Vec::new());
+    /// dynamic_message.accept(&mut visitor);
+    /// assert_eq!(visitor.0, ["package.MyMessage.foo", "package.MyMessage.bar"]);
+    /// ```
+    pub fn accept(&self, visitor: &mut impl Visitor) {
+        for (field, value) in self.fields() {
+            if field.is_list() {
+                for (index, value) in value.as_list().unwrap().iter().enumerate() {
+                    visitor.visit_list_element(&field, index, value);
+                    if let Value::Message(message) = value {
+                        message.accept(visitor);
+                    }
+                }
+            } else if field.is_map() {
+                for (key, value) in value.as_map().unwrap() {
+                    visitor.visit_map_entry(&field, key, value);
+                    if let Value::Message(message) = value {
+                        message.accept(visitor);
+                    }
+                }
+            } else if let Value::Message(message) = value {
+                visitor.visit_message(&field, message);
+                message.accept(visitor);
+            } else {
+                visitor.visit_scalar(&field, value);
+            }
+        }
+    }
+
+    /// Walks all fields of this message and any messages nested within it, calling the corresponding method of the
+    /// visitor with a mutable reference to each value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, FieldDescriptor, Value, VisitorMut};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// struct Double;
+    ///
+    /// impl VisitorMut for Double {
+    ///     fn visit_scalar(&mut self, _: &FieldDescriptor, value: &mut Value) {
+    ///         if let Value::I32(value) = value {
+    ///             *value *= 2;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// dynamic_message.accept_mut(&mut Double);
+    /// assert_eq!(dynamic_message.encode_to_vec(), b"\x08\xac\x02\x1a\x03\x10\x84\x01");
+    /// ```
+    pub fn accept_mut(&mut self, visitor: &mut impl VisitorMut) {
+        for (field, value) in self.fields_mut() {
+            if field.is_list() {
+                for (index, value) in value.as_list_mut().unwrap().iter_mut().enumerate() {
+                    visitor.visit_list_element(&field, index, value);
+                    if let Value::Message(message) = value {
+                        message.accept_mut(visitor);
+                    }
+                }
+            } else if field.is_map() {
+                for (key, value) in value.as_map_mut().unwrap() {
+                    visitor.visit_map_entry(&field, key, value);
+                    if let Value::Message(message) = value {
+                        message.accept_mut(visitor);
+                    }
+                }
+            } else if let Value::Message(message) = value {
+                visitor.visit_message(&field, message);
+                message.accept_mut(visitor);
+            } else {
+                visitor.visit_scalar(&field, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{DynamicMessage, ReflectMessage};
+
+pub(super) const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
+pub(super) const MAX_DURATION_NANOS: u32 = 999_999_999;
+
+pub(super) const MIN_TIMESTAMP_SECONDS: i64 = -62135596800;
+pub(super) const MAX_TIMESTAMP_SECONDS: i64 = 253402300799;
+
+impl DynamicMessage {
+    /// Creates a new `google.protobuf.Timestamp` message from a [`SystemTime`].
+    ///
+    /// Returns `None` if the time is outside the range supported by `google.protobuf.Timestamp`, which is
+    /// `0001-01-01T00:00:00Z` to `9999-12-31T23:59:59.999999999Z` inclusive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::{Duration, SystemTime};
+    /// # use prost_reflect::{DynamicMessage, Value};
+    /// let time = SystemTime::UNIX_EPOCH + Duration::new(1, 500);
+    /// let message = DynamicMessage::from_system_time(time).unwrap();
+    /// assert_eq!(message.get_field_by_name("seconds").unwrap().as_ref(), &Value::I64(1));
+    /// assert_eq!(message.get_field_by_name("nanos").unwrap().as_ref(), &Value::I32(500));
+    /// assert_eq!(message.to_system_time(), Some(time));
+    /// ```
+    pub fn from_system_time(time: SystemTime) -> Option<Self> {
+        let timestamp = prost_types::Timestamp::from(time);
+        if !is_valid_timestamp(&timestamp) {
+            return None;
+        }
+
+        Some(timestamp.transcode_to_dynamic())
+    }
+
+    /// Converts a `google.protobuf.Timestamp` message to a [`SystemTime`].
+    ///
+    /// Returns `None` if this message is not a `google.protobuf.Timestamp`, or if its value is outside the
+    /// range documented for `google.protobuf.Timestamp`.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        if self.descriptor().full_name() != "google.protobuf.Timestamp" {
+            return None;
+        }
+
+        let timestamp: prost_types::Timestamp = self.transcode_to().ok()?;
+        if !is_valid_timestamp(&timestamp) {
+            return None;
+        }
+
+        SystemTime::try_from(timestamp).ok()
+    }
+
+    /// Creates a new `google.protobuf.Duration` message from a [`Duration`].
+    ///
+    /// Returns `None` if the duration is longer than the range supported by `google.protobuf.Duration`, which is
+    /// approximately 10,000 years.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use prost_reflect::{DynamicMessage, Value};
+    /// let message = DynamicMessage::from_duration(Duration::from_millis(1500)).unwrap();
+    /// assert_eq!(message.get_field_by_name("seconds").unwrap().as_ref(), &Value::I64(1));
+    /// assert_eq!(message.get_field_by_name("nanos").unwrap().as_ref(), &Value::I32(500_000_000));
+    /// assert_eq!(message.to_duration(), Some(Duration::from_millis(1500)));
+    /// ```
+    pub fn from_duration(duration: Duration) -> Option<Self> {
+        if duration.as_secs() > MAX_DURATION_SECONDS {
+            return None;
+        }
+
+        Some(
+            prost_types::Duration::try_from(duration)
+                .ok()?
+                .transcode_to_dynamic(),
+        )
+    }
+
+    /// Converts a `google.protobuf.Duration` message to a [`Duration`].
+    ///
+    /// Returns `None` if this message is not a `google.protobuf.Duration`, if its value is outside the range
+    /// documented for `google.protobuf.Duration`, or if it is negative, since [`Duration`] cannot represent negative
+    /// values.
+    pub fn to_duration(&self) -> Option<Duration> {
+        if self.descriptor().full_name() != "google.protobuf.Duration" {
+            return None;
+        }
+
+        let duration: prost_types::Duration = self.transcode_to().ok()?;
+        if duration.seconds < 0
+            || duration.nanos < 0
+            || duration.seconds.unsigned_abs() > MAX_DURATION_SECONDS
+            || duration.nanos.unsigned_abs() > MAX_DURATION_NANOS
+        {
+            return None;
+        }
+
+        Some(Duration::new(
+            duration.seconds.unsigned_abs(),
+            duration.nanos.unsigned_abs(),
+        ))
+    }
+
+    /// Creates a new `google.protobuf.FieldMask` message from a list of field paths.
+    ///
+    /// Each path is a sequence of field names separated by `.`, using the original (snake_case) names of the fields,
+    /// as stored in the `paths` field of the message. The paths are not validated against any message type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, Value};
+    /// let message = DynamicMessage::from_field_mask_paths(["foo_bar", "baz.qux"]);
+    /// assert_eq!(message.field_mask_paths(), Some(vec!["foo_bar".to_owned(), "baz.qux".to_owned()]));
+    /// ```
+    pub fn from_field_mask_paths<I>(paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        prost_types::FieldMask {
+            paths: paths.into_iter().map(Into::into).collect(),
+        }
+        .transcode_to_dynamic()
+    }
+
+    /// Gets the field paths of a `google.protobuf.FieldMask` message.
+    ///
+    /// Returns `None` if this message is not a `google.protobuf.FieldMask`. The paths are returned as stored in the
+    /// message, using the original field names. Note that in the JSON mapping, a field mask is instead represented as
+    /// a single string of comma-separated paths, with each field name converted to lowerCamelCase.
+    pub fn field_mask_paths(&self) -> Option<Vec<String>> {
+        if self.descriptor().full_name() != "google.protobuf.FieldMask" {
+            return None;
+        }
+
+        let field_mask: prost_types::FieldMask = self.transcode_to().ok()?;
+        Some(field_mask.paths)
+    }
+}
+
+fn is_valid_timestamp(timestamp: &prost_types::Timestamp) -> bool {
+    (MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&timestamp.seconds)
+        && (0..=MAX_DURATION_NANOS as i32).contains(&timestamp.nanos)
+}
@@ -24,11 +24,14 @@ pub use self::descriptor::{
     ExtensionDescriptor, FieldDescriptor, FileDescriptor, Kind, MessageDescriptor,
     MethodDescriptor, OneofDescriptor, ServiceDescriptor, Syntax,
 };
-pub use self::dynamic::{DynamicMessage, MapKey, SetFieldError, UnknownField, Value};
+pub use self::dynamic::{
+    DecodeOptions, DynamicMessage, DynamicMessageView, EncodeOptions, FieldOrExtension, MapKey,
+    SetFieldError, TryFromValueError, UnknownField, Value, Visitor, VisitorMut,
+};
 pub use self::reflect::ReflectMessage;
 
 #[cfg(feature = "serde")]
-pub use self::dynamic::{DeserializeOptions, SerializeOptions};
+pub use self::dynamic::{BytesEncoding, DeserializeOptions, FieldAliasError, SerializeOptions};
 
 #[cfg(feature = "derive")]
 #[cfg_attr(docsrs, doc(cfg(feature = "derive")))]